@@ -0,0 +1,45 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lf::bench::{collect_matching_files, heavy_excludes, process_file, synth_text, synth_tree, transform_options};
+use lf::tokenizer::{make_tokenizer, ApproxTokenizer, Tokenizer, TokenizerKind};
+use lf::Options;
+
+const MB: usize = 1024 * 1024;
+
+fn walk_and_match(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collect_matching_files");
+    let patterns = heavy_excludes();
+    for files in [500, 5_000] {
+        let dir = tempfile::tempdir().unwrap();
+        synth_tree(dir.path(), files, 512).unwrap();
+        group.throughput(Throughput::Elements(files as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(files), &files, |b, _| b.iter(|| collect_matching_files(dir.path(), &patterns).unwrap()));
+    }
+    group.finish();
+}
+
+fn process(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big.rs");
+    std::fs::write(&path, synth_text(MB)).unwrap();
+    let mut group = c.benchmark_group("process_file");
+    group.throughput(Throughput::Bytes(MB as u64));
+    for (name, opts) in [("plain", Options::new(["**/*"])), ("transforms", transform_options())] {
+        group.bench_function(name, |b| b.iter(|| process_file(&path, &opts, &ApproxTokenizer).unwrap()));
+    }
+    group.finish();
+}
+
+fn tokenize(c: &mut Criterion) {
+    let text = synth_text(MB);
+    let mut group = c.benchmark_group("tokenize");
+    group.throughput(Throughput::Bytes(MB as u64));
+    group.bench_function("approx", |b| b.iter(|| ApproxTokenizer.count_tokens(&text)));
+    if let Ok(bpe) = make_tokenizer(Some(TokenizerKind::O200k)) {
+        bpe.count_tokens("warm up");
+        group.bench_function("o200k_base", |b| b.iter(|| bpe.count_tokens(&text)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, walk_and_match, process, tokenize);
+criterion_main!(benches);
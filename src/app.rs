@@ -1,15 +1,35 @@
-use crate::binary::{get_binary_file_info, is_binary_file};
+use crate::archive::{archive_kind, read_entries, ArchiveKind};
+use crate::attributes::GitAttributes;
+use crate::binary::{binary_file_info, blob_info, embed_binary, get_binary_file_info, sha256_file, sniff_binary, with_hash, BinaryOverrides};
+use crate::chunk::{check_overlap, chunk_file};
 use crate::clipboard::ClipboardSink;
-use crate::fs::{FileReader, WalkerFactory};
-use crate::patterns::{build_glob_sets, path_matches};
-use crate::tokenizer::Tokenizer;
+use crate::format::{civil_datetime, file_id, format_entry, format_tree, formatter_for, group_key, json_array, render_file_index, render_toc, section_footer, section_header, Entry, Format, Formatter, FormatterFactory, HeaderFields, HeaderMeta, PathDisplay, PathStyle, RunMeta, Templates};
+use crate::format::html::{render_fragment as render_html_fragment, HtmlFile};
+use crate::error::{io_error, LfError};
+use crate::extract::{document_kind, extract_document};
+use crate::generated::classify;
+use crate::git::{changed_files, read_blob, staged_files, tracked_files, tree_blobs};
+use crate::limits::{fit_selection, fit_tokens, fit_tokens_per_dir, render_dir_omitted, render_omitted, Cap, Capped, DirCapped};
+use crate::fs::{case_collisions, normalize_lines, same_file, AtomicOutput, Candidate, FileReader, WalkerFactory};
+use crate::snapshot::{diff_snapshots, read_snapshot, render_diff, SnapshotFile};
+use crate::state::{fingerprint, state_path, FileStamp, State};
+use crate::report::{describe_difference, dry_run, ext_stats, lang_stats, render_lang_stats, render_dry_run, render_ext_stats, render_preview, render_skipped, render_timings, SkipCategory, SLOWEST_FILES};
+use crate::units::{format_size, thousands, SizeUnits};
+use crate::patterns::{explicit_path, has_globs, MatchResult, PatternOptions, PatternSet, Rejection};
+use crate::picker::{pick, PickItem};
+use crate::tokenizer::{ApproxTokenizer, Tokenizer, NO_TOKENIZER};
+use crate::transform::{find_todos, flatten_notebook, head_lines, number_lines, pipeline, sample_data, strip_ansi, truncation_marker, CustomTransform, Replacement, Stage, Transform, DEFAULT_TODO_TAGS};
+use crate::tree::render_tree;
 use anyhow::{Context, Result};
-use globset::GlobSet;
 use rayon::prelude::*;
-use std::io::{BufWriter, Write};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 pub struct Deps<'a> {
     pub walker: &'a dyn WalkerFactory,
@@ -18,102 +38,1211 @@ pub struct Deps<'a> {
     pub clipboard: Option<&'a dyn ClipboardSink>,
 }
 
+/// Content piped in for a `-` pattern, emitted as one more file called `name`.
+#[derive(Debug, Clone)]
+pub struct StdinFile {
+    pub name: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub patterns: Vec<String>,
+    pub dirs: Vec<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub append: bool,
+    pub tee: bool,
+    pub no_clipboard: bool,
+    pub clipboard_limit: Option<u64>,
+    pub clipboard_limit_strict: bool,
+    pub mask_java_imports: bool,
+    pub no_gitignore: bool,
+    pub git_only: bool,
+    pub changed_since: Option<String>,
+    pub staged: bool,
+    pub at_rev: Option<String>,
+    pub expand_archives: bool,
+    pub interactive: bool,
+    pub count_only: bool,
+    pub stats_by_ext: bool,
+    pub lang_stats: bool,
+    pub tree: bool,
+    pub tree_only: bool,
+    pub format: Format,
+    pub toc: bool,
+    pub number_files: bool,
+    pub header_meta: Option<HeaderFields>,
+    pub templates: Templates,
+    pub head_lines: Option<usize>,
+    pub line_numbers: bool,
+    pub path_style: PathStyle,
+    pub cancel: Option<Arc<AtomicBool>>,
+    pub explain: Vec<PathBuf>,
+    pub explain_all: bool,
+    pub include_generated: bool,
+    pub binary_overrides: BinaryOverrides,
+    pub hash_binaries: bool,
+    pub manifest: Option<PathBuf>,
+    pub embed_binaries_under: Option<u64>,
+    pub skip_binary: bool,
+    pub binary_only: bool,
+    pub extract_documents: bool,
+    pub raw_notebooks: bool,
+    pub outline: bool,
+    pub todos: bool,
+    pub todo_tags: Vec<String>,
+    pub strip_rust_tests: bool,
+    pub strip_license_headers: bool,
+    pub replacements: Vec<Replacement>,
+    pub max_line_length: Option<usize>,
+    pub expand_tabs: Option<usize>,
+    pub expand_all_tabs: bool,
+    pub dedent: bool,
+    pub sample_data: Option<usize>,
+    pub strip_ansi: bool,
+    pub strip_frontmatter: bool,
+    pub prefix: Vec<String>,
+    pub suffix: Vec<String>,
+    pub chunk_tokens: Option<usize>,
+    pub chunk_overlap: usize,
+    pub dedupe_content: bool,
+    pub recent: Option<usize>,
+    pub skip_empty: bool,
+    pub dry_run: bool,
+    pub check: bool,
+    pub incremental: bool,
+    pub diff_against: Option<PathBuf>,
+    pub fail_on_change: bool,
+    pub max_files: Option<usize>,
+    pub max_total_bytes: Option<u64>,
+    pub max_tokens: Option<usize>,
+    pub max_tokens_per_dir: Option<usize>,
+    pub budget_depth: Option<usize>,
+    pub strict_limits: bool,
+    pub group_by_dir: bool,
+    pub group_depth: Option<usize>,
+    pub timings: bool,
+    pub warn_tokens_per_file: Option<usize>,
+    pub skip_tokens_per_file: Option<usize>,
+    pub si_units: bool,
+    pub show_skipped: bool,
+    pub preview: Option<usize>,
+    pub fallback_stdout: bool,
+    pub clipboard_html: bool,
+    pub pattern_order: bool,
+    pub strict_patterns: bool,
+    pub region_markers: Option<(String, String)>,
+    pub stdin: Option<StdinFile>,
+    pub formatter: Option<FormatterFactory>,
+    pub transforms: Vec<CustomTransform>,
+}
+
+impl Options {
+    fn cancelled(&self) -> bool { self.cancel.as_ref().is_some_and(|c| c.load(Ordering::Relaxed)) }
+
+    pub fn size_units(&self) -> SizeUnits { if self.si_units { SizeUnits::Si } else { SizeUnits::Binary } }
+}
+
+macro_rules! setters {
+    ($($name:ident: $ty:ty),* $(,)?) => {
+        $(pub fn $name(mut self, v: $ty) -> Self { self.$name = v; self })*
+    };
+    (some $($name:ident: $ty:ty),* $(,)?) => {
+        $(pub fn $name(mut self, v: $ty) -> Self { self.$name = Some(v); self })*
+    };
+    (into $($name:ident: $ty:ty),* $(,)?) => {
+        $(pub fn $name(mut self, v: impl Into<$ty>) -> Self { self.$name = Some(v.into()); self })*
+    };
+}
+
+impl Options {
+    pub fn new<S: Into<String>>(patterns: impl IntoIterator<Item = S>) -> Self {
+        Options { patterns: patterns.into_iter().map(Into::into).collect(), ..Options::default() }
+    }
+
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self { self.dirs.push(dir.into()); self }
+
+    /// Renders with a formatter built by `make` for each run instead of the one `format` picks.
+    pub fn formatter(mut self, make: impl Fn() -> Box<dyn Formatter> + Send + Sync + 'static) -> Self { self.formatter = Some(FormatterFactory(Arc::new(make))); self }
+
+    /// Adds `content` (read from stdin) as a file named `name`, listed before the matched files.
+    pub fn stdin(mut self, name: impl Into<String>, content: impl Into<String>) -> Self { self.stdin = Some(StdinFile { name: name.into(), content: content.into() }); self }
+
+    /// Adds a custom content transform, run after the built-in ones of `stage`.
+    pub fn transform(mut self, stage: Stage, transform: impl Transform + 'static) -> Self { self.transforms.push(CustomTransform { stage, transform: Arc::new(transform) }); self }
+
+    setters!(into output: PathBuf, changed_since: String, at_rev: String, manifest: PathBuf, diff_against: PathBuf);
+    setters!(some cancel: Arc<AtomicBool>, clipboard_limit: u64, header_meta: HeaderFields, head_lines: usize, embed_binaries_under: u64, max_line_length: usize, expand_tabs: usize, sample_data: usize, chunk_tokens: usize, recent: usize, max_files: usize, max_total_bytes: u64, max_tokens: usize, max_tokens_per_dir: usize, budget_depth: usize, group_depth: usize, warn_tokens_per_file: usize, skip_tokens_per_file: usize, preview: usize, region_markers: (String, String));
+    setters!(
+        append: bool, tee: bool, no_clipboard: bool, clipboard_limit_strict: bool, mask_java_imports: bool, no_gitignore: bool,
+        git_only: bool, staged: bool, expand_archives: bool, interactive: bool, count_only: bool, stats_by_ext: bool, lang_stats: bool, tree: bool, tree_only: bool,
+        format: Format, toc: bool, number_files: bool, templates: Templates, line_numbers: bool, path_style: PathStyle,
+        binary_overrides: BinaryOverrides, hash_binaries: bool, skip_binary: bool, binary_only: bool,
+        extract_documents: bool, raw_notebooks: bool, outline: bool, todos: bool, todo_tags: Vec<String>,
+        strip_rust_tests: bool, strip_license_headers: bool, replacements: Vec<Replacement>, expand_all_tabs: bool, dedent: bool,
+        strip_ansi: bool, strip_frontmatter: bool, prefix: Vec<String>, suffix: Vec<String>, chunk_overlap: usize, dedupe_content: bool, skip_empty: bool, dry_run: bool, check: bool, incremental: bool, fail_on_change: bool, strict_limits: bool, group_by_dir: bool, timings: bool, si_units: bool, show_skipped: bool, fallback_stdout: bool, clipboard_html: bool, pattern_order: bool, strict_patterns: bool,
+    );
+}
+
+const APPEND_SEPARATOR: &str = "========\n\n";
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct Stats {
+    pub files: usize,
     pub lines: usize,
     pub tokens: usize,
+    pub bytes: u64,
+    pub clipboard_fallback: bool,
+    pub summarized: usize,
+    pub skipped_binary: usize,
+    pub skipped_binary_bytes: u64,
+    pub skipped_empty: usize,
+    pub changed_during_run: usize,
+    pub duplicates: usize,
+    pub duplicate_tokens_saved: usize,
+    pub omitted_by_limits: usize,
+    pub skipped_token_limit: usize,
+    pub skipped_token_limit_tokens: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todos: Option<usize>,
+    pub tokenizer: String,
+    pub timing: Timing,
+    pub entries: Vec<FileStats>,
 }
 
-fn java_mask(content: &str) -> String {
-    let mut out = String::new();
-    let mut added = false;
-    for line in content.lines() {
-        if line.trim_start().starts_with("import ") {
-            if !added { out.push_str("import ...\n"); added = true; }
-        } else {
-            out.push_str(line);
-            out.push('\n');
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Timing {
+    pub walk_ms: u64,
+    pub process_ms: u64,
+    pub total_ms: u64,
+    /// Per-file phases summed over all files (so over all worker threads); only filled in with `--timings`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phases: Option<FileMetrics>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct FileMetrics {
+    pub read_us: u64,
+    pub transform_us: u64,
+    pub tokenize_us: u64,
+}
+
+impl FileMetrics {
+    pub fn total_us(&self) -> u64 { self.read_us + self.transform_us + self.tokenize_us }
+
+    fn add(&mut self, other: &FileMetrics) {
+        self.read_us += other.read_us;
+        self.transform_us += other.transform_us;
+        self.tokenize_us += other.tokenize_us;
+    }
+}
+
+fn lap(clock: &mut Instant) -> u64 {
+    let now = Instant::now();
+    let us = (now - *clock).as_micros() as u64;
+    *clock = now;
+    us
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FileStats {
+    pub path: String,
+    pub lines: usize,
+    pub tokens: usize,
+    pub bytes: u64,
+    pub binary: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub omitted: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<FileMetrics>,
+}
+
+impl Stats {
+    fn record(&mut self, f: &FileEntry, bytes: u64, metrics: Option<FileMetrics>) {
+        self.files += 1;
+        self.lines += f.lines;
+        self.tokens += f.tokens;
+        self.bytes += bytes;
+        if f.omitted.is_some() { self.summarized += 1; }
+        if f.note.as_deref() == Some(CHANGED_DURING_RUN) { self.changed_during_run += 1; }
+        if let Some(tokens) = f.skipped_tokens {
+            self.skipped_token_limit += 1;
+            self.skipped_token_limit_tokens += tokens;
         }
+        self.entries.push(FileStats { path: f.display.clone(), lines: f.lines, tokens: f.tokens, bytes, binary: f.is_binary(), omitted: f.omitted.clone(), metrics });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    Text,
+    Binary,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileEntry {
+    pub path: PathBuf,
+    pub display: String,
+    pub content: String,
+    pub lines: usize,
+    pub tokens: usize,
+    pub kind: FileKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub omitted: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// The real token count of a file `--skip-tokens-per-file` replaced with a placeholder.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped_tokens: Option<usize>,
+}
+
+impl FileEntry {
+    pub fn is_binary(&self) -> bool { self.kind == FileKind::Binary }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Aggregation {
+    pub files: Vec<FileEntry>,
+    #[serde(skip)]
+    pub candidates: Vec<Candidate>,
+    #[serde(skip)]
+    pub state: Option<State>,
+    #[serde(skip)]
+    pub unchanged: Option<State>,
+    #[serde(skip)]
+    pub capped: Option<Capped>,
+    #[serde(skip)]
+    pub dir_capped: Vec<DirCapped>,
+    #[serde(skip)]
+    pub metrics: HashMap<PathBuf, FileMetrics>,
+    #[serde(skip)]
+    pub skipped: Vec<(SkipCategory, String)>,
+    pub matched: Vec<String>,
+    pub lines: usize,
+    pub tokens: usize,
+    pub skipped_binary: usize,
+    pub skipped_binary_bytes: u64,
+    pub skipped_empty: usize,
+    pub tokenizer: String,
+    pub timing: Timing,
+}
+
+struct FileCtx<'a> {
+    shown: &'a PathDisplay,
+    reader: &'a dyn FileReader,
+    tokenizer: &'a dyn Tokenizer,
+    opts: &'a Options,
+    generated: &'a HashSet<PathBuf>,
+    transforms: &'a [Box<dyn Transform>],
+    sources: &'a HashMap<PathBuf, Source>,
+}
+
+/// Where a candidate that isn't a file on disk gets its content from.
+enum Source {
+    /// A blob of the `--at-rev` revision: the repository directory to run git in and the blob id.
+    Blob(PathBuf, String),
+    /// An entry `--expand-archives` read out of an archive.
+    Entry(Vec<u8>),
+}
+
+/// Reads of a file whose size or mtime moved during the read, before it's emitted with `CHANGED_DURING_RUN`.
+const MAX_READS: usize = 3;
+pub const CHANGED_DURING_RUN: &str = "warning: file changed during aggregation";
+
+// Reads `c` until its size and mtime are the same after the read as they were before it (at walk time for
+// the first read). The bool is false when the file was still changing after `MAX_READS` reads.
+fn read_settled(c: &Candidate, ctx: &FileCtx) -> Result<((String, usize), bool)> {
+    let path = c.path.as_path();
+    let stat = || std::fs::metadata(path).ok().map(|md| (md.len(), md.modified().ok()));
+    let mut before = match &c.metadata { Some(md) => Some((md.len(), md.modified().ok())), None => stat() };
+    let mut attempt = 1;
+    loop {
+        let read = ctx.reader.read_candidate(c, ctx.opts.binary_overrides.forces_text(path)).map_err(|e| io_error(e, read_error(path)))?;
+        let after = stat();
+        if after == before || attempt == MAX_READS { return Ok((read, after == before)); }
+        tracing::info!(path = %path.display(), attempt, "changed while being read, reading again");
+        before = after;
+        attempt += 1;
     }
-    if added { out } else { content.to_string() }
 }
 
-fn process_file(path: &Path, reader: &dyn FileReader, tokenizer: &dyn Tokenizer, mask_java: bool) -> Result<(String, usize, usize)> {
-    if is_binary_file(path) {
-        let info = get_binary_file_info(path)?;
+fn process_file(c: &Candidate, ctx: &FileCtx, m: &mut FileMetrics) -> Result<Option<FileEntry>> {
+    let (path, opts, tokenizer) = (c.path.as_path(), ctx.opts, ctx.tokenizer);
+    let mut clock = Instant::now();
+    let stdin = opts.stdin.as_ref().filter(|s| Path::new(&s.name) == path);
+    let source = ctx.sources.get(path);
+    let stored = match source {
+        Some(Source::Blob(repo, oid)) => Some(Cow::Owned(read_blob(repo, oid).with_context(|| format!("Failed to read {} at {}", path.display(), opts.at_rev.as_deref().unwrap_or_default()))?)),
+        Some(Source::Entry(bytes)) => Some(Cow::Borrowed(bytes.as_slice())),
+        None => None,
+    };
+    let display = stdin.map_or_else(|| ctx.shown.show(path), |s| s.name.clone());
+    let display = match (source, &opts.at_rev) { (Some(Source::Blob(..)), Some(rev)) => format!("{} @ {}", display, rev), _ => display };
+    let extracted = document_kind(path).filter(|_| opts.extract_documents && stdin.is_none()).and_then(|kind| match extract_document(path, kind) {
+        Ok(text) => Some((text, kind)),
+        Err(e) => { eprintln!("Warning: could not extract text from {}: {:#}", display, e); None }
+    });
+    let note = extracted.as_ref().map(|(_, kind)| format!("extracted from {}", kind));
+    // A blob or archive entry has no file to look inside, so besides the extension it's binary when its bytes look it.
+    let binary = match &stored {
+        Some(bytes) => opts.binary_overrides.is_binary(path) || !opts.binary_overrides.forces_text(path) && sniff_binary(bytes),
+        None => stdin.is_none() && extracted.is_none() && opts.binary_overrides.is_binary(path),
+    };
+    if binary {
+        let embed = opts.embed_binaries_under.is_some_and(|limit| c.metadata.is_some() && c.size() <= limit);
+        let units = opts.size_units();
+        let info = if let Some(bytes) = &stored { Ok(blob_info(path, bytes.len() as u64, units)) }
+            else if embed { embed_binary(path, &display, units) }
+            else if let Some(md) = &c.metadata { binary_file_info(path, md.len(), units) }
+            else { get_binary_file_info(path, units) };
+        let mut info = info.map_err(|e| io_error(e, read_error(path)))?;
+        if opts.hash_binaries { info = with_hash(&info, &sha256_file(path).map_err(|e| io_error(e, read_error(path)))?); }
+        m.read_us = lap(&mut clock);
         let tokens = tokenizer.count_tokens(&info);
-        return Ok((info, 0, tokens));
+        m.tokenize_us = lap(&mut clock);
+        return Ok(Some(FileEntry { path: path.to_path_buf(), display, content: info, lines: 0, tokens, kind: FileKind::Binary, omitted: None, note: None, skipped_tokens: None }));
     }
-    let (mut content, lines) = reader.read_to_string(path)?;
-    if mask_java {
-        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            if ext.eq_ignore_ascii_case("java") { content = java_mask(&content); }
+    let mut changed = false;
+    let (content, lines) = match (stdin, extracted, stored) {
+        (Some(s), _, _) => normalize_lines(&s.content),
+        (None, Some((text, _)), _) => { let lines = text.lines().count(); (text, lines) }
+        (None, None, Some(bytes)) => normalize_lines(&String::from_utf8_lossy(&bytes)),
+        (None, None, None) => {
+            let (read, settled) = read_settled(c, ctx)?;
+            if !settled {
+                eprintln!("Warning: {} kept changing while it was read; its content may mix old and new versions", display);
+                changed = true;
+            }
+            read
         }
+    };
+    let notebook = !opts.raw_notebooks && path.extension().is_some_and(|e| e.eq_ignore_ascii_case("ipynb"));
+    let (mut content, mut lines) = match notebook.then(|| flatten_notebook(&content)).flatten() {
+        Some(flat) => { let lines = flat.lines().count(); (flat, lines) }
+        None => (content, lines),
+    };
+    m.read_us = lap(&mut clock);
+    if opts.strip_ansi { content = strip_ansi(&content); }
+    let sampled = opts.sample_data.and_then(|n| sample_data(path, &content, n));
+    let summarize = note.is_none() && sampled.is_none();
+    if let Some(s) = sampled {
+        content = s;
+        lines = content.lines().count();
+    }
+    if ctx.generated.contains(path) {
+        let content = format!("[Generated file omitted: {}, {} lines]\n", display, thousands(lines));
+        m.transform_us = lap(&mut clock);
+        let tokens = tokenizer.count_tokens(&content);
+        m.tokenize_us = lap(&mut clock);
+        return Ok(Some(FileEntry { path: path.to_path_buf(), display, content, lines, tokens, kind: FileKind::Text, omitted: Some("generated".to_string()), note: None, skipped_tokens: None }));
     }
+    if !opts.include_generated && summarize && let Some(reason) = classify(path, &content, lines) {
+        let content = format!("[Minified/generated file: {} — {}, {} lines]\n", display, format_size(content.len() as u64, opts.size_units()), thousands(lines));
+        m.transform_us = lap(&mut clock);
+        let tokens = tokenizer.count_tokens(&content);
+        m.tokenize_us = lap(&mut clock);
+        return Ok(Some(FileEntry { path: path.to_path_buf(), display, content, lines, tokens, kind: FileKind::Text, omitted: Some(reason.to_string()), note: None, skipped_tokens: None }));
+    }
+    for t in ctx.transforms {
+        let (out, warning) = t.apply_or_warn(path, content);
+        if let Some(w) = warning { eprintln!("Warning: {}:{}", display, w); }
+        content = out;
+        if t.recounts_lines() { lines = content.lines().count(); }
+    }
+    if opts.skip_empty && content.trim().is_empty() { return Ok(None); }
+    let mut omitted = 0;
+    if let Some(n) = opts.head_lines { (content, omitted) = head_lines(&content, n); }
+    if opts.line_numbers { content = number_lines(&content); }
+    if omitted > 0 { content.push_str(&truncation_marker(omitted)); }
+    m.transform_us = lap(&mut clock);
     let tokens = tokenizer.count_tokens(&content);
-    Ok((content, lines, tokens))
+    m.tokenize_us = lap(&mut clock);
+    let note = if changed { Some(CHANGED_DURING_RUN.to_string()) } else { note };
+    Ok(Some(FileEntry { path: path.to_path_buf(), display, content, lines, tokens, kind: FileKind::Text, omitted: None, note, skipped_tokens: None }))
+}
+
+// Swaps a file over `--skip-tokens-per-file` for a placeholder, keeping its real count for the summary.
+fn over_token_limit(f: FileEntry, ctx: &FileCtx) -> FileEntry {
+    match ctx.opts.skip_tokens_per_file {
+        Some(max) if f.tokens > max => {
+            let content = format!("[Skipped: {} tokens, exceeds per-file limit]\n", thousands(f.tokens));
+            FileEntry { tokens: ctx.tokenizer.count_tokens(&content), content, omitted: Some("token-limit".to_string()), skipped_tokens: Some(f.tokens), ..f }
+        }
+        _ => f,
+    }
 }
 
-fn format_entry(path: &Path, content: &str) -> String {
-    let p = path.to_string_lossy().replace('\\', "/");
-    let disp = p.strip_prefix("./").unwrap_or(&p);
-    let mut s = String::new();
-    s.push_str(disp);
-    s.push('\n');
-    s.push_str(content);
-    s.push_str("\n\n");
-    s
+#[cfg(any(feature = "bench", test))]
+pub(crate) fn process_one(path: &Path, opts: &Options, tokenizer: &dyn Tokenizer) -> Result<Option<FileEntry>> {
+    let shown = PathDisplay::new(opts.path_style, Path::new(""));
+    let ctx = FileCtx { shown: &shown, reader: &crate::fs::StdFileReader, tokenizer, opts, generated: &HashSet::new(), transforms: &pipeline(opts), sources: &HashMap::new() };
+    process_file(&Candidate { path: path.to_path_buf(), metadata: std::fs::metadata(path).ok(), known_size: None }, &ctx, &mut FileMetrics::default())
 }
 
-fn collect_matching_files(walker: &dyn WalkerFactory, include: &GlobSet, hidden_inc: &GlobSet, exclude: &GlobSet, no_gitignore: bool) -> Vec<PathBuf> {
+fn todo_report(results: &[FileEntry], opts: &Options, tokenizer: &dyn Tokenizer, stats: &mut Stats) -> Vec<String> {
+    let tags: Vec<&str> = if opts.todo_tags.is_empty() { DEFAULT_TODO_TAGS.to_vec() } else { opts.todo_tags.iter().map(String::as_str).collect() };
+    let mut rendered = Vec::new();
+    let mut findings = 0;
+    for r in results.iter().filter(|r| !r.is_binary()) {
+        let hits = find_todos(&r.content, &tags);
+        let mut group: String = hits.iter().map(|(n, line)| format!("{}:{}: {}\n", r.display, n, line)).collect();
+        if !group.is_empty() && !rendered.is_empty() { group.insert(0, '\n'); }
+        findings += hits.len();
+        let entry = FileEntry { path: r.path.clone(), display: r.display.clone(), content: String::new(), lines: r.lines, tokens: tokenizer.count_tokens(&group), kind: r.kind, omitted: None, note: None, skipped_tokens: None };
+        stats.record(&entry, group.len() as u64, None);
+        if !group.is_empty() { rendered.push(group); }
+    }
+    stats.todos = Some(findings);
+    rendered
+}
+
+fn header_meta(r: &FileEntry, md: Option<&std::fs::Metadata>, fields: HeaderFields) -> HeaderMeta {
+    HeaderMeta {
+        lines: (fields.lines && !r.is_binary()).then_some(r.lines),
+        tokens: fields.tokens.then_some(r.tokens),
+        size: md.filter(|_| fields.size).map(|m| m.len()),
+        modified: md.filter(|_| fields.mtime).and_then(|m| m.modified().ok()),
+    }
+}
+
+pub(crate) fn collect_matching_files(walker: &dyn WalkerFactory, root: &Path, sets: &PatternSet, no_gitignore: bool, selected: Option<&HashSet<PathBuf>>, opts: &Options, mut rejected: Option<&mut Vec<(PathBuf, Rejection)>>) -> Result<Vec<Candidate>> {
+    let in_git_dir = |rel: &Path| rel.components().any(|c| c.as_os_str() == ".git");
     let mut files = Vec::new();
-    for e in walker.build(no_gitignore).into_iter().filter_map(|e| e.ok()) {
+    let mut walked = HashSet::new();
+    for e in walker.build(root, no_gitignore).filter_map(|e| e.inspect_err(|err| tracing::warn!(error = %err, "skipping unreadable entry")).ok()) {
+        if opts.cancelled() { return Err(LfError::Cancelled.into()); }
         if e.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
-            let p = e.into_path();
-            if path_matches(&p, include, hidden_inc, exclude) { files.push(p); }
+            let rel = e.path().strip_prefix(root).unwrap_or(e.path());
+            if selected.is_some_and(|s| !s.contains(rel)) {
+                tracing::info!(path = %rel.display(), "skipped: not in the git selection");
+                continue;
+            }
+            let result = sets.matches(rel);
+            tracing::debug!(path = %rel.display(), set = result.set(), included = result.is_match(), "match decision");
+            if let Some(r) = rejected.as_deref_mut() && !in_git_dir(rel) {
+                walked.insert(rel.to_path_buf());
+                if let Some(why) = result.rejection() { r.push((e.path().to_path_buf(), why)); }
+            }
+            if result.is_match() { files.push(Candidate { metadata: walker.metadata(&e), path: e.into_path(), known_size: None }); }
         }
     }
-    files
+    // The walker drops gitignored files before matching; walk again without ignore rules to find the ones the patterns wanted.
+    if let Some(r) = rejected && !no_gitignore {
+        for e in walker.build(root, true).filter_map(Result::ok).filter(|e| e.file_type().is_some_and(|t| t.is_file())) {
+            let rel = e.path().strip_prefix(root).unwrap_or(e.path());
+            if !walked.contains(rel) && !in_git_dir(rel) && matches!(sets.matches(rel).rejection(), None | Some(Rejection::Gitignore)) { r.push((e.path().to_path_buf(), Rejection::Gitignore)); }
+        }
+    }
+    Ok(files)
 }
 
-pub fn run_app(deps: Deps, patterns: &[String], output_path: Option<&Path>, no_clipboard: bool, mask_java_imports: bool, no_gitignore: bool) -> Result<Stats> {
-    let (include_set, hidden_include_set, exclude_set) = build_glob_sets(patterns, !no_gitignore)?;
-    let files = collect_matching_files(deps.walker, &include_set, &hidden_include_set, &exclude_set, no_gitignore);
-    if files.is_empty() {
-        println!("No files found matching the patterns.");
-        return Ok(Stats { lines: 0, tokens: 0 });
+// `collect_matching_files` over the tree of `rev` instead of the walked directory. Every blob in it is tracked,
+// so gitignore rules don't apply; the blob id to read each candidate from goes into `sources`.
+fn revision_files(root: &Path, rev: &str, sets: &PatternSet, sources: &mut HashMap<PathBuf, Source>, mut rejected: Option<&mut Vec<(PathBuf, Rejection)>>) -> Result<Vec<Candidate>> {
+    let mut files = Vec::new();
+    for (rel, blob) in tree_blobs(root, rev)? {
+        let result = sets.matches(&rel);
+        tracing::debug!(path = %rel.display(), set = result.set(), included = result.is_match(), "match decision");
+        let path = root.join(&rel);
+        if let Some(r) = rejected.as_deref_mut() && let Some(why) = result.rejection() { r.push((path.clone(), why)); }
+        if result.is_match() {
+            sources.insert(path.clone(), Source::Blob(root.to_path_buf(), blob.oid));
+            files.push(Candidate { path, metadata: None, known_size: Some(blob.size) });
+        }
     }
-    let total_lines = Arc::new(AtomicUsize::new(0));
-    let total_tokens = Arc::new(AtomicUsize::new(0));
-    let use_clipboard = !no_clipboard && output_path.is_none();
-    let content_buffer = if use_clipboard { Some(Arc::new(Mutex::new(String::new()))) } else { None };
-    let mut output_writer: Option<Box<dyn Write + Send>> = if let Some(p) = output_path {
-        let f = std::fs::File::create(p).with_context(|| format!("Failed to create output file: {}", p.display()))?;
-        Some(Box::new(BufWriter::new(f)))
-    } else if no_clipboard { Some(Box::new(std::io::stdout())) } else { None };
-    let tokenizer = deps.tokenizer.clone();
-    let reader = deps.reader;
-    let results: Result<Vec<(PathBuf, String, usize, usize)>> = files.par_iter().map(|p| {
-        let (content, lines, tokens) = process_file(p, reader, tokenizer.as_ref(), mask_java_imports)?;
-        Ok((p.clone(), content, lines, tokens))
+    Ok(files)
+}
+
+// The include patterns `--expand-archives` opens: each one's index and the archive file it names, under the first root that has it.
+fn archive_patterns(opts: &Options, roots: &[&Path]) -> Vec<(usize, PathBuf, ArchiveKind)> {
+    opts.patterns.iter().enumerate().filter(|(_, p)| !p.starts_with('~')).filter_map(|(i, p)| {
+        let kind = archive_kind(Path::new(p))?;
+        let path = match explicit_path(p) { Some(path) => path, None => roots.iter().map(|r| r.join(p)).find(|a| a.is_file())? };
+        path.is_file().then_some((i, path, kind))
+    }).collect()
+}
+
+// Reads the entries of `archives` the other patterns select into `sources`, as candidates named `ARCHIVE!ENTRY`.
+// Returns each candidate with the indexes of the include patterns that matched it, its archive's last.
+fn archive_files(archives: &[(usize, PathBuf, ArchiveKind)], opts: &Options, sources: &mut HashMap<PathBuf, Source>, mut rejected: Option<&mut Vec<(PathBuf, Rejection)>>) -> Result<Vec<(Candidate, Vec<usize>)>> {
+    let (mut index, mut patterns): (Vec<usize>, Vec<String>) = opts.patterns.iter().enumerate().filter(|(i, _)| !archives.iter().any(|a| a.0 == *i)).map(|(i, p)| (i, p.clone())).unzip();
+    if !has_globs(&patterns) {
+        index.insert(0, usize::MAX);
+        patterns.insert(0, "**/*".to_string());
+    }
+    let sets = PatternSet::compile(&patterns, &PatternOptions::default())?;
+    let mut files = Vec::new();
+    for (i, archive, kind) in archives {
+        let entry_path = |name: &str| PathBuf::from(format!("{}!{}", archive.display(), name));
+        let entries = read_entries(archive, *kind, |name| {
+            let result = sets.matches(Path::new(name));
+            tracing::debug!(archive = %archive.display(), entry = name, set = result.set(), included = result.is_match(), "match decision");
+            if let Some(r) = rejected.as_deref_mut() && let Some(why) = result.rejection() { r.push((entry_path(name), why)); }
+            result.is_match()
+        })?;
+        for (name, bytes) in entries {
+            let mut hits: Vec<usize> = sets.matching_patterns(Path::new(&name)).into_iter().map(|k| index[k]).filter(|&k| k != usize::MAX).collect();
+            hits.push(*i);
+            let path = entry_path(&name);
+            files.push((Candidate { path: path.clone(), metadata: None, known_size: Some(bytes.len() as u64) }, hits));
+            sources.insert(path, Source::Entry(bytes));
+        }
+    }
+    Ok(files)
+}
+
+fn most_recent(files: Vec<Candidate>, n: usize) -> Vec<Candidate> {
+    let mut dated: Vec<(SystemTime, Candidate)> = files.into_iter().map(|c| (c.modified().unwrap_or(UNIX_EPOCH), c)).collect();
+    dated.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.path.cmp(&b.1.path)));
+    dated.truncate(n);
+    if let Some((cutoff, _)) = dated.last() { eprintln!("Keeping the {} most recently modified files (modified at or after {})", dated.len(), civil_datetime(*cutoff)); }
+    dated.into_iter().map(|(_, p)| p).collect()
+}
+
+fn pick_files(files: &[Candidate], base: &Path, reader: &dyn FileReader, overrides: &BinaryOverrides) -> Result<Vec<Candidate>> {
+    let shown = PathDisplay::new(PathStyle::Relative, base);
+    let items = files.par_iter().map(|c| {
+        let p = &c.path;
+        let tokens = if overrides.is_binary(p) { 0 } else { reader.read_candidate(c, false).map(|(c, _)| ApproxTokenizer.count_tokens(&c)).unwrap_or_else(|e| { tracing::warn!(path = %p.display(), error = %e, "could not read for the estimate"); 0 }) };
+        PickItem { label: shown.show(p), tokens }
     }).collect();
-    let results = results?;
-    for (path, content, lines, tokens) in results {
-        total_lines.fetch_add(lines, Ordering::Relaxed);
-        total_tokens.fetch_add(tokens, Ordering::Relaxed);
-        let out = format_entry(&path, &content);
-        if let Some(ref buf) = content_buffer { buf.lock().unwrap().push_str(&out); }
+    match pick(items, std::io::stdin().lock(), std::io::stderr())? {
+        Some(kept) => Ok(kept.into_iter().map(|i| files[i].clone()).collect()),
+        None => anyhow::bail!("interactive selection aborted"),
+    }
+}
+
+fn read_error(path: &Path) -> impl Fn(std::io::Error) -> LfError + '_ {
+    move |source| LfError::ReadError { path: path.to_path_buf(), source }
+}
+
+fn output_io(path: &Path) -> impl Fn(std::io::Error) -> LfError + '_ {
+    move |source| LfError::OutputIo { path: path.to_path_buf(), source }
+}
+
+fn write_manifest(files: &[FileEntry], path: &Path) -> Result<()> {
+    let lines = files.par_iter().map(|f| {
+        let size = std::fs::metadata(&f.path).map_err(read_error(&f.path))?.len();
+        let hash = sha256_file(&f.path).map_err(|e| io_error(e, read_error(&f.path)))?;
+        Ok(format!("{}\t{}\t{}\n", f.display, size, hash))
+    }).collect::<Result<Vec<String>>>()?;
+    let mut out = open_output(path, false)?;
+    out.write_all(lines.concat().as_bytes()).map_err(output_io(path))?;
+    out.commit().map_err(|e| io_error(e, output_io(path)))?;
+    Ok(())
+}
+
+fn open_output(path: &Path, append: bool) -> Result<AtomicOutput> {
+    let mut out = AtomicOutput::create(path, append).map_err(|e| io_error(e, output_io(path)))?;
+    if append && !out.is_empty() { out.write_all(APPEND_SEPARATOR.as_bytes()).map_err(output_io(path))?; }
+    Ok(out)
+}
+
+#[derive(Debug, Clone)]
+pub enum FileEvent {
+    Matched(PathBuf),
+    Processed { path: PathBuf, lines: usize, tokens: usize },
+    Skipped { path: PathBuf, reason: String },
+    Done(Stats),
+}
+
+type Emit<'a> = &'a mut dyn FnMut(&FileEntry) -> Result<()>;
+
+type Processed = (Vec<FileEntry>, usize, HashMap<PathBuf, FileMetrics>);
+
+fn process_files(files: &[Candidate], ctx: &FileCtx, on_event: &mut dyn FnMut(FileEvent), emit: Emit) -> Result<Processed> {
+    let (tx, rx) = mpsc::channel();
+    let opts = ctx.opts;
+    let mut slots: Vec<Option<FileEntry>> = vec![None; files.len()];
+    let mut first_err = None;
+    let mut empty = 0;
+    let mut metrics = HashMap::new();
+    std::thread::scope(|s| {
+        s.spawn(move || files.par_iter().enumerate().try_for_each_with(tx, |tx, (i, p)| {
+            if opts.cancelled() { return Err(()); }
+            let mut m = FileMetrics::default();
+            let r = process_file(p, ctx, &mut m).map(|f| f.map(|f| over_token_limit(f, ctx)));
+            tx.send((i, r, m)).map_err(|_| ())
+        }));
+        for (i, r, m) in rx {
+            metrics.insert(files[i].path.clone(), m);
+            match r {
+                Ok(None) => {
+                    tracing::info!(path = %files[i].path.display(), "skipped: empty");
+                    on_event(FileEvent::Skipped { path: files[i].path.clone(), reason: "empty".to_string() });
+                    empty += 1;
+                }
+                Ok(Some(f)) => {
+                    on_event(FileEvent::Processed { path: f.path.clone(), lines: f.lines, tokens: f.tokens });
+                    if first_err.is_none() && let Err(e) = emit(&f) { first_err = Some(e); }
+                    slots[i] = Some(f);
+                }
+                Err(e) => { first_err.get_or_insert(e); }
+            }
+        }
+    });
+    if let Some(e) = first_err { return Err(e); }
+    if opts.cancelled() { return Err(LfError::Cancelled.into()); }
+    Ok((slots.into_iter().flatten().collect(), empty, metrics))
+}
+
+fn roots(opts: &Options) -> Result<Vec<&Path>> {
+    let roots: Vec<&Path> = if opts.dirs.is_empty() { vec![Path::new(".")] } else { opts.dirs.iter().map(PathBuf::as_path).collect() };
+    if let Some(root) = roots.iter().find(|r| !r.is_dir()) { return Err(LfError::InvalidOption(format!("not a directory: {}", root.display())).into()); }
+    Ok(roots)
+}
+
+fn git_selection(root: &Path, opts: &Options) -> Result<Option<HashSet<PathBuf>>> {
+    if opts.staged { return Ok(Some(staged_files(root)?)); }
+    let mut selected = if opts.git_only { Some(tracked_files(root)?) } else { None };
+    if let Some(rev) = &opts.changed_since {
+        let changed = changed_files(root, rev)?;
+        selected = Some(match selected { Some(tracked) => changed.intersection(&tracked).cloned().collect(), None => changed });
+    }
+    Ok(selected)
+}
+
+fn explain_paths(deps: &Deps, opts: &Options) -> Result<Vec<(String, String)>> {
+    let mut out = Vec::new();
+    for root in roots(opts)? {
+        let selected = git_selection(root, opts)?;
+        let no_gitignore = opts.no_gitignore || selected.is_some();
+        let sets = PatternSet::compile(&opts.patterns, &PatternOptions { gitignore: !no_gitignore, root: root.to_path_buf() })?;
+        let walk = |no_gitignore| -> Vec<PathBuf> {
+            deps.walker.build(root, no_gitignore).filter_map(|e| e.ok()).filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+                .map(|e| e.path().strip_prefix(root).unwrap_or(e.path()).to_path_buf()).collect()
+        };
+        let walked: HashSet<PathBuf> = walk(no_gitignore).into_iter().collect();
+        let mut targets = if opts.explain_all { walk(true) } else { opts.explain.clone() };
+        targets.sort();
+        for rel in targets {
+            let verdict = match sets.matches(&rel) {
+                _ if selected.as_ref().is_some_and(|s| !s.contains(&rel)) => "skipped: not in the git selection".to_string(),
+                m if m.is_match() && !walked.contains(&rel) => format!("skipped: ignored by the walker's gitignore rules ({})", m.explain()),
+                m => m.explain(),
+            };
+            out.push((PathDisplay::new(PathStyle::Relative, Path::new("")).show(&rel), verdict));
+        }
+        if !opts.explain_all { break; }
+    }
+    Ok(out)
+}
+
+fn aggregate(deps: &Deps, opts: &Options, interactive: bool, on_event: &mut dyn FnMut(FileEvent), emit: Emit) -> Result<Aggregation> {
+    let started = Instant::now();
+    let roots = roots(opts)?;
+    let mut seen = HashSet::new();
+    let mut files = Vec::new();
+    let mut generated = HashSet::new();
+    let mut rejected = opts.show_skipped.then(Vec::new);
+    // Index of the include pattern that matched each file, for `--pattern-order`.
+    let mut order: HashMap<PathBuf, usize> = HashMap::new();
+    // Include patterns that matched at least one file, to report the dead ones.
+    let live_patterns = opts.patterns.iter().filter(|p| !p.starts_with('~')).count();
+    let mut hit: HashSet<usize> = HashSet::new();
+    let mut sources = HashMap::new();
+    // With `--expand-archives`, patterns naming archives open them and the other patterns select entries instead of files on disk.
+    let archives = if opts.expand_archives { archive_patterns(opts, &roots) } else { Vec::new() };
+    let mut add = |c: Candidate, files: &mut Vec<Candidate>| {
+        // A file of a revision may not exist on disk any more, so there's nothing to canonicalize.
+        let key = if opts.at_rev.is_some() { Ok(c.path.clone()) } else { std::fs::canonicalize(&c.path) };
+        let key = key.unwrap_or_else(|e| {
+            tracing::warn!(path = %c.path.display(), error = %e, "could not canonicalize, deduplicating by the walked path");
+            c.path.clone()
+        });
+        if seen.insert(key) { files.push(c); } else { tracing::info!(path = %c.path.display(), "skipped: already included from another root"); }
+    };
+    for (c, patterns) in archive_files(&archives, opts, &mut sources, rejected.as_mut())? {
+        if opts.pattern_order && let Some(&i) = patterns.first() { order.insert(c.path.clone(), i); }
+        hit.extend(patterns);
+        files.push(c);
+    }
+    for root in roots.iter().filter(|_| archives.is_empty() && has_globs(&opts.patterns)) {
+        let selected = if opts.at_rev.is_some() { None } else { git_selection(root, opts)? };
+        let no_gitignore = opts.no_gitignore || selected.is_some() || opts.at_rev.is_some();
+        let sets = PatternSet::compile(&opts.patterns, &PatternOptions { gitignore: !no_gitignore, root: root.to_path_buf() })?;
+        let attributes = if opts.include_generated || opts.at_rev.is_some() { GitAttributes::default() } else { GitAttributes::discover(deps.walker, root, no_gitignore) };
+        let found = match &opts.at_rev {
+            Some(rev) => revision_files(root, rev, &sets, &mut sources, rejected.as_mut())?,
+            None => collect_matching_files(deps.walker, root, &sets, no_gitignore, selected.as_ref(), opts, rejected.as_mut())?,
+        };
+        for c in found {
+            let p = &c.path;
+            if attributes.is_generated(p.strip_prefix(root).unwrap_or(p)) { generated.insert(p.clone()); }
+            let rel = p.strip_prefix(root).unwrap_or(p);
+            if opts.pattern_order && let MatchResult::Matched { rule, .. } = sets.matches(rel) { order.entry(p.clone()).or_insert(rule.index); }
+            if hit.len() < live_patterns { hit.extend(sets.matching_patterns(rel)); }
+            add(c, &mut files);
+        }
+    }
+    // Absolute and `~/` patterns: files are taken as named, directories are walked like a bare directory pattern.
+    let dir_patterns: Vec<String> = std::iter::once("**/*".to_string()).chain(opts.patterns.iter().filter(|p| p.starts_with('~') && explicit_path(p).is_none()).cloned()).collect();
+    for (i, path) in opts.patterns.iter().enumerate().filter(|_| archives.is_empty()).filter_map(|(i, p)| Some((i, explicit_path(p)?))) {
+        let md = std::fs::metadata(&path).map_err(|e| io_error(e.into(), read_error(&path)))?;
+        let found = if md.is_dir() {
+            let sets = PatternSet::compile(&dir_patterns, &PatternOptions { gitignore: !opts.no_gitignore, root: path.clone() })?;
+            collect_matching_files(deps.walker, &path, &sets, opts.no_gitignore, None, opts, rejected.as_mut())?
+        } else { vec![Candidate { path, metadata: Some(md), known_size: None }] };
+        if !found.is_empty() { hit.insert(i); }
+        for c in found {
+            if opts.pattern_order { order.entry(c.path.clone()).or_insert(i); }
+            add(c, &mut files);
+        }
+    }
+    if let Some(s) = &opts.stdin { files.insert(0, Candidate { path: PathBuf::from(&s.name), metadata: None, known_size: Some(s.content.len() as u64) }); }
+    let dead: Vec<String> = opts.patterns.iter().enumerate().filter(|(i, p)| !p.starts_with('~') && !hit.contains(i)).map(|(_, p)| p.clone()).collect();
+    if opts.strict_patterns && !dead.is_empty() { return Err(LfError::DeadPatterns(dead).into()); }
+    for p in &dead { eprintln!("Warning: pattern '{}' matched no files", p); }
+    if opts.pattern_order { files.sort_by_cached_key(|c| (order.get(&c.path).copied().unwrap_or(usize::MAX), c.path.clone())); }
+    let base = if roots.len() == 1 { roots[0] } else { Path::new("") };
+    let walk_ms = started.elapsed().as_millis() as u64;
+    let relative = PathDisplay::new(PathStyle::Relative, base);
+    let mut agg = Aggregation { tokenizer: deps.tokenizer.name().to_string(), ..Aggregation::default() };
+    for (p, why) in rejected.unwrap_or_default() {
+        let shown = relative.show(&p);
+        agg.skipped.push(match why {
+            Rejection::Gitignore => (SkipCategory::Gitignore, shown),
+            Rejection::ExcludePattern(pattern) => (SkipCategory::ExcludePattern, format!("{} ({})", shown, pattern)),
+            Rejection::Hidden => (SkipCategory::Hidden, shown),
+            Rejection::NotMatched => continue,
+        });
+    }
+    if opts.skip_binary || opts.binary_only {
+        let (binary, text): (Vec<Candidate>, Vec<Candidate>) = files.into_iter().partition(|c| opts.binary_overrides.is_binary(&c.path));
+        let (kept, dropped, reason) = if opts.skip_binary { (text, binary, "binary") } else { (binary, text, "text") };
+        for c in dropped {
+            tracing::info!(path = %c.path.display(), "skipped: {}", reason);
+            if opts.skip_binary {
+                agg.skipped_binary += 1;
+                agg.skipped_binary_bytes += c.size();
+                if opts.show_skipped { agg.skipped.push((SkipCategory::Binary, relative.show(&c.path))); }
+            }
+            on_event(FileEvent::Skipped { path: c.path, reason: reason.to_string() });
+        }
+        files = kept;
+    }
+    if let Some(n) = opts.recent { files = most_recent(files, n); }
+    if interactive && !files.is_empty() {
+        let kept = pick_files(&files, base, deps.reader, &opts.binary_overrides)?;
+        for c in files.iter().filter(|c| !kept.iter().any(|k| k.path == c.path)) {
+            tracing::info!(path = %c.path.display(), "skipped: deselected");
+            on_event(FileEvent::Skipped { path: c.path.clone(), reason: "deselected".to_string() });
+        }
+        files = kept;
+        if files.is_empty() { anyhow::bail!("no files selected"); }
+    }
+    agg.matched = files.iter().map(|c| relative.show(&c.path)).collect();
+    let mut aliases = HashSet::new();
+    for group in case_collisions(&agg.matched) {
+        let mut kept: Vec<usize> = Vec::new();
+        for i in group.iter().filter_map(|g| agg.matched.iter().position(|m| m == g)) {
+            if kept.iter().any(|&k| same_file(&files[k].path, &files[i].path)) { aliases.insert(i); } else { kept.push(i); }
+        }
+        let note = if kept.len() < group.len() { " (one file on disk, emitted once)" } else { "" };
+        eprintln!("Warning: paths differ only in case and would collide on a case-insensitive filesystem: {}{}", group.join(", "), note);
+    }
+    if !aliases.is_empty() {
+        files = files.into_iter().enumerate().filter(|(i, _)| !aliases.contains(i)).map(|(_, p)| p).collect();
+        agg.matched = files.iter().map(|c| relative.show(&c.path)).collect();
+    }
+    if opts.max_files.is_some() || opts.max_total_bytes.is_some() {
+        let sizes: Vec<u64> = files.iter().map(Candidate::size).collect();
+        if let (fit, Some(cap)) = fit_selection(&sizes, opts.max_files, opts.max_total_bytes) {
+            for c in files.drain(fit..) { on_event(FileEvent::Skipped { path: c.path, reason: "limit".to_string() }); }
+            agg.capped = Some(Capped { cap, omitted: agg.matched.split_off(fit) });
+        }
+    }
+    for c in &files { on_event(FileEvent::Matched(c.path.clone())); }
+    agg.candidates = files.clone();
+    if opts.incremental && let Some(output) = &opts.output {
+        let options = format!("{:?}\n{}", Options { cancel: None, ..opts.clone() }, deps.tokenizer.name());
+        let current = State { fingerprint: fingerprint(&options), files: agg.matched.iter().zip(&files)
+            .filter(|(s, c)| !s.ends_with(crate::state::STATE_SUFFIX) && !same_file(&c.path, output))
+            .map(|(s, c)| FileStamp::of(s, c.metadata.as_ref())).collect(), ..State::default() };
+        if let Some(prev) = crate::state::load(&state_path(output)) && prev.matches(&current, output) { agg.unchanged = Some(prev); }
+        agg.state = Some(current);
+    }
+    if !files.is_empty() && !opts.dry_run && agg.unchanged.is_none() { deps.tokenizer.prepare()?; }
+    if !opts.tree_only && !opts.dry_run && agg.unchanged.is_none() {
+        let shown = PathDisplay::new(opts.path_style, base);
+        let transforms = pipeline(opts);
+        let ctx = FileCtx { shown: &shown, reader: deps.reader, tokenizer: deps.tokenizer.as_ref(), opts, generated: &generated, transforms: &transforms, sources: &sources };
+        (agg.files, agg.skipped_empty, agg.metrics) = process_files(&files, &ctx, on_event, emit)?;
+        if let Some(max) = opts.max_tokens_per_dir { cap_tokens_per_dir(&mut agg, max, opts.budget_depth.unwrap_or(1)); }
+        if let Some(max) = opts.max_tokens { cap_tokens(&mut agg, max); }
+    }
+    agg.lines = agg.files.iter().map(|f| f.lines).sum();
+    agg.tokens = agg.files.iter().map(|f| f.tokens).sum();
+    agg.timing.walk_ms = walk_ms;
+    agg.timing.process_ms = started.elapsed().as_millis() as u64 - walk_ms;
+    agg.timing.total_ms = agg.timing.process_ms + walk_ms;
+    Ok(agg)
+}
+
+// Removes `dropped` from the files, candidates and matched paths of `agg`, returning how the matched list showed them.
+fn drop_files(agg: &mut Aggregation, dropped: &HashSet<PathBuf>) -> Vec<String> {
+    agg.files.retain(|f| !dropped.contains(&f.path));
+    let (kept, gone): (Vec<_>, Vec<_>) = agg.candidates.drain(..).zip(agg.matched.drain(..)).partition(|(c, _)| !dropped.contains(&c.path));
+    (agg.candidates, agg.matched) = kept.into_iter().unzip();
+    gone.into_iter().map(|(_, shown)| shown).collect()
+}
+
+// Drops the files past their group's `--max-tokens-per-dir` budget, counted in path order within each group;
+// the files that stay keep their output order.
+fn cap_tokens_per_dir(agg: &mut Aggregation, max: usize, depth: usize) {
+    let over = fit_tokens_per_dir(&agg.files.iter().map(|f| (f.display.as_str(), f.tokens)).collect::<Vec<_>>(), depth, max);
+    let dropped: HashSet<PathBuf> = over.iter().flat_map(|(_, omitted)| omitted.iter().map(|&i| agg.files[i].path.clone())).collect();
+    agg.dir_capped = over.into_iter().map(|(dir, omitted)| DirCapped { dir, omitted: omitted.into_iter().map(|i| agg.files[i].display.clone()).collect() }).collect();
+    drop_files(agg, &dropped);
+}
+
+// Drops the files past the token budget from `agg`; files the selection caps already dropped stay listed after them.
+fn cap_tokens(agg: &mut Aggregation, max: usize) {
+    let fit = fit_tokens(&agg.files.iter().map(|f| f.tokens).collect::<Vec<_>>(), max);
+    if fit == agg.files.len() { return; }
+    let dropped: HashSet<PathBuf> = agg.files[fit..].iter().map(|f| f.path.clone()).collect();
+    let mut omitted = drop_files(agg, &dropped);
+    omitted.extend(agg.capped.take().map(|c| c.omitted).unwrap_or_default());
+    agg.capped = Some(Capped { cap: Cap::Tokens(max), omitted });
+}
+
+pub fn collect(deps: &Deps, opts: &Options) -> Result<Aggregation, LfError> {
+    let agg = aggregate(deps, opts, false, &mut |_| {}, &mut |_| Ok(()))?;
+    if agg.matched.is_empty() { return Err(LfError::NoMatches); }
+    Ok(agg)
+}
+
+/// The positional `run_app` of earlier versions, under a new name now that `run_app` takes an `Options`.
+#[deprecated(note = "build an Options and call run_app")]
+#[allow(clippy::too_many_arguments)]
+pub fn run_app_positional(deps: Deps, patterns: &[String], output_path: Option<&Path>, append: bool, tee: bool, no_clipboard: bool, mask_java_imports: bool, no_gitignore: bool) -> Result<Stats, LfError> {
+    let opts = Options { output: output_path.map(Path::to_path_buf), ..Options::new(patterns) }
+        .append(append).tee(tee).no_clipboard(no_clipboard).mask_java_imports(mask_java_imports).no_gitignore(no_gitignore);
+    run_app(deps, &opts)
+}
+
+pub fn run_app(deps: Deps, opts: &Options) -> Result<Stats, LfError> {
+    run_with(deps, opts, |_| {})
+}
+
+pub fn run_with<F: FnMut(FileEvent)>(deps: Deps, opts: &Options, mut callback: F) -> Result<Stats, LfError> {
+    let stats = run_pipeline(deps, opts, &mut callback)?;
+    callback(FileEvent::Done(stats.clone()));
+    Ok(stats)
+}
+
+/// Replaces every text file whose content matches an earlier one in output order with a pointer to it, unless
+/// the pointer would cost as many tokens as the content. Returns the number of folded files and the tokens they no longer cost.
+fn fold_duplicates(files: &mut [FileEntry], tokenizer: &dyn Tokenizer) -> (usize, usize) {
+    use sha2::{Digest, Sha256};
+    let mut first: HashMap<Vec<u8>, String> = HashMap::new();
+    let (mut folded, mut saved) = (0, 0);
+    for f in files.iter_mut().filter(|f| !f.is_binary() && f.omitted.is_none() && !f.content.is_empty()) {
+        let hash = Sha256::digest(f.content.as_bytes()).to_vec();
+        let Some(canonical) = first.get(&hash) else { first.insert(hash, f.display.clone()); continue };
+        let pointer = format!("[identical to {}]\n", canonical);
+        let tokens = tokenizer.count_tokens(&pointer);
+        if tokens >= f.tokens { continue; }
+        saved += f.tokens - tokens;
+        f.content = pointer;
+        f.tokens = tokens;
+        f.omitted = Some("duplicate".to_string());
+        folded += 1;
+    }
+    (folded, saved)
+}
+
+/// Wraps each run of `rendered` entries that share a `group_key` in a section header and a subtotal footer.
+fn group_sections(results: &[FileEntry], rendered: Vec<String>, format: Format, depth: usize, counting: bool) -> Vec<String> {
+    let mut out = Vec::with_capacity(rendered.len());
+    let mut rendered = rendered.into_iter();
+    for section in results.chunk_by(|a, b| group_key(&a.display, depth) == group_key(&b.display, depth)) {
+        let key = group_key(&section[0].display, depth);
+        out.push(section_header(format, &key));
+        out.extend(rendered.by_ref().take(section.len()));
+        let (lines, tokens) = (section.iter().map(|f| f.lines).sum(), section.iter().map(|f| f.tokens).sum());
+        out.push(section_footer(format, &key, section.len(), lines, counting.then_some(tokens)));
+    }
+    out
+}
+
+fn render_with(write: impl FnOnce(&mut Vec<u8>) -> std::io::Result<()>) -> Result<String> {
+    let mut buf = Vec::new();
+    write(&mut buf).context("Failed to format output")?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+fn json_records(r: &FileEntry, opts: &Options, tokenizer: &dyn Tokenizer) -> Result<Vec<String>> {
+    if let Some(size) = opts.chunk_tokens {
+        let chunks = chunk_file(&r.display, &r.content, tokenizer, size, opts.chunk_overlap)?;
+        return Ok(chunks.iter().map(|c| format!("{}\n", serde_json::to_string(c).expect("chunks always serialize"))).collect());
+    }
+    let entry = Entry { path: &r.path, display: &r.display, content: &r.content, binary: r.is_binary(), note: r.note.as_deref(), meta: None, lines: r.lines, tokens: r.tokens, size: 0, index: 0, total: 0, units: opts.size_units(), id: None };
+    Ok(vec![format_entry(Format::Json, &Templates::default(), &entry)])
+}
+
+fn run_pipeline(deps: Deps, opts: &Options, on_event: &mut dyn FnMut(FileEvent)) -> Result<Stats, LfError> {
+    let invalid = |msg: &str| Err(LfError::InvalidOption(msg.to_string()));
+    if opts.toc && opts.format != Format::Markdown { return invalid("--toc requires --format markdown"); }
+    if opts.number_files && (!matches!(opts.format, Format::Plain | Format::Markdown) || opts.todos) { return invalid("--number-files requires --format plain or markdown and cannot be combined with --todos"); }
+    if !opts.templates.is_empty() && opts.format != Format::Plain { return invalid("--header-template/--footer-template require --format plain"); }
+    if opts.skip_binary && opts.binary_only { return invalid("--skip-binary and --binary-only are mutually exclusive"); }
+    if opts.extract_documents && !cfg!(feature = "doc-extract") { return invalid("--extract-documents needs a build with the doc-extract feature"); }
+    let jsonl = opts.format == Format::Jsonl;
+    if opts.chunk_tokens.is_some() && !matches!(opts.format, Format::Json | Format::Jsonl) { return invalid("--chunk-tokens requires --format json or jsonl"); }
+    if matches!(opts.format, Format::Json | Format::Jsonl | Format::Html) && (opts.tree || opts.tree_only || opts.todos || !opts.prefix.is_empty() || !opts.suffix.is_empty()) {
+        return invalid("--tree, --todos, --prefix and --suffix are not supported with --format json, jsonl or html");
+    }
+    if jsonl && (opts.count_only || !opts.no_clipboard && (opts.output.is_none() || opts.tee)) {
+        return invalid("--format jsonl streams to stdout or a file; use --no-clipboard or -o without --tee or --count-only");
+    }
+    if opts.incremental && (opts.output.is_none() || opts.append || opts.tee || opts.check) {
+        return invalid("--incremental needs -o FILE and cannot be combined with --append, --tee or --check");
+    }
+    if opts.check && (opts.output.is_none() || opts.append || opts.tee || opts.count_only || opts.dry_run || jsonl) {
+        return invalid("--check compares against -o FILE and cannot be combined with --append, --tee, --count-only, --dry-run or --format jsonl");
+    }
+    if opts.clipboard_html && (opts.no_clipboard || opts.output.is_some() && !opts.tee) { return invalid("--clipboard-html needs the clipboard; drop --no-clipboard, or use -o with --tee"); }
+    if opts.region_markers.as_ref().is_some_and(|(s, e)| s.is_empty() || e.is_empty() || s == e) { return invalid("--region-markers needs two different, non-empty markers"); }
+    if opts.at_rev.is_some() && (opts.staged || opts.changed_since.is_some() || opts.recent.is_some() || opts.incremental || opts.interactive || opts.extract_documents || opts.hash_binaries || opts.manifest.is_some() || opts.explain_all || !opts.explain.is_empty()) {
+        return invalid("--at-rev reads files from a git revision and cannot be combined with --staged, --changed-since, --recent, --incremental, --interactive, --extract-documents, --hash-binaries, --manifest or --explain, which look at the working tree");
+    }
+    if opts.at_rev.is_some() && opts.patterns.iter().any(|p| explicit_path(p).is_some()) { return invalid("--at-rev only takes patterns relative to the walked directory, not absolute or ~/ paths"); }
+    if opts.expand_archives && !cfg!(feature = "archive") { return invalid("--expand-archives needs a build with the archive feature"); }
+    if opts.expand_archives && (opts.at_rev.is_some() || opts.incremental || opts.interactive || opts.extract_documents || opts.hash_binaries || opts.manifest.is_some()) {
+        return invalid("--expand-archives cannot be combined with --at-rev, --incremental, --interactive, --extract-documents, --hash-binaries or --manifest, which read entries as files on disk");
+    }
+    if opts.stdin.is_some() && opts.incremental { return invalid("- (stdin) cannot be combined with --incremental"); }
+    if jsonl && opts.dedupe_content { return invalid("--dedupe-content is not supported with --format jsonl"); }
+    if opts.group_by_dir && (matches!(opts.format, Format::Json | Format::Jsonl | Format::Html) || opts.todos) { return invalid("--group-by-dir is not supported with --format json, jsonl or html or with --todos"); }
+    if opts.group_depth.is_some_and(|d| d == 0) { return invalid("--group-depth must be at least 1"); }
+    if jsonl && (opts.max_tokens.is_some() || opts.max_tokens_per_dir.is_some()) { return invalid("--max-tokens and --max-tokens-per-dir are not supported with --format jsonl"); }
+    if opts.budget_depth.is_some_and(|d| d == 0) { return invalid("--budget-depth must be at least 1"); }
+    if let Some(size) = opts.chunk_tokens { check_overlap(size, opts.chunk_overlap)?; }
+    if opts.interactive && !std::io::stdin().is_terminal() { return invalid("--interactive needs a terminal on stdin"); }
+    let started = Instant::now();
+    let baseline = opts.diff_against.as_deref().map(read_snapshot).transpose()?;
+    if opts.explain_all || !opts.explain.is_empty() {
+        for (path, verdict) in explain_paths(&deps, opts)? { println!("{}: {}", path, verdict); }
+        return Ok(Stats { tokenizer: deps.tokenizer.name().to_string(), ..Stats::default() });
+    }
+    let mut early_output = if jsonl && !opts.dry_run { opts.output.as_deref().map(|p| open_output(p, opts.append)).transpose()? } else { None };
+    let mut stream = |f: &FileEntry| -> Result<()> {
+        if !jsonl { return Ok(()); }
+        let records = json_records(f, opts, deps.tokenizer.as_ref())?.concat();
+        match (&mut early_output, &opts.output) {
+            (Some(w), Some(path)) => w.write_all(records.as_bytes()).map_err(output_io(path))?,
+            _ => {
+                let mut out = std::io::stdout().lock();
+                out.write_all(records.as_bytes()).and_then(|_| out.flush()).context("Failed to write to output")?;
+            }
+        }
+        Ok(())
+    };
+    let mut agg = aggregate(&deps, opts, opts.interactive, on_event, &mut stream)?;
+    let mut stats = Stats { tokenizer: agg.tokenizer.clone(), skipped_binary: agg.skipped_binary, skipped_binary_bytes: agg.skipped_binary_bytes, skipped_empty: agg.skipped_empty, ..Stats::default() };
+    if let Some(capped) = &agg.capped {
+        eprint!("{}", render_omitted(capped, opts.size_units()));
+        stats.omitted_by_limits = capped.omitted.len();
+    }
+    if agg.matched.is_empty() {
+        if opts.strict_limits && let Some(capped) = &agg.capped { return Err(LfError::LimitExceeded { cap: capped.cap.describe(opts.size_units()), omitted: capped.omitted.len() }); }
+        return Err(LfError::NoMatches);
+    }
+    if let Some(max) = opts.max_tokens_per_dir && !agg.dir_capped.is_empty() {
+        eprint!("{}", render_dir_omitted(max, &agg.dir_capped));
+        stats.omitted_by_limits += agg.dir_capped.iter().map(|g| g.omitted.len()).sum::<usize>();
+    }
+    if opts.dry_run {
+        let items = agg.candidates.iter().zip(&agg.matched)
+            .map(|(c, shown)| (shown.clone(), c.size(), opts.binary_overrides.is_binary(&c.path)))
+            .collect();
+        let report = dry_run(items);
+        print!("{}", render_dry_run(&report, opts.size_units()));
+        stats.files = report.files;
+        stats.bytes = report.bytes;
+        stats.tokens = report.estimated_tokens();
+        return Ok(stats);
+    }
+    if let Some(prev) = &agg.unchanged {
+        println!("Output up to date ({} files, {} tokens)", thousands(prev.files.len()), thousands(prev.tokens));
+        stats.files = prev.files.len();
+        stats.tokens = prev.tokens;
+        return Ok(stats);
+    }
+    if opts.dedupe_content { (stats.duplicates, stats.duplicate_tokens_saved) = fold_duplicates(&mut agg.files, deps.tokenizer.as_ref()); }
+    let counting = deps.tokenizer.name() != NO_TOKENIZER;
+    let depth = opts.group_depth.unwrap_or(1);
+    if opts.group_by_dir { agg.files.sort_by_cached_key(|f| (group_key(&f.display, depth), f.display.clone())); }
+    let tokenizer = deps.tokenizer.clone();
+    let results = &agg.files;
+    let metadata: HashMap<&Path, &std::fs::Metadata> = agg.candidates.iter().filter_map(|c| Some((c.path.as_path(), c.metadata.as_ref()?))).collect();
+    let total = results.len();
+    let mut formatter = opts.formatter.as_ref().map_or_else(|| formatter_for(opts.format, &opts.templates), FormatterFactory::make);
+    let displays: Vec<&str> = results.iter().map(|r| r.display.as_str()).collect();
+    let run_meta = RunMeta { displays: &displays, lines: results.iter().map(|r| r.lines).sum(), tokens: counting.then(|| results.iter().map(|r| r.tokens).sum()) };
+    let head = if opts.todos { String::new() } else { render_with(|b| formatter.begin(b, &run_meta))? };
+    let mut rendered: Vec<String> = if opts.todos { todo_report(results, opts, tokenizer.as_ref(), &mut stats) } else { results.iter().enumerate().map(|(i, r)| {
+        let md = metadata.get(r.path.as_path()).copied();
+        let meta = opts.header_meta.map(|f| header_meta(r, md, f));
+        let size = md.map_or(0, |m| m.len());
+        let entry = Entry { path: &r.path, display: &r.display, content: &r.content, binary: r.is_binary(), note: r.note.as_deref(), meta: meta.as_ref(), lines: r.lines, tokens: r.tokens, size, index: i + 1, total, units: opts.size_units(), id: opts.number_files.then_some(i + 1) };
+        render_with(|b| formatter.entry(b, &entry))
+    }).collect::<Result<_>>()? };
+    if !opts.todos { for (r, out) in results.iter().zip(&rendered) { stats.record(r, out.len() as u64, agg.metrics.get(&r.path).copied().filter(|_| opts.timings)); } }
+    if opts.group_by_dir {
+        rendered = group_sections(results, rendered, opts.format, depth, counting);
+        stats.bytes = rendered.iter().map(|r| r.len() as u64).sum();
+    }
+    if opts.chunk_tokens.is_some() { rendered = results.iter().map(|r| json_records(r, opts, tokenizer.as_ref())).collect::<Result<Vec<_>>>()?.concat(); }
+    // jsonl records were already streamed as each file finished; only the summary from `finish` is left to write.
+    if jsonl { rendered.clear(); }
+    if opts.chunk_tokens.is_some() && opts.format == Format::Json { rendered = vec![json_array(&rendered)]; }
+    else if !opts.todos {
+        let tail = render_with(|b| formatter.finish(b, &stats))?;
+        if !head.is_empty() { rendered.insert(0, head); }
+        if !tail.is_empty() { rendered.push(tail); }
+    }
+    if opts.toc || opts.number_files {
+        let headings: Vec<String> = results.iter().enumerate().map(|(i, r)| if opts.number_files { format!("{} {}", file_id(i + 1), r.display) } else { r.display.clone() }).collect();
+        let index = if opts.toc { render_toc(&headings) } else { render_file_index(&displays) };
+        stats.tokens += tokenizer.count_tokens(&index);
+        stats.bytes += index.len() as u64;
+        rendered.insert(0, index);
+    }
+    if opts.tree || opts.tree_only {
+        let mut paths = agg.matched.clone();
+        paths.sort();
+        let tree = format_tree(opts.format, &render_tree(&paths));
+        stats.tokens += tokenizer.count_tokens(&tree);
+        stats.bytes += tree.len() as u64;
+        if opts.tree_only { stats.files = agg.matched.len(); }
+        rendered.insert(0, tree);
+    }
+    let wrap = |parts: &[String]| parts.iter().map(|p| p.trim_end_matches('\n')).collect::<Vec<_>>().join("\n\n");
+    for (text, at_start) in [(wrap(&opts.prefix), true), (wrap(&opts.suffix), false)] {
+        if text.is_empty() { continue; }
+        let text = if at_start { format!("{}\n\n", text) }
+            else if rendered.last().is_none_or(|r| r.ends_with("\n\n")) { format!("{}\n", text) }
+            else { format!("\n{}\n", text) };
+        stats.tokens += tokenizer.count_tokens(&text);
+        stats.bytes += text.len() as u64;
+        if at_start { rendered.insert(0, text); } else { rendered.push(text); }
+    }
+    if let Some(path) = &opts.manifest { write_manifest(results, path)?; }
+    stats.timing.walk_ms = agg.timing.walk_ms;
+    stats.timing.process_ms = started.elapsed().as_millis() as u64 - agg.timing.walk_ms;
+    if let Some(max) = opts.warn_tokens_per_file {
+        let offenders: Vec<(&str, usize)> = results.iter().map(|r| (r.display.as_str(), r.skipped_tokens.unwrap_or(r.tokens))).filter(|&(_, t)| t > max).collect();
+        if !offenders.is_empty() { eprintln!("Warning: {} files exceed {} tokens each:", thousands(offenders.len()), thousands(max)); }
+        for (path, tokens) in offenders { eprintln!("  {} ({} tokens)", path, thousands(tokens)); }
+    }
+    if stats.skipped_binary > 0 { eprintln!("Skipped {} binary files ({})", thousands(stats.skipped_binary), format_size(stats.skipped_binary_bytes, opts.size_units())); }
+    if stats.skipped_empty > 0 { eprintln!("Skipped {} empty files", thousands(stats.skipped_empty)); }
+    if stats.skipped_token_limit > 0 {
+        eprintln!("Skipped {} files over the per-file token limit ({} tokens)", thousands(stats.skipped_token_limit), thousands(stats.skipped_token_limit_tokens));
+    }
+    if opts.show_skipped {
+        let mut skipped = agg.skipped.clone();
+        skipped.extend(results.iter().filter(|r| r.skipped_tokens.is_some()).map(|r| (SkipCategory::TooLarge, r.display.clone())));
+        skipped.extend(agg.capped.iter().flat_map(|c| c.omitted.iter().map(|p| (SkipCategory::TooLarge, p.clone()))));
+        skipped.extend(agg.dir_capped.iter().flat_map(|g| g.omitted.iter().map(|p| (SkipCategory::TooLarge, p.clone()))));
+        eprint!("{}", render_skipped(&skipped));
+    }
+    if stats.duplicates > 0 { eprintln!("Folded {} duplicate files (saved {} tokens)", thousands(stats.duplicates), thousands(stats.duplicate_tokens_saved)); }
+    if opts.timings {
+        let mut phases = FileMetrics::default();
+        for m in agg.metrics.values() { phases.add(m); }
+        stats.timing.phases = Some(phases);
+        let mut slowest: Vec<(&str, FileMetrics)> = results.iter().filter_map(|r| agg.metrics.get(&r.path).map(|m| (r.display.as_str(), *m))).collect();
+        slowest.sort_by(|a, b| b.1.total_us().cmp(&a.1.total_us()).then_with(|| a.0.cmp(b.0)));
+        slowest.truncate(SLOWEST_FILES);
+        eprint!("{}", render_timings(&stats.timing, &slowest));
+    }
+    if opts.count_only {
+        let mut summary = format!("Files: {}  Lines: {}", thousands(stats.files), thousands(stats.lines));
+        if counting { summary.push_str(&format!("  Tokens: {}", thousands(stats.tokens))); }
+        println!("{}", summary);
+        stats.timing.total_ms = started.elapsed().as_millis() as u64;
+        return Ok(stats);
+    }
+    if opts.check && let Some(path) = &opts.output {
+        let existing = std::fs::read(path).ok();
+        stats.timing.total_ms = started.elapsed().as_millis() as u64;
+        return match describe_difference(&rendered.concat(), existing.as_deref()) {
+            None => { eprintln!("{} is up to date", path.display()); Ok(stats) }
+            Some(detail) => Err(LfError::OutOfDate { path: path.clone(), detail }),
+        };
+    }
+    let use_clipboard = !opts.no_clipboard && (opts.output.is_none() || opts.tee);
+    let mut content_buffer = if use_clipboard { Some(String::new()) } else { None };
+    let mut file_output = match early_output {
+        Some(out) => Some(out),
+        None => opts.output.as_deref().map(|p| open_output(p, opts.append)).transpose()?,
+    };
+    let mut output_writer: Option<Box<dyn Write + Send>> = if file_output.is_none() && opts.no_clipboard { Some(Box::new(std::io::stdout())) } else { None };
+    for out in &rendered {
+        if let Some(ref mut buf) = content_buffer { buf.push_str(out); }
+        if let (Some(w), Some(path)) = (&mut file_output, &opts.output) { w.write_all(out.as_bytes()).map_err(output_io(path))?; }
         else if let Some(ref mut w) = output_writer { w.write_all(out.as_bytes()).context("Failed to write to output")?; }
-        else { print!("{}", out); }
+        else if content_buffer.is_none() { print!("{}", out); }
     }
-    if let Some(buf) = content_buffer {
-        let content = buf.lock().unwrap().clone();
-        if let Some(cb) = deps.clipboard { if cb.set_text(content.clone()).is_err() { print!("{}", content); } } else { print!("{}", content); }
+    if let (Some(f), Some(path)) = (file_output.take(), &opts.output) { f.commit().map_err(|e| io_error(e, output_io(path)))?; }
+    if let (Some(state), Some(path)) = (&agg.state, &opts.output) {
+        let output_size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        crate::state::save(&state_path(path), &State { output_size, tokens: stats.tokens, ..state.clone() })?;
+    }
+    if let Some(content) = content_buffer {
+        let copied = match opts.clipboard_limit.filter(|&l| content.len() as u64 > l) {
+            Some(limit) => {
+                if opts.clipboard_limit_strict { return Err(LfError::ClipboardLimit { size: content.len() as u64, limit }); }
+                let msg = format!("clipboard payload is {}, above the clipboard limit of {}", format_size(content.len() as u64, opts.size_units()), format_size(limit, opts.size_units()));
+                eprintln!("Warning: {}", msg);
+                stats.clipboard_fallback = true;
+                Err(msg)
+            }
+            None => deps.clipboard.map(|cb| if opts.clipboard_html {
+                let files: Vec<HtmlFile> = results.iter().map(|r| HtmlFile { display: &r.display, content: &r.content, binary: r.is_binary() }).collect();
+                cb.set_html(render_html_fragment(&files), content.clone())
+            } else { cb.set_text(content.clone()) }).unwrap_or_else(|| Err("no clipboard available".to_string())),
+        };
+        match copied {
+            Ok(()) => {
+                let tokens = if counting { format!(" ({} tokens)", thousands(stats.tokens)) } else { String::new() };
+                eprintln!("Copied {} files{} to clipboard", thousands(stats.files), tokens);
+                if let Some(n) = opts.preview.filter(|_| opts.output.is_none()) { eprint!("{}", render_preview(&content, n)); }
+            }
+            Err(_) if opts.output.is_none() && (opts.fallback_stdout || stats.clipboard_fallback) => print!("{}", content),
+            Err(e) if opts.output.is_none() => return Err(LfError::ClipboardFailed(e)),
+            Err(e) => if !stats.clipboard_fallback { eprintln!("Warning: failed to copy to clipboard: {}", e); },
+        }
     }
     if let Some(mut w) = output_writer { w.flush().context("Failed to flush final output")?; }
-    let lines = total_lines.load(Ordering::Relaxed);
-    let tokens = total_tokens.load(Ordering::Relaxed);
-    println!("Lines: {}", lines);
-    #[cfg(feature = "token-counting")]
-    println!("Tokens (o200k_base): {}", tokens);
-    Ok(Stats { lines, tokens })
-}
\ No newline at end of file
+    if !jsonl {
+        let mut summary = format!("Files: {}   Size: {}\nLines: {}\n", thousands(stats.files), format_size(stats.bytes, opts.size_units()), thousands(stats.lines));
+        if let Some(n) = stats.todos { summary.push_str(&format!("Findings: {}\n", thousands(n))); }
+        if counting { summary.push_str(&format!("Tokens ({}): {}\n", stats.tokenizer, thousands(stats.tokens))); }
+        // JSON and HTML on stdout must stay parseable, so their summary goes to stderr.
+        if matches!(opts.format, Format::Json | Format::Html) { eprint!("{}", summary); } else { print!("{}", summary); }
+    }
+    if opts.stats_by_ext { eprint!("{}", render_ext_stats(&ext_stats(results), opts.size_units())); }
+    if opts.lang_stats { eprint!("{}", render_lang_stats(&lang_stats(results))); }
+    if stats.clipboard_fallback {
+        eprintln!("Clipboard: skipped, payload over --clipboard-limit{}", if opts.output.is_none() { " (printed to stdout instead)" } else { "" });
+    }
+    stats.timing.total_ms = started.elapsed().as_millis() as u64;
+    if let Some(old) = baseline {
+        let current: Vec<SnapshotFile> = results.iter().map(|r| SnapshotFile { path: r.display.clone(), tokens: r.tokens, content: r.content.clone() }).collect();
+        let diff = diff_snapshots(&old, &current);
+        print!("{}", render_diff(&diff));
+        if opts.fail_on_change && !diff.is_empty() { return Err(LfError::Changed(diff.summary())); }
+    }
+    if opts.strict_limits && let Some(capped) = agg.capped { return Err(LfError::LimitExceeded { cap: capped.cap.describe(opts.size_units()), omitted: capped.omitted.len() }); }
+    if opts.strict_limits && let Some(max) = opts.max_tokens_per_dir && !agg.dir_capped.is_empty() {
+        return Err(LfError::LimitExceeded { cap: Cap::TokensPerDir(max).describe(opts.size_units()), omitted: stats.omitted_by_limits });
+    }
+    Ok(stats)
+}
+
+include!("app_tests.rs");
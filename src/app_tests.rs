@@ -4,14 +4,13 @@ mod tests {
     use crate::clipboard::ClipboardSink;
     use crate::fs::{FileReader, WalkerFactory};
     use crate::tokenizer::Tokenizer;
-    use std::cell::RefCell;
     use std::fs;
     use std::path::{Path, PathBuf};
-    use tempfile::tempdir;
+    use std::sync::Mutex;
 
-    struct NoopClipboard(RefCell<Option<String>>);
+    struct NoopClipboard(Mutex<Option<String>>);
     impl ClipboardSink for NoopClipboard {
-        fn set_text(&self, text: String) -> Result<(), String> { self.0.replace(Some(text)); Ok(()) }
+        fn set_text(&self, text: String) -> Result<(), String> { *self.0.lock().unwrap() = Some(text); Ok(()) }
     }
 
     struct TestReader;
@@ -25,13 +24,15 @@ mod tests {
 
     struct FixedWalker { root: PathBuf }
     impl WalkerFactory for FixedWalker {
-        fn build(&self, no_gitignore: bool) -> ignore::Walk {
+        fn build(&self, _root: &Path, no_gitignore: bool) -> ignore::Walk {
             let mut wb = ignore::WalkBuilder::new(&self.root);
             wb.hidden(false).follow_links(false).git_ignore(!no_gitignore).git_global(!no_gitignore).git_exclude(!no_gitignore).parents(true);
             wb.build()
         }
     }
 
+    fn tempdir() -> std::io::Result<tempfile::TempDir> { tempfile::Builder::new().prefix("lf").tempdir() }
+
     struct T0;
     impl Tokenizer for T0 { fn count_tokens(&self, _: &str) -> usize { 0 } }
 
@@ -40,14 +41,443 @@ mod tests {
         let d = tempdir().unwrap();
         fs::write(d.path().join("a.txt"), "x\n").unwrap();
         fs::write(d.path().join(".gitignore"), "ignored.txt\n").unwrap();
-        let cb = NoopClipboard(RefCell::new(None));
+        let cb = NoopClipboard(Mutex::new(None));
         let deps = Deps {
             walker: &FixedWalker { root: d.path().to_path_buf() },
             reader: &TestReader,
             tokenizer: std::sync::Arc::new(T0),
             clipboard: Some(&cb),
         };
-        let stats = run_app(deps, &["**/*".to_string()], None, false, false, false).unwrap();
-        assert_eq!(stats.lines, 1);
+        let opts = Options::new(["**/*"]).head_lines(0);
+        let stats = run_app(deps, &opts).unwrap();
+        assert_eq!((stats.files, stats.lines), (1, 1));
+        // Bytes are what was emitted: the header and the truncation marker, not the file's size on disk.
+        let copied = cb.0.lock().unwrap().clone().unwrap();
+        assert!(copied.ends_with("a.txt\n… (2 more lines)\n\n\n"), "{}", copied);
+        assert_eq!(stats.bytes, copied.len() as u64);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn positional_shim_keeps_the_old_arguments() {
+        let d = tempdir().unwrap();
+        fs::write(d.path().join("a.txt"), "x\n").unwrap();
+        let out = d.path().join("out.md");
+        let deps = Deps { walker: &FixedWalker { root: d.path().to_path_buf() }, reader: &TestReader, tokenizer: std::sync::Arc::new(T0), clipboard: None };
+        let patterns = vec!["*.txt".to_string()];
+        let stats = run_app_positional(deps, &patterns, Some(out.as_path()), false, false, true, false, false).unwrap();
+        assert_eq!(stats.files, 1);
+        let written = fs::read_to_string(&out).unwrap();
+        assert!(written.contains("a.txt\nx\n"), "{}", written);
+    }
+
+    #[test]
+    fn tee_sends_identical_content_to_file_and_clipboard() {
+        let d = tempdir().unwrap();
+        fs::create_dir(d.path().join("src")).unwrap();
+        fs::write(d.path().join("src").join("a.txt"), "alpha\n").unwrap();
+        fs::write(d.path().join("src").join("b.txt"), "beta\n").unwrap();
+        let out = d.path().join("out.txt");
+        let cb = NoopClipboard(Mutex::new(None));
+        let deps = Deps {
+            walker: &FixedWalker { root: d.path().join("src") },
+            reader: &TestReader,
+            tokenizer: std::sync::Arc::new(T0),
+            clipboard: Some(&cb),
+        };
+        let opts = Options::new(["**/*.txt"]).output(out.clone()).tee(true);
+        run_app(deps, &opts).unwrap();
+        let copied = cb.0.lock().unwrap().clone().unwrap();
+        assert!(copied.contains("alpha") && copied.contains("beta"));
+        assert_eq!(fs::read_to_string(&out).unwrap(), copied);
+    }
+
+    fn run_with_limit(dir: &Path, cb: &NoopClipboard, strict: bool) -> Result<Stats, LfError> {
+        let deps = Deps {
+            walker: &FixedWalker { root: dir.to_path_buf() },
+            reader: &TestReader,
+            tokenizer: std::sync::Arc::new(T0),
+            clipboard: Some(cb),
+        };
+        let opts = Options::new(["**/*"]).clipboard_limit(8).clipboard_limit_strict(strict);
+        run_app(deps, &opts)
+    }
+
+    #[test]
+    fn clipboard_limit_falls_back_without_copying() {
+        let d = tempdir().unwrap();
+        fs::write(d.path().join("big.txt"), "more than eight bytes\n").unwrap();
+        let cb = NoopClipboard(Mutex::new(None));
+        let stats = run_with_limit(d.path(), &cb, false).unwrap();
+        assert!(stats.clipboard_fallback);
+        assert!(cb.0.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn clipboard_limit_strict_fails() {
+        let d = tempdir().unwrap();
+        fs::write(d.path().join("big.txt"), "more than eight bytes\n").unwrap();
+        let cb = NoopClipboard(Mutex::new(None));
+        let err = run_with_limit(d.path(), &cb, true).unwrap_err();
+        assert!(err.to_string().contains("clipboard limit"));
+        assert!(cb.0.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn collect_returns_entries_without_output() {
+        let d = tempdir().unwrap();
+        fs::create_dir(d.path().join("src")).unwrap();
+        fs::write(d.path().join("src").join("a.txt"), "one\ntwo\n").unwrap();
+        fs::write(d.path().join("logo.png"), [0u8, 1, 2]).unwrap();
+        let deps = Deps {
+            walker: &FixedWalker { root: d.path().to_path_buf() },
+            reader: &TestReader,
+            tokenizer: std::sync::Arc::new(T0),
+            clipboard: None,
+        };
+        let opts = Options::new(["**/*"]).dir(d.path());
+        let mut agg = collect(&deps, &opts).unwrap();
+        agg.files.sort_by(|a, b| a.display.cmp(&b.display));
+        let shown: Vec<(&str, FileKind, usize)> = agg.files.iter().map(|f| (f.display.as_str(), f.kind, f.lines)).collect();
+        assert_eq!(shown, vec![("logo.png", FileKind::Binary, 0), ("src/a.txt", FileKind::Text, 2)]);
+        assert!(agg.files[1].content.starts_with("one\ntwo\n"));
+        assert_eq!(agg.lines, 2);
+        assert_eq!(agg.matched.len(), 2);
+    }
+
+    #[test]
+    fn options_builder_defaults_and_setters() {
+        let o = Options::new(["src", "~src/gen"]);
+        assert_eq!(o.patterns, vec!["src".to_string(), "~src/gen".to_string()]);
+        assert!(o.dirs.is_empty() && o.output.is_none() && o.clipboard_limit.is_none() && o.head_lines.is_none());
+        assert!(!o.no_clipboard && !o.mask_java_imports && !o.no_gitignore && !o.tee && !o.append);
+        assert_eq!(o.format, Format::Plain);
+        assert_eq!(o.path_style, PathStyle::Relative);
+
+        let o = Options::new(vec![String::from("*.rs")]).output("out.txt").mask_java_imports(true).no_gitignore(true).dir("a").dir("b").head_lines(5);
+        assert_eq!(o.output.as_deref(), Some(Path::new("out.txt")));
+        assert!(o.mask_java_imports && o.no_gitignore && !o.no_clipboard);
+        assert_eq!(o.dirs, vec![PathBuf::from("a"), PathBuf::from("b")]);
+        assert_eq!(o.head_lines, Some(5));
+    }
+
+    #[test]
+    fn run_with_streams_events_in_order() {
+        let d = tempdir().unwrap();
+        for i in 0..20 { fs::write(d.path().join(format!("f{}.txt", i)), "x\n".repeat(i + 1)).unwrap(); }
+        let deps = Deps {
+            walker: &FixedWalker { root: d.path().to_path_buf() },
+            reader: &TestReader,
+            tokenizer: std::sync::Arc::new(T0),
+            clipboard: None,
+        };
+        let mut events = Vec::new();
+        let stats = run_with(deps, &Options::new(["*.txt"]).dir(d.path()).count_only(true), |e| events.push(e)).unwrap();
+        let pos = |want: &dyn Fn(&FileEvent) -> bool| events.iter().position(want).unwrap();
+        for i in 0..20 {
+            let p = d.path().join(format!("f{}.txt", i));
+            let matched = pos(&|e| matches!(e, FileEvent::Matched(m) if *m == p));
+            let processed = pos(&|e| matches!(e, FileEvent::Processed { path, lines, .. } if *path == p && *lines == i + 1));
+            assert!(matched < processed);
+        }
+        assert_eq!(events.len(), 41);
+        assert!(matches!(events.last(), Some(FileEvent::Done(s)) if s.files == 20 && s.lines == stats.lines));
+    }
+
+    struct SlowReader(std::sync::atomic::AtomicUsize);
+    impl FileReader for SlowReader {
+        fn read_to_string(&self, path: &Path) -> Result<(String, usize)> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            TestReader.read_to_string(path)
+        }
+    }
+
+    #[test]
+    fn cancellation_stops_the_run_early() {
+        let d = tempdir().unwrap();
+        for i in 0..2000 { fs::write(d.path().join(format!("f{}.txt", i)), "x\n").unwrap(); }
+        let reader = SlowReader(Default::default());
+        let deps = Deps {
+            walker: &FixedWalker { root: d.path().to_path_buf() },
+            reader: &reader,
+            tokenizer: std::sync::Arc::new(T0),
+            clipboard: None,
+        };
+        let cancel = Arc::new(AtomicBool::new(false));
+        let opts = Options::new(["*.txt"]).dir(d.path()).cancel(cancel.clone());
+        let flag = cancel.clone();
+        let setter = std::thread::spawn(move || { std::thread::sleep(std::time::Duration::from_millis(30)); flag.store(true, Ordering::SeqCst); });
+        let err = collect(&deps, &opts).unwrap_err();
+        setter.join().unwrap();
+        assert!(matches!(err, LfError::Cancelled));
+        assert!(reader.0.load(Ordering::SeqCst) < 2000);
+
+        let err = collect(&deps, &opts).unwrap_err();
+        assert!(matches!(err, LfError::Cancelled));
+    }
+
+    struct FailingReader;
+    impl FileReader for FailingReader {
+        fn read_to_string(&self, path: &Path) -> Result<(String, usize)> {
+            let e = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+            Err(anyhow::Error::new(e).context(format!("Failed to open file: {}", path.display())))
+        }
+    }
+
+    fn run_err(dir: &Path, reader: &dyn FileReader, opts: Options) -> LfError {
+        let deps = Deps { walker: &FixedWalker { root: dir.to_path_buf() }, reader, tokenizer: std::sync::Arc::new(T0), clipboard: None };
+        run_app(deps, &opts.dir(dir)).unwrap_err()
+    }
+
+    #[test]
+    fn failures_map_to_error_variants() {
+        let d = tempdir().unwrap();
+        fs::write(d.path().join("a.txt"), "a\n").unwrap();
+        let e = run_err(d.path(), &TestReader, Options::new(["src/[a"]));
+        assert!(matches!(&e, LfError::InvalidPattern { pattern, .. } if pattern == "src/[a"));
+        assert_eq!(e.exit_code(), 3);
+        let e = run_err(d.path(), &TestReader, Options::new(["*.md"]));
+        assert!(matches!(e, LfError::NoMatches));
+        assert_eq!(e.exit_code(), 2);
+        let missing = d.path().join("missing").join("out.txt");
+        let e = run_err(d.path(), &TestReader, Options::new(["*.txt"]).output(missing.clone()));
+        assert!(matches!(&e, LfError::OutputIo { path, .. } if *path == missing));
+        let e = run_err(d.path(), &FailingReader, Options::new(["*.txt"]));
+        assert!(matches!(&e, LfError::ReadError { source, .. } if source.kind() == std::io::ErrorKind::PermissionDenied));
+        let e = run_err(d.path(), &TestReader, Options::new(["*.txt"]).toc(true));
+        assert!(matches!(e, LfError::InvalidOption(_)));
+        let e = run_err(d.path(), &TestReader, Options::new(["*.txt"]).clipboard_limit(1).clipboard_limit_strict(true));
+        assert!(matches!(e, LfError::ClipboardLimit { limit: 1, .. }));
+    }
+
+    #[test]
+    fn tokenizer_is_not_built_unless_something_is_counted() {
+        use crate::tokenizer::LazyTokenizer;
+        use std::sync::atomic::AtomicUsize;
+        let d = tempdir().unwrap();
+        fs::write(d.path().join("a.txt"), "a\n").unwrap();
+        let built = Arc::new(AtomicUsize::new(0));
+        let run = |opts: Options| {
+            let counter = built.clone();
+            let tokenizer = Arc::new(LazyTokenizer::new("counted", move || { counter.fetch_add(1, Ordering::SeqCst); Ok(Arc::new(T0)) }));
+            let deps = Deps { walker: &FixedWalker { root: d.path().to_path_buf() }, reader: &TestReader, tokenizer, clipboard: None };
+            run_app(deps, &opts.dir(d.path()).no_clipboard(true))
+        };
+        assert!(matches!(run(Options::new(["*.md"])), Err(LfError::NoMatches)));
+        assert_eq!(run(Options::new(["*.txt"]).dry_run(true)).unwrap().files, 1);
+        assert_eq!(built.load(Ordering::SeqCst), 0);
+        run(Options::new(["*.txt"]).count_only(true)).unwrap();
+        assert_eq!(built.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn tokenizer_build_failure_is_an_error() {
+        use crate::tokenizer::LazyTokenizer;
+        let d = tempdir().unwrap();
+        fs::write(d.path().join("a.txt"), "a\n").unwrap();
+        let tokenizer = Arc::new(LazyTokenizer::new("broken", || anyhow::bail!("ranks missing")));
+        let deps = Deps { walker: &FixedWalker { root: d.path().to_path_buf() }, reader: &TestReader, tokenizer, clipboard: None };
+        let e = run_app(deps, &Options::new(["*.txt"]).dir(d.path()).no_clipboard(true)).unwrap_err();
+        assert!(matches!(e, LfError::Other(_)) && e.exit_code() == 1, "{:?}", e);
+        assert_eq!(e.to_string(), "failed to initialize the broken tokenizer: ranks missing");
+    }
+
+    struct CountingWalker { inner: FixedWalker, stats: std::sync::atomic::AtomicUsize }
+    impl WalkerFactory for CountingWalker {
+        fn build(&self, root: &Path, no_gitignore: bool) -> ignore::Walk { self.inner.build(root, no_gitignore) }
+        fn metadata(&self, entry: &ignore::DirEntry) -> Option<std::fs::Metadata> {
+            self.stats.fetch_add(1, Ordering::SeqCst);
+            entry.metadata().ok()
+        }
+    }
+
+    #[test]
+    fn each_matched_file_is_stat_once() {
+        let d = tempdir().unwrap();
+        fs::write(d.path().join("a.txt"), "a\n").unwrap();
+        fs::write(d.path().join("b.txt"), "bb\n").unwrap();
+        fs::write(d.path().join("c.png"), [0u8; 8]).unwrap();
+        fs::write(d.path().join("skipped.md"), "x\n").unwrap();
+        let walker = CountingWalker { inner: FixedWalker { root: d.path().to_path_buf() }, stats: Default::default() };
+        let deps = Deps { walker: &walker, reader: &TestReader, tokenizer: Arc::new(T0), clipboard: None };
+        let opts = Options::new(["*.txt", "*.png"]).dir(d.path()).no_clipboard(true).recent(10).max_total_bytes(1 << 20).embed_binaries_under(4);
+        let stats = run_app(deps, &opts).unwrap();
+        assert_eq!(stats.files, 3);
+        assert_eq!(walker.stats.load(Ordering::SeqCst), 3);
+    }
+
+    struct LenTokenizer;
+    impl Tokenizer for LenTokenizer { fn count_tokens(&self, text: &str) -> usize { text.len() } }
+
+    #[test]
+    fn files_over_the_per_file_token_limit_become_placeholders() {
+        let d = tempdir().unwrap();
+        fs::write(d.path().join("big.txt"), "x".repeat(1199)).unwrap();
+        fs::write(d.path().join("small.txt"), "tiny").unwrap();
+        let out = d.path().join("out.txt");
+        let deps = Deps { walker: &FixedWalker { root: d.path().to_path_buf() }, reader: &TestReader, tokenizer: Arc::new(LenTokenizer), clipboard: None };
+        let opts = Options::new(["*.txt"]).dir(d.path()).output(out.clone()).warn_tokens_per_file(3).skip_tokens_per_file(1000);
+        let stats = run_app(deps, &opts).unwrap();
+        let text = fs::read_to_string(&out).unwrap();
+        let placeholder = "[Skipped: 1,200 tokens, exceeds per-file limit]\n";
+        assert!(text.contains(placeholder) && !text.contains("xxx"));
+        assert!(text.contains("tiny"));
+        assert_eq!((stats.skipped_token_limit, stats.skipped_token_limit_tokens), (1, 1200));
+        let big = stats.entries.iter().find(|e| e.path.ends_with("big.txt")).unwrap();
+        assert_eq!((big.tokens, big.omitted.as_deref()), (placeholder.len(), Some("token-limit")));
+    }
+
+    struct FlakyClipboard { failures: std::sync::atomic::AtomicUsize, copied: Mutex<Option<String>> }
+    impl ClipboardSink for FlakyClipboard {
+        fn set_text(&self, text: String) -> Result<(), String> {
+            if self.failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() { return Err("clipboard busy".to_string()); }
+            *self.copied.lock().unwrap() = Some(text);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn flaky_clipboards_are_retried_and_failures_do_not_flood_stdout() {
+        use crate::clipboard::RetryClipboard;
+        let d = tempdir().unwrap();
+        fs::write(d.path().join("a.txt"), "a\n").unwrap();
+        let run = |failures: usize, opts: Options| {
+            let cb = RetryClipboard { inner: FlakyClipboard { failures: failures.into(), copied: Mutex::new(None) }, attempts: 3, backoff: std::time::Duration::ZERO };
+            let deps = Deps { walker: &FixedWalker { root: d.path().to_path_buf() }, reader: &TestReader, tokenizer: Arc::new(T0), clipboard: Some(&cb) };
+            let result = run_app(deps, &opts.dir(d.path()));
+            (result, cb.inner.copied.into_inner().unwrap())
+        };
+        let (result, copied) = run(2, Options::new(["*.txt"]));
+        assert!(result.is_ok() && copied.is_some_and(|c| c.contains("a.txt")));
+        let (result, copied) = run(3, Options::new(["*.txt"]));
+        let e = result.unwrap_err();
+        assert!(matches!(&e, LfError::ClipboardFailed(msg) if msg == "clipboard busy (after 3 attempts)"), "{:?}", e);
+        assert_eq!((e.exit_code(), copied), (8, None));
+        assert!(run(3, Options::new(["*.txt"]).fallback_stdout(true)).0.is_ok());
+    }
+
+    struct HtmlClipboard(Mutex<Option<(String, String)>>);
+    impl ClipboardSink for HtmlClipboard {
+        fn set_text(&self, _: String) -> Result<(), String> { Err("plain text only".to_string()) }
+        fn set_html(&self, html: String, alt_text: String) -> Result<(), String> { *self.0.lock().unwrap() = Some((html, alt_text)); Ok(()) }
+    }
+
+    #[test]
+    fn clipboard_html_sets_both_flavors_and_falls_back_to_text() {
+        let d = tempdir().unwrap();
+        fs::write(d.path().join("a.rs"), "fn f() -> Vec<u8> { vec![] }\n").unwrap();
+        let walker = FixedWalker { root: d.path().to_path_buf() };
+        let opts = Options::new(["*.rs"]).dir(d.path()).clipboard_html(true);
+        let cb = HtmlClipboard(Mutex::new(None));
+        run_app(Deps { walker: &walker, reader: &TestReader, tokenizer: Arc::new(T0), clipboard: Some(&cb) }, &opts).unwrap();
+        let (html, text) = cb.0.into_inner().unwrap().unwrap();
+        assert!(html.starts_with("<h3>a.rs</h3>\n<pre><code>fn f() -&gt; Vec&lt;u8&gt;"), "{}", html);
+        assert!(text.starts_with("a.rs\nfn f() -> Vec<u8>"), "{}", text);
+
+        let plain = NoopClipboard(Mutex::new(None));
+        run_app(Deps { walker: &walker, reader: &TestReader, tokenizer: Arc::new(T0), clipboard: Some(&plain) }, &opts).unwrap();
+        assert!(plain.0.into_inner().unwrap().is_some_and(|t| t.contains("Vec<u8>")));
+        let e = run_app(Deps { walker: &walker, reader: &TestReader, tokenizer: Arc::new(T0), clipboard: None }, &opts.clone().no_clipboard(true)).unwrap_err();
+        assert_eq!(e.exit_code(), 3);
+    }
+
+    struct RecordingFormatter(Arc<Mutex<Vec<String>>>);
+    impl Formatter for RecordingFormatter {
+        fn begin(&mut self, out: &mut dyn Write, meta: &RunMeta) -> std::io::Result<()> {
+            self.0.lock().unwrap().push(format!("begin {:?} {}", meta.displays, meta.lines));
+            out.write_all(b"<<\n")
+        }
+        fn entry(&mut self, out: &mut dyn Write, e: &Entry) -> std::io::Result<()> {
+            self.0.lock().unwrap().push(format!("entry {} {}/{}", e.display, e.index, e.total));
+            writeln!(out, "* {}", e.display)
+        }
+        fn finish(&mut self, out: &mut dyn Write, stats: &Stats) -> std::io::Result<()> {
+            self.0.lock().unwrap().push(format!("finish {}", stats.files));
+            out.write_all(b">>\n")
+        }
+    }
+
+    #[test]
+    fn custom_formatter_sees_begin_entries_and_finish_in_order() {
+        let d = tempdir().unwrap();
+        fs::write(d.path().join("b.txt"), "b\n").unwrap();
+        fs::write(d.path().join("a.txt"), "a\na\n").unwrap();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorder = calls.clone();
+        let out = d.path().join("out.txt");
+        let opts = Options { output: Some(out.clone()), ..Options::new(["*.txt"]).dir(d.path()) }.formatter(move || Box::new(RecordingFormatter(recorder.clone())));
+        run_app(Deps { walker: &FixedWalker { root: d.path().to_path_buf() }, reader: &TestReader, tokenizer: Arc::new(T0), clipboard: None }, &opts).unwrap();
+        let calls = calls.lock().unwrap();
+        let (x, y) = if calls[1].starts_with("entry a.txt") { ("a.txt", "b.txt") } else { ("b.txt", "a.txt") };
+        assert_eq!(*calls, [format!("begin [\"{x}\", \"{y}\"] 3"), format!("entry {x} 1/2"), format!("entry {y} 2/2"), "finish 2".to_string()]);
+        assert_eq!(fs::read_to_string(&out).unwrap(), format!("<<\n* {x}\n* {y}\n>>\n"));
+    }
+
+    struct Upper;
+    impl Transform for Upper {
+        fn apply(&self, _path: &Path, content: String) -> String { content.to_uppercase() }
+    }
+
+    #[test]
+    fn custom_transforms_run_on_every_text_file() {
+        let d = tempdir().unwrap();
+        fs::write(d.path().join("a.txt"), "quiet\n").unwrap();
+        let opts = Options::new(["*.txt"]).dir(d.path()).transform(Stage::Redact, Upper);
+        let agg = collect(&Deps { walker: &FixedWalker { root: d.path().to_path_buf() }, reader: &TestReader, tokenizer: Arc::new(T0), clipboard: None }, &opts).unwrap();
+        assert_eq!(agg.files[0].content, "QUIET\n\n");
+    }
+
+    // Reads the file, then has another thread rewrite it before returning, like an editor saving mid-read.
+    struct RacingReader {
+        races: std::sync::atomic::AtomicUsize,
+        inside: std::sync::mpsc::Sender<()>,
+        swapped: Mutex<std::sync::mpsc::Receiver<()>>,
+    }
+    impl FileReader for RacingReader {
+        fn read_to_string(&self, path: &Path) -> anyhow::Result<(String, usize)> {
+            let read = TestReader.read_to_string(path)?;
+            if self.races.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+                self.inside.send(()).unwrap();
+                self.swapped.lock().unwrap().recv().unwrap();
+            }
+            Ok(read)
+        }
+    }
+
+    fn race(races: usize) -> (Stats, String) {
+        let d = tempdir().unwrap();
+        let file = d.path().join("a.txt");
+        fs::write(&file, "old\n").unwrap();
+        let (inside, inside_rx) = std::sync::mpsc::channel();
+        let (swapped_tx, swapped) = std::sync::mpsc::channel();
+        let writer = std::thread::spawn(move || {
+            for (i, ()) in inside_rx.iter().enumerate() {
+                fs::write(&file, format!("new {}\n", "!".repeat(i + 1))).unwrap();
+                swapped_tx.send(()).unwrap();
+            }
+        });
+        let reader = RacingReader { races: races.into(), inside, swapped: Mutex::new(swapped) };
+        let cb = NoopClipboard(Mutex::new(None));
+        let deps = Deps { walker: &FixedWalker { root: d.path().to_path_buf() }, reader: &reader, tokenizer: Arc::new(T0), clipboard: Some(&cb) };
+        let stats = run_app(deps, &Options::new(["*.txt"]).dir(d.path())).unwrap();
+        drop(reader);
+        writer.join().unwrap();
+        (stats, cb.0.into_inner().unwrap().unwrap())
+    }
+
+    #[test]
+    fn files_changed_while_read_are_read_again() {
+        let (stats, copied) = race(1);
+        assert!(copied.contains("a.txt\nnew !\n"), "{}", copied);
+        assert!(!copied.contains("old") && !copied.contains(CHANGED_DURING_RUN));
+        assert_eq!(stats.changed_during_run, 0);
+    }
+
+    #[test]
+    fn files_that_keep_changing_are_noted_and_counted() {
+        let (stats, copied) = race(MAX_READS);
+        assert!(copied.contains(&format!("a.txt [{}]\nnew !!\n", CHANGED_DURING_RUN)), "{}", copied);
+        assert_eq!(stats.changed_during_run, 1);
     }
-}
\ No newline at end of file
+}
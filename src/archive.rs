@@ -0,0 +1,68 @@
+use anyhow::Result;
+use std::path::Path;
+
+/// The archives `--expand-archives` can open, told apart by file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+pub fn archive_kind(name: &Path) -> Option<ArchiveKind> {
+    let name = name.file_name()?.to_string_lossy().to_lowercase();
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") { Some(ArchiveKind::TarGz) }
+    else if name.ends_with(".tar") { Some(ArchiveKind::Tar) }
+    else if name.ends_with(".zip") || name.ends_with(".jar") { Some(ArchiveKind::Zip) }
+    else { None }
+}
+
+/// The name and bytes of every file in the archive at `path` that `keep` accepts, in archive order.
+/// Directories and nested archives are left out.
+#[cfg(feature = "archive")]
+pub fn read_entries(path: &Path, kind: ArchiveKind, mut keep: impl FnMut(&str) -> bool) -> Result<Vec<(String, Vec<u8>)>> {
+    use anyhow::Context;
+    use std::io::Read;
+    let file = std::fs::File::open(path).with_context(|| format!("Failed to open archive: {}", path.display()))?;
+    let mut wanted = |name: &str| {
+        if archive_kind(Path::new(name)).is_some() {
+            tracing::info!(archive = %path.display(), entry = name, "skipped: nested archive");
+            return false;
+        }
+        keep(name)
+    };
+    let mut entries = Vec::new();
+    match kind {
+        ArchiveKind::Zip => {
+            let mut zip = zip::ZipArchive::new(file).with_context(|| format!("Failed to read archive: {}", path.display()))?;
+            for i in 0..zip.len() {
+                let mut e = zip.by_index(i).with_context(|| format!("Failed to read entry {} of: {}", i, path.display()))?;
+                if e.is_dir() || !wanted(e.name()) { continue; }
+                let mut bytes = Vec::with_capacity(e.size() as usize);
+                e.read_to_end(&mut bytes).with_context(|| format!("Failed to read {} in: {}", e.name(), path.display()))?;
+                entries.push((e.name().to_string(), bytes));
+            }
+        }
+        ArchiveKind::Tar | ArchiveKind::TarGz => {
+            let reader: Box<dyn Read> = if kind == ArchiveKind::TarGz { Box::new(flate2::read::GzDecoder::new(file)) } else { Box::new(file) };
+            let mut tar = tar::Archive::new(reader);
+            for e in tar.entries().with_context(|| format!("Failed to read archive: {}", path.display()))? {
+                let mut e = e.with_context(|| format!("Failed to read archive: {}", path.display()))?;
+                if !e.header().entry_type().is_file() { continue; }
+                let name = e.path().with_context(|| format!("Bad entry name in: {}", path.display()))?.to_string_lossy().trim_start_matches("./").to_string();
+                if !wanted(&name) { continue; }
+                let mut bytes = Vec::with_capacity(e.size() as usize);
+                e.read_to_end(&mut bytes).with_context(|| format!("Failed to read {} in: {}", name, path.display()))?;
+                entries.push((name, bytes));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(not(feature = "archive"))]
+pub fn read_entries(_path: &Path, _kind: ArchiveKind, _keep: impl FnMut(&str) -> bool) -> Result<Vec<(String, Vec<u8>)>> {
+    anyhow::bail!("reading archive entries needs a build with the archive feature")
+}
+
+include!("archive_tests.rs");
@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_archive_kinds() {
+        assert_eq!(archive_kind(Path::new("dist/release-src.TAR.GZ")), Some(ArchiveKind::TarGz));
+        assert_eq!(archive_kind(Path::new("src.tgz")), Some(ArchiveKind::TarGz));
+        assert_eq!(archive_kind(Path::new("src.tar")), Some(ArchiveKind::Tar));
+        assert_eq!(archive_kind(Path::new("library-sources.jar")), Some(ArchiveKind::Zip));
+        assert_eq!(archive_kind(Path::new("notes.gz")), None);
+        assert_eq!(archive_kind(Path::new("tar")), None);
+    }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn reads_kept_zip_entries_and_skips_nested_archives() {
+        use std::io::Write;
+        let d = tempfile::tempdir().unwrap();
+        let path = d.path().join("lib.jar");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.add_directory("com/", options).unwrap();
+        for (name, body) in [("com/A.java", "class A {}\n"), ("README", "read me\n"), ("deps/inner.zip", "PK")] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(body.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let entries = read_entries(&path, ArchiveKind::Zip, |name| name != "README").unwrap();
+        assert_eq!(entries, vec![("com/A.java".to_string(), b"class A {}\n".to_vec())]);
+    }
+}
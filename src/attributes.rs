@@ -0,0 +1,73 @@
+use crate::fs::WalkerFactory;
+use globset::{GlobBuilder, GlobMatcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const OMIT_ATTRS: [&str; 2] = ["linguist-generated", "linguist-vendored"];
+
+struct AttrRule {
+    base: PathBuf,
+    glob: GlobMatcher,
+    attrs: Vec<(String, Option<bool>)>,
+}
+
+#[derive(Default)]
+pub struct GitAttributes {
+    rules: Vec<AttrRule>,
+}
+
+fn parse_attr(a: &str) -> Option<(String, Option<bool>)> {
+    let (name, value) = if let Some(n) = a.strip_prefix('-') { (n, Some(false)) }
+        else if let Some(n) = a.strip_prefix('!') { (n, None) }
+        else if let Some((n, v)) = a.split_once('=') { (n, Some(!matches!(v, "false" | "0"))) }
+        else { (a, Some(true)) };
+    OMIT_ATTRS.contains(&name).then(|| (name.to_string(), value))
+}
+
+impl GitAttributes {
+    pub fn parse(&mut self, base: &Path, text: &str) {
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let mut parts = line.split_whitespace();
+            let Some(pattern) = parts.next() else { continue };
+            let attrs: Vec<_> = parts.filter_map(parse_attr).collect();
+            if attrs.is_empty() || pattern.ends_with('/') { continue; }
+            let glob = match pattern.strip_prefix('/') {
+                Some(anchored) => anchored.to_string(),
+                None if pattern.contains('/') => pattern.to_string(),
+                None => format!("**/{}", pattern),
+            };
+            let Ok(glob) = GlobBuilder::new(&glob).literal_separator(true).build() else { continue };
+            self.rules.push(AttrRule { base: base.to_path_buf(), glob: glob.compile_matcher(), attrs });
+        }
+    }
+
+    pub fn discover(walker: &dyn WalkerFactory, root: &Path, no_gitignore: bool) -> Self {
+        let mut files: Vec<PathBuf> = walker.build(root, no_gitignore).filter_map(|e| e.ok())
+            .filter(|e| e.file_name() == ".gitattributes" && e.file_type().is_some_and(|t| t.is_file()))
+            .map(|e| e.into_path()).collect();
+        files.sort_by_key(|p| (p.components().count(), p.clone()));
+        let mut attrs = GitAttributes::default();
+        for f in files {
+            let base = f.parent().unwrap_or(root).strip_prefix(root).unwrap_or(Path::new("")).to_path_buf();
+            match std::fs::read_to_string(&f) {
+                Ok(text) => attrs.parse(&base, &text),
+                Err(e) => tracing::warn!(path = %f.display(), error = %e, "could not read .gitattributes"),
+            }
+        }
+        attrs
+    }
+
+    pub fn is_generated(&self, rel: &Path) -> bool {
+        let mut state: HashMap<&str, Option<bool>> = HashMap::new();
+        for rule in &self.rules {
+            let Ok(sub) = rel.strip_prefix(&rule.base) else { continue };
+            if !rule.glob.is_match(sub) { continue; }
+            for (name, value) in &rule.attrs { state.insert(name, *value); }
+        }
+        state.values().any(|v| *v == Some(true))
+    }
+}
+
+include!("attributes_tests.rs");
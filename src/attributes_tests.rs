@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(files: &[(&str, &str)]) -> GitAttributes {
+        let mut a = GitAttributes::default();
+        for (base, text) in files { a.parse(Path::new(base), text); }
+        a
+    }
+
+    #[test]
+    fn generated_and_vendored_patterns() {
+        let a = attrs(&[("", "*.pb.go linguist-generated=true\n/vendor/** linguist-vendored\npackage-lock.json linguist-generated\n# *.rs linguist-generated\n")]);
+        assert!(a.is_generated(Path::new("api/v1/user.pb.go")));
+        assert!(a.is_generated(Path::new("vendor/lib/x.js")));
+        assert!(a.is_generated(Path::new("web/package-lock.json")));
+        assert!(!a.is_generated(Path::new("api/v1/user.go")));
+        assert!(!a.is_generated(Path::new("src/vendor/x.js")));
+        assert!(!a.is_generated(Path::new("src/main.rs")));
+    }
+
+    #[test]
+    fn later_and_nested_rules_override() {
+        let a = attrs(&[("", "*.js linguist-generated\n"), ("web", "keep.js -linguist-generated\nsrc/*.ts linguist-generated=true\n")]);
+        assert!(a.is_generated(Path::new("web/app.js")));
+        assert!(!a.is_generated(Path::new("web/keep.js")));
+        assert!(a.is_generated(Path::new("keep.js")));
+        assert!(a.is_generated(Path::new("web/src/a.ts")));
+        assert!(!a.is_generated(Path::new("web/src/deep/a.ts")));
+        let unset = attrs(&[("", "*.js linguist-generated\nx.js !linguist-generated\nfalse.js linguist-generated=false\n")]);
+        assert!(!unset.is_generated(Path::new("x.js")) && !unset.is_generated(Path::new("false.js")));
+    }
+}
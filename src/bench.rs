@@ -0,0 +1,62 @@
+//! Fixtures and entry points for the criterion benches in `benches/`, so they time the real walk,
+//! match and per-file pipeline instead of the binary. Built with the `bench` feature.
+use crate::app::{FileEntry, Options};
+use crate::fs::{Candidate, StdWalkerFactory};
+use crate::patterns::{PatternOptions, PatternSet};
+use crate::tokenizer::Tokenizer;
+use anyhow::Result;
+use std::path::Path;
+
+/// Directories `synth_tree` fills alongside `src/` that `heavy_excludes` prunes again.
+const NOISE_DIRS: [&str; 3] = ["target", "node_modules", "vendor"];
+
+/// `len` bytes of plausible Rust source (indented, with tabs and a license header) for the transforms to chew on.
+pub fn synth_text(len: usize) -> String {
+    let mut text = String::from("// Copyright (c) 2024 Example\n// SPDX-License-Identifier: MIT\n\n");
+    for i in 0.. {
+        if text.len() >= len { break; }
+        text.push_str(&format!("    fn item_{i}(x: usize) -> usize {{\n\t\tlet y = x * {i} + 1; // keep {i}\n        y.wrapping_add(x)\n    }}\n"));
+    }
+    text.truncate(len);
+    text
+}
+
+/// Writes `files` files of `len` bytes under `root`, a third of them in directories `heavy_excludes` drops.
+pub fn synth_tree(root: &Path, files: usize, len: usize) -> std::io::Result<()> {
+    let body = synth_text(len);
+    for i in 0..files {
+        let dir = match i % 3 {
+            0 => root.join(NOISE_DIRS[i / 3 % NOISE_DIRS.len()]).join(format!("m{}", i % 7)),
+            _ => root.join("src").join(format!("m{}", i % 11)).join(format!("n{}", i % 5)),
+        };
+        std::fs::create_dir_all(&dir)?;
+        let ext = if i % 2 == 0 { "rs" } else { "txt" };
+        std::fs::write(dir.join(format!("f{i}.{ext}")), &body)?;
+    }
+    Ok(())
+}
+
+/// A broad include followed by many excludes: the pattern shape that makes matching the hot path.
+pub fn heavy_excludes() -> Vec<String> {
+    let mut patterns = vec!["**/*".to_string()];
+    patterns.extend(NOISE_DIRS.iter().map(|d| format!("~{}/", d)));
+    patterns.extend((0..40).map(|i| format!("~**/generated_{i}/**")));
+    patterns.extend(["~**/*.lock", "~**/*.min.js", "~**/*.snap", "~**/.cache/**"].map(String::from));
+    patterns
+}
+
+/// The text transforms a typical prompt-building run enables.
+pub fn transform_options() -> Options {
+    Options::new(["**/*"]).strip_license_headers(true).dedent(true).expand_tabs(4).max_line_length(120).line_numbers(true)
+}
+
+pub fn collect_matching_files(root: &Path, patterns: &[String]) -> Result<Vec<Candidate>> {
+    let sets = PatternSet::compile(patterns, &PatternOptions { gitignore: false, root: root.to_path_buf() })?;
+    crate::app::collect_matching_files(&StdWalkerFactory, root, &sets, true, None, &Options::new(patterns.iter().cloned()), None)
+}
+
+pub fn process_file(path: &Path, opts: &Options, tokenizer: &dyn Tokenizer) -> Result<Option<FileEntry>> {
+    crate::app::process_one(path, opts, tokenizer)
+}
+
+include!("bench_tests.rs");
@@ -0,0 +1,28 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::{make_tokenizer, ApproxTokenizer};
+
+    #[test]
+    fn bench_scenarios_run_against_the_real_pipeline() {
+        let d = tempfile::tempdir().unwrap();
+        synth_tree(d.path(), 30, 2048).unwrap();
+        let found = collect_matching_files(d.path(), &heavy_excludes()).unwrap();
+        assert_eq!(found.len(), 20);
+        assert!(found.iter().all(|c| c.path.strip_prefix(d.path()).unwrap().starts_with("src") && c.size() == 2048));
+
+        let text = synth_text(64 * 1024);
+        assert_eq!(text.len(), 64 * 1024);
+        let path = d.path().join("big.rs");
+        std::fs::write(&path, &text).unwrap();
+        let plain = process_file(&path, &Options::new(["**/*"]), &ApproxTokenizer).unwrap().unwrap();
+        let transformed = process_file(&path, &transform_options(), &ApproxTokenizer).unwrap().unwrap();
+        assert_eq!(plain.content.trim_end(), text.trim_end());
+        assert!(transformed.content.trim_start().starts_with("1 | "));
+        assert!(!transformed.content.contains('\t') && !transformed.content.contains("SPDX"));
+
+        let bpe = make_tokenizer(None).unwrap();
+        assert!(ApproxTokenizer.count_tokens(&text) > 0);
+        assert!(cfg!(not(feature = "token-counting")) || bpe.count_tokens(&text) > 0);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::units::{format_size, SizeUnits};
 use anyhow::{Context, Result};
 use std::path::Path;
 
@@ -19,19 +20,188 @@ pub fn is_binary_file(path: &Path) -> bool {
     }
 }
 
-pub fn get_binary_file_info(path: &Path) -> Result<String> {
-    let metadata = std::fs::metadata(path)
+#[derive(Debug, Clone, Default)]
+pub struct BinaryOverrides {
+    text: Vec<String>,
+    binary: Vec<String>,
+}
+
+impl BinaryOverrides {
+    pub fn new<S: AsRef<str>>(text: &[S], binary: &[S]) -> Self {
+        let norm = |exts: &[S]| exts.iter().map(|e| e.as_ref().trim_start_matches('.').to_lowercase()).collect();
+        BinaryOverrides { text: norm(text), binary: norm(binary) }
+    }
+
+    fn ext(path: &Path) -> Option<String> { path.extension().map(|e| e.to_string_lossy().to_lowercase()) }
+
+    pub fn forces_text(&self, path: &Path) -> bool { Self::ext(path).is_some_and(|e| self.text.contains(&e)) }
+
+    pub fn is_binary(&self, path: &Path) -> bool {
+        if self.forces_text(path) { return false; }
+        Self::ext(path).is_some_and(|e| self.binary.contains(&e)) || is_binary_file(path)
+    }
+}
+
+pub fn sha256_file(path: &Path) -> Result<String> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+    let mut file = std::fs::File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).with_context(|| format!("Failed to read file: {}", path.display()))?;
+        if n == 0 { break; }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+pub fn with_hash(info: &str, hash: &str) -> String {
+    match info.split_once('\n') {
+        Some((first, rest)) => format!("{} sha256:{}\n{}", first, hash, rest),
+        None => format!("{} sha256:{}", info, hash),
+    }
+}
+
+pub const BASE64_WIDTH: usize = 76;
+
+pub fn encode_base64(bytes: &[u8]) -> String {
+    use base64::Engine;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    let mut out = String::with_capacity(encoded.len() + encoded.len() / BASE64_WIDTH + 1);
+    for chunk in encoded.as_bytes().chunks(BASE64_WIDTH) {
+        out.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        out.push('\n');
+    }
+    out
+}
+
+pub fn embed_binary(path: &Path, display: &str, units: SizeUnits) -> Result<String> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read file: {}", path.display()))?;
+    Ok(format!("[Binary file: {} — {}, base64 below]\n```base64\n{}```\n", display, format_size(bytes.len() as u64, units), encode_base64(&bytes)))
+}
+
+pub const ARCHIVE_LISTING_LIMIT: usize = 50;
+
+#[cfg(feature = "archive-info")]
+fn archive_entries(path: &Path, ext: &str) -> Option<Vec<(String, u64)>> {
+    let file = std::fs::File::open(path).ok()?;
+    match ext {
+        "zip" | "jar" => {
+            let mut zip = zip::ZipArchive::new(file).ok()?;
+            let mut entries = Vec::new();
+            for i in 0..zip.len() {
+                let e = zip.by_index_raw(i).ok()?;
+                if !e.is_dir() { entries.push((e.name().to_string(), e.size())); }
+            }
+            Some(entries)
+        }
+        "tar" => {
+            let mut tar = tar::Archive::new(file);
+            let mut entries = Vec::new();
+            for e in tar.entries().ok()? {
+                let e = e.ok()?;
+                if e.header().entry_type().is_dir() { continue; }
+                entries.push((e.path().ok()?.to_string_lossy().into_owned(), e.size()));
+            }
+            Some(entries)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "archive-info"))]
+fn archive_entries(_path: &Path, _ext: &str) -> Option<Vec<(String, u64)>> { None }
+
+fn archive_listing(path: &Path, size: u64, entries: &[(String, u64)], units: SizeUnits) -> String {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut s = format!("[Archive: {} — {}, {} entries]", name, format_size(size, units), entries.len());
+    for (entry, len) in entries.iter().take(ARCHIVE_LISTING_LIMIT) { s.push_str(&format!("\n  {} ({})", entry, format_size(*len, units))); }
+    if entries.len() > ARCHIVE_LISTING_LIMIT { s.push_str(&format!("\n  … ({} more entries)", entries.len() - ARCHIVE_LISTING_LIMIT)); }
+    s
+}
+
+const IMAGE_HEADER_BYTES: u64 = 64 * 1024;
+
+fn be16(b: &[u8], i: usize) -> Option<u32> { b.get(i..i + 2).map(|s| u16::from_be_bytes([s[0], s[1]]) as u32) }
+fn le16(b: &[u8], i: usize) -> Option<u32> { b.get(i..i + 2).map(|s| u16::from_le_bytes([s[0], s[1]]) as u32) }
+fn le24(b: &[u8], i: usize) -> Option<u32> { b.get(i..i + 3).map(|s| u32::from_le_bytes([s[0], s[1], s[2], 0])) }
+
+fn jpeg_dimensions(b: &[u8]) -> Option<(u32, u32)> {
+    let mut i = 2;
+    loop {
+        while *b.get(i)? == 0xFF && *b.get(i + 1)? == 0xFF { i += 1; }
+        if *b.get(i)? != 0xFF { return None; }
+        let marker = *b.get(i + 1)?;
+        if matches!(marker, 0x01 | 0xD0..=0xD7) { i += 2; continue; }
+        if matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) { return Some((be16(b, i + 7)?, be16(b, i + 5)?)); }
+        i += 2 + be16(b, i + 2)? as usize;
+    }
+}
+
+pub fn image_dimensions(b: &[u8]) -> Option<(&'static str, u32, u32)> {
+    if b.starts_with(b"\x89PNG\r\n\x1a\n") && b.get(12..16) == Some(b"IHDR") {
+        let w = u32::from_be_bytes(b.get(16..20)?.try_into().ok()?);
+        let h = u32::from_be_bytes(b.get(20..24)?.try_into().ok()?);
+        return Some(("PNG", w, h));
+    }
+    if b.starts_with(b"GIF87a") || b.starts_with(b"GIF89a") { return Some(("GIF", le16(b, 6)?, le16(b, 8)?)); }
+    if b.starts_with(b"BM") {
+        let w = i32::from_le_bytes(b.get(18..22)?.try_into().ok()?);
+        let h = i32::from_le_bytes(b.get(22..26)?.try_into().ok()?);
+        return Some(("BMP", w.unsigned_abs(), h.unsigned_abs()));
+    }
+    if b.starts_with(b"\xFF\xD8") { return jpeg_dimensions(b).map(|(w, h)| ("JPEG", w, h)); }
+    if b.starts_with(b"RIFF") && b.get(8..12) == Some(b"WEBP") {
+        let (w, h) = match b.get(12..16)? {
+            b"VP8 " => (le16(b, 26)? & 0x3FFF, le16(b, 28)? & 0x3FFF),
+            b"VP8L" => {
+                let v = u32::from_le_bytes(b.get(21..25)?.try_into().ok()?);
+                ((v & 0x3FFF) + 1, ((v >> 14) & 0x3FFF) + 1)
+            }
+            b"VP8X" => (le24(b, 24)? + 1, le24(b, 27)? + 1),
+            _ => return None,
+        };
+        return Some(("WEBP", w, h));
+    }
+    None
+}
+
+fn read_image_header(path: &Path) -> Option<(&'static str, u32, u32)> {
+    use std::io::Read;
+    let mut buf = Vec::new();
+    std::fs::File::open(path).ok()?.take(IMAGE_HEADER_BYTES).read_to_end(&mut buf).ok()?;
+    image_dimensions(&buf)
+}
+
+pub fn get_binary_file_info(path: &Path, units: SizeUnits) -> Result<String> {
+    let metadata = std::fs::metadata(crate::fs::long_path(path))
         .with_context(|| format!("Failed to get metadata for: {}", path.display()))?;
-    let size = metadata.len();
-    let size_str = if size < 1024 {
-        format!("{} bytes", size)
-    } else if size < 1024 * 1024 {
-        format!("{:.1} KB", size as f64 / 1024.0)
-    } else if size < 1024 * 1024 * 1024 {
-        format!("{:.1} MB", size as f64 / (1024.0 * 1024.0))
-    } else {
-        format!("{:.1} GB", size as f64 / (1024.0 * 1024.0 * 1024.0))
-    };
+    binary_file_info(path, metadata.len(), units)
+}
+
+/// `get_binary_file_info` for a file whose size is already known.
+pub fn binary_file_info(path: &Path, size: u64, units: SizeUnits) -> Result<String> {
+    let size_str = format_size(size, units);
+    let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if let Some(entries) = archive_entries(path, &ext) { return Ok(archive_listing(path, size, &entries, units)); }
+    if matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp") && let Some((format, w, h)) = read_image_header(path) {
+        return Ok(format!("[Image file: {} {}x{} - Size: {}]", format, w, h, size_str));
+    }
+    Ok(generic_info(path, &size_str))
+}
+
+/// A placeholder for binary content that isn't on disk (a git blob), so only the extension and size are known.
+pub fn blob_info(path: &Path, size: u64, units: SizeUnits) -> String {
+    generic_info(path, &format_size(size, units))
+}
+
+/// Whether `bytes` look binary: a NUL in the first 8 KiB, as git decides.
+pub fn sniff_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8000).any(|&b| b == 0)
+}
+
+fn generic_info(path: &Path, size_str: &str) -> String {
     if let Some(ext) = path.extension() {
         let ext = ext.to_string_lossy().to_lowercase();
         let kind = match ext.as_str() {
@@ -43,8 +213,10 @@ pub fn get_binary_file_info(path: &Path) -> Result<String> {
             "pdf"|"doc"|"docx"|"xls"|"xlsx"|"ppt"|"pptx" => "Document file",
             _ => "Binary file",
         };
-        Ok(format!("[{}: {}]", kind, size_str))
+        format!("[{}: {}]", kind, size_str)
     } else {
-        Ok(format!("[Binary file - Size: {}]", size_str))
+        format!("[Binary file - Size: {}]", size_str)
     }
-}
\ No newline at end of file
+}
+
+include!("binary_tests.rs");
@@ -15,12 +15,107 @@ mod tests {
         assert!(is_binary_file(&jar));
     }
 
+    #[test]
+    fn overrides_adjust_classification() {
+        let o = BinaryOverrides::new(&["pdf", ".JSON"], &["json", "dat"]);
+        assert!(!o.is_binary(Path::new("doc.pdf")));
+        assert!(o.forces_text(Path::new("doc.PDF")));
+        assert!(!o.is_binary(Path::new("a.json")));
+        assert!(o.is_binary(Path::new("blob.dat")));
+        assert!(o.is_binary(Path::new("app.exe")));
+        assert!(!o.is_binary(Path::new("notes.txt")));
+        assert!(!BinaryOverrides::default().is_binary(Path::new("Makefile")));
+    }
+
+    #[test]
+    fn archive_listing_caps_entries() {
+        let entries: Vec<(String, u64)> = (0..ARCHIVE_LISTING_LIMIT + 3).map(|i| (format!("f{}.class", i), 3174)).collect();
+        let s = archive_listing(Path::new("out/lib.jar"), 1_300_000, &entries, SizeUnits::Binary);
+        assert!(s.starts_with("[Archive: lib.jar — 1.2 MiB, 53 entries]\n  f0.class (3.1 KiB)\n"), "{}", s);
+        assert!(s.ends_with("\n  … (3 more entries)"));
+        assert_eq!(s.lines().count(), ARCHIVE_LISTING_LIMIT + 2);
+    }
+
+    #[cfg(feature = "archive-info")]
+    #[test]
+    fn corrupt_archives_fall_back_to_size() {
+        let d = tempdir().unwrap();
+        let f = d.path().join("broken.zip");
+        fs::write(&f, b"PK\x03\x04 not really").unwrap();
+        assert_eq!(get_binary_file_info(&f, SizeUnits::Binary).unwrap(), "[Archive file: 15 bytes]");
+    }
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\x02\0\0\0\x01\x80\x08\x06\0\0\0";
+    const GIF: &[u8] = b"GIF89a\x10\0\x20\0\x80\0\0";
+    const BMP: &[u8] = b"BM\x46\0\0\0\0\0\0\0\x36\0\0\0\x28\0\0\0\x03\0\0\0\xfe\xff\xff\xff\x01\0\x18\0";
+    const JPEG: &[u8] = b"\xff\xd8\xff\xe0\0\x10JFIF\0\x01\x01\0\0\x01\0\x01\0\0\xff\xc0\0\x11\x08\x04\x38\x07\x80\x03\x01\x22\0";
+    const WEBP_LOSSY: &[u8] = b"RIFF\x24\0\0\0WEBPVP8 \x18\0\0\0\x30\x01\0\x9d\x01\x2a\x40\x01\xf0\0";
+    const WEBP_LOSSLESS: &[u8] = b"RIFF\x1a\0\0\0WEBPVP8L\x0d\0\0\0\x2f\x3f\xc0\x0f\0";
+    const WEBP_EXTENDED: &[u8] = b"RIFF\x1e\0\0\0WEBPVP8X\x0a\0\0\0\x10\0\0\0\xff\x01\0\x7f\x01\0";
+
+    #[test]
+    fn reads_image_headers() {
+        assert_eq!(image_dimensions(PNG), Some(("PNG", 512, 384)));
+        assert_eq!(image_dimensions(GIF), Some(("GIF", 16, 32)));
+        assert_eq!(image_dimensions(BMP), Some(("BMP", 3, 2)));
+        assert_eq!(image_dimensions(JPEG), Some(("JPEG", 1920, 1080)));
+        assert_eq!(image_dimensions(WEBP_LOSSY), Some(("WEBP", 320, 240)));
+        assert_eq!(image_dimensions(WEBP_LOSSLESS), Some(("WEBP", 64, 64)));
+        assert_eq!(image_dimensions(WEBP_EXTENDED), Some(("WEBP", 512, 384)));
+    }
+
+    #[test]
+    fn truncated_image_headers_degrade_to_plain_placeholder() {
+        for bytes in [&PNG[..20], &GIF[..7], &BMP[..20], &JPEG[..22], &WEBP_LOSSY[..25], b"not an image"] {
+            assert_eq!(image_dimensions(bytes), None);
+        }
+        let d = tempdir().unwrap();
+        let f = d.path().join("logo.png");
+        fs::write(&f, &PNG[..20]).unwrap();
+        assert_eq!(get_binary_file_info(&f, SizeUnits::Binary).unwrap(), "[Image file: 20 bytes]");
+        fs::write(&f, PNG).unwrap();
+        assert_eq!(get_binary_file_info(&f, SizeUnits::Binary).unwrap(), "[Image file: PNG 512x384 - Size: 29 bytes]");
+    }
+
+    #[test]
+    fn hashes_files_in_chunks() {
+        let d = tempdir().unwrap();
+        let f = d.path().join("blob.bin");
+        fs::write(&f, b"abc").unwrap();
+        assert_eq!(sha256_file(&f).unwrap(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        fs::write(&f, vec![b'a'; 200_000]).unwrap();
+        assert_eq!(sha256_file(&f).unwrap(), "2287d207f24a941ff3b56c04c8a25ad56b63e3023207b3bb5b4ac0c9869d74be");
+        assert_eq!(with_hash("[Archive: a.zip]\n  x (1 bytes)", "ab"), "[Archive: a.zip] sha256:ab\n  x (1 bytes)");
+    }
+
+    #[test]
+    fn base64_round_trips_and_wraps() {
+        use base64::Engine;
+        let bytes: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        let encoded = encode_base64(&bytes);
+        assert!(encoded.lines().all(|l| l.len() <= BASE64_WIDTH));
+        assert_eq!(encoded.lines().next().unwrap().len(), BASE64_WIDTH);
+        let joined: String = encoded.lines().collect();
+        assert_eq!(base64::engine::general_purpose::STANDARD.decode(joined).unwrap(), bytes);
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"hi"), "aGk=\n");
+    }
+
     #[test]
     fn formats_binary_info() {
         let d = tempdir().unwrap();
         let f = d.path().join("file.bin");
         fs::write(&f, vec![0u8; 2048]).unwrap();
-        let s = get_binary_file_info(&f).unwrap();
+        let s = get_binary_file_info(&f, SizeUnits::Binary).unwrap();
         assert!(s.contains("Binary file") || s.contains("Document file") || s.contains("Archive file") || s.contains("Image file") || s.contains("Audio file") || s.contains("Video file"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn sniffs_blobs_and_describes_them_by_extension() {
+        assert!(sniff_binary(b"ab\0cd"));
+        assert!(!sniff_binary("plain text ü\n".as_bytes()));
+        assert!(!sniff_binary(&[vec![b'a'; 8000], vec![0]].concat()));
+        assert_eq!(blob_info(Path::new("logo.png"), 2048, SizeUnits::Binary), "[Image file: 2.0 KiB]");
+        assert_eq!(blob_info(Path::new("data"), 5, SizeUnits::Binary), "[Binary file - Size: 5 bytes]");
+    }
+}
@@ -0,0 +1,49 @@
+use crate::error::LfError;
+use crate::tokenizer::Tokenizer;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Chunk {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub tokens: usize,
+    pub text: String,
+}
+
+pub fn check_overlap(size: usize, overlap: usize) -> Result<(), LfError> {
+    if size == 0 { return Err(LfError::InvalidOption("--chunk-tokens must be at least 1".to_string())); }
+    if overlap >= size { return Err(LfError::InvalidOption(format!("--chunk-overlap ({}) must be smaller than --chunk-tokens ({})", overlap, size))); }
+    Ok(())
+}
+
+/// Splits `content` on line boundaries into chunks of at most `size` tokens, each starting with up to
+/// `overlap` tokens of the previous chunk's tail. A single line over `size` becomes a chunk of its own.
+pub fn chunk_file(path: &str, content: &str, tokenizer: &dyn Tokenizer, size: usize, overlap: usize) -> Result<Vec<Chunk>, LfError> {
+    check_overlap(size, overlap)?;
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let counts: Vec<usize> = lines.iter().map(|l| tokenizer.count_tokens(l)).collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let mut end = start;
+        let mut used = 0;
+        while end < lines.len() && (end == start || used + counts[end] <= size) {
+            used += counts[end];
+            end += 1;
+        }
+        let text = lines[start..end].concat();
+        chunks.push(Chunk { path: path.to_string(), start_line: start + 1, end_line: end, tokens: tokenizer.count_tokens(&text), text });
+        if end == lines.len() { break; }
+        let mut next = end;
+        let mut carried = 0;
+        while next > start + 1 && carried + counts[next - 1] <= overlap {
+            carried += counts[next - 1];
+            next -= 1;
+        }
+        start = next;
+    }
+    Ok(chunks)
+}
+
+include!("chunk_tests.rs");
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::ApproxTokenizer;
+
+    // Every line is "abcdefg\n": 8 chars, 2 approx tokens.
+    fn lines(n: usize) -> String { "abcdefg\n".repeat(n) }
+
+    fn ranges(chunks: &[Chunk]) -> Vec<(usize, usize)> {
+        chunks.iter().map(|c| (c.start_line, c.end_line)).collect()
+    }
+
+    #[test]
+    fn small_file_is_one_chunk() {
+        let chunks = chunk_file("a.txt", &lines(3), &ApproxTokenizer, 100, 10).unwrap();
+        assert_eq!(chunks, vec![Chunk { path: "a.txt".into(), start_line: 1, end_line: 3, tokens: 6, text: lines(3) }]);
+        assert!(chunk_file("empty.txt", "", &ApproxTokenizer, 100, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn exact_multiple_splits_evenly() {
+        let chunks = chunk_file("a.txt", &lines(6), &ApproxTokenizer, 4, 0).unwrap();
+        assert_eq!(ranges(&chunks), vec![(1, 2), (3, 4), (5, 6)]);
+        assert!(chunks.iter().all(|c| c.tokens == 4 && c.text == lines(2)));
+    }
+
+    #[test]
+    fn overlap_repeats_trailing_lines() {
+        let chunks = chunk_file("a.txt", &lines(6), &ApproxTokenizer, 6, 2).unwrap();
+        assert_eq!(ranges(&chunks), vec![(1, 3), (3, 5), (5, 6)]);
+    }
+
+    #[test]
+    fn oversized_line_is_its_own_chunk() {
+        let content = format!("{}\nshort\n", "x".repeat(100));
+        let chunks = chunk_file("a.txt", &content, &ApproxTokenizer, 4, 1).unwrap();
+        assert_eq!(ranges(&chunks), vec![(1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn overlap_must_be_smaller_than_chunk() {
+        let err = chunk_file("a.txt", &lines(3), &ApproxTokenizer, 4, 8).unwrap_err();
+        assert_eq!(err.to_string(), "--chunk-overlap (8) must be smaller than --chunk-tokens (4)");
+        assert!(matches!(chunk_file("a.txt", "x\n", &ApproxTokenizer, 4, 4), Err(LfError::InvalidOption(_))));
+    }
+}
@@ -1,3 +1,11 @@
+use crate::clipboard::ClipboardBackend;
+use crate::config::Config;
+use crate::app::Options;
+use crate::binary::BinaryOverrides;
+use crate::format::{Format, HeaderFields, PathStyle, Template, Templates};
+use crate::error::LfError;
+use crate::tokenizer::TokenizerKind;
+use crate::transform::{Replacement, DEFAULT_REGION_MARKERS};
 use clap::Parser;
 use std::path::PathBuf;
 
@@ -6,12 +14,360 @@ use std::path::PathBuf;
 #[command(about = "A fast file aggregation tool with glob patterns and tokenization. Hidden paths are skipped unless a pattern containing a '.' at the first path-segment is supplied.")]
 pub struct Args {
     pub patterns: Vec<String>,
+    #[arg(short = 'C', long = "dir", value_name = "DIR", help = "Walk DIR instead of the current directory; paths are shown relative to it. Repeat to combine several roots")]
+    pub dirs: Vec<PathBuf>,
+    #[arg(long, value_name = "PATH", help = "Explain why PATH is included or excluded instead of emitting content (repeatable)")]
+    pub explain: Vec<PathBuf>,
+    #[arg(long, conflicts_with = "explain", help = "Explain the verdict for every file under the root instead of emitting content")]
+    pub explain_all: bool,
+    #[arg(long, value_name = "PATTERN", help = "Exclude PATTERN, same as a ~PATTERN argument (repeatable; LF_EXCLUDE takes a ':'-separated list)")]
+    pub exclude: Vec<String>,
+    #[arg(long, help = "Fail when no pattern is given instead of defaulting to '**/*'")]
+    pub require_patterns: bool,
+    #[arg(short, long, action = clap::ArgAction::Count, help = "Log selection decisions to stderr (-v skipped files, -vv every match decision)")]
+    pub verbose: u8,
     #[arg(short, long)]
     pub output: Option<PathBuf>,
-    #[arg(short, long)]
+    #[arg(long, requires = "output", help = "Append to the output file instead of truncating it (created if missing)")]
+    pub append: bool,
+    #[arg(long, requires = "output", help = "Also copy the output to the clipboard when writing to a file (wins over --no-clipboard and LF_NO_CLIPBOARD)")]
+    pub tee: bool,
+    #[arg(short, long, env = "LF_NO_CLIPBOARD", value_parser = clap::builder::FalseyValueParser::new())]
+    #[cfg_attr(not(feature = "clipboard"), arg(help = "Always on: this build has no clipboard support (the `clipboard` feature is off)"))]
     pub no_clipboard: bool,
+    #[arg(long, value_enum, default_value_t = ClipboardBackend::Auto, help = "Clipboard backend: auto tries the system clipboard, then OSC 52 when a terminal is attached")]
+    pub clipboard_backend: ClipboardBackend,
+    #[arg(long, value_name = "COMMAND", env = "LF_CLIPBOARD_CMD", help = "Pipe the output into this command instead of using a clipboard backend, e.g. 'wl-copy'")]
+    pub clipboard_cmd: Option<String>,
+    #[arg(long, value_name = "PATH", help = "Config file to use instead of ./.lf.toml or ~/.config/lf/config.toml")]
+    pub config: Option<PathBuf>,
+    #[arg(long, help = "Truncate OSC 52 payloads to the terminal limit instead of refusing them")]
+    pub osc52_truncate: bool,
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, default_value = "10MiB", help = "Largest payload to put on the clipboard (e.g. 512K, 10MiB; K/M/G are 1000-based, KiB/MiB/GiB 1024-based); larger output goes to stdout instead")]
+    pub clipboard_limit: u64,
+    #[arg(long, help = "Fail instead of falling back to stdout when --clipboard-limit is exceeded")]
+    pub clipboard_limit_strict: bool,
+    #[arg(long, value_enum, help = "Tokenizer used for token counts [default: o200k, or none in builds without token-counting]")]
+    pub tokenizer: Option<TokenizerKind>,
+    #[arg(long, help = "Print estimated input cost for common models to stderr (prices can be overridden under [prices] in the config file)")]
+    pub estimate_cost: bool,
+    #[arg(long, value_name = "NAME", help = "Warn on stderr when the token total exceeds this model's context window (extend under [models] in the config file)")]
+    pub target_model: Option<String>,
+    #[arg(long, value_name = "TOKENS", conflicts_with = "target_model", help = "Warn on stderr when the token total exceeds this many tokens")]
+    pub target_context: Option<usize>,
+    #[arg(long, help = "Exit non-zero when the token total exceeds --target-model/--target-context")]
+    pub fit: bool,
+    #[arg(long, value_name = "N", help = "Keep only the first N lines of each text file")]
+    pub head_lines: Option<usize>,
+    #[arg(long, value_name = "N", help = "Cut lines longer than N characters, noting how many were dropped")]
+    pub max_line_length: Option<usize>,
+    #[arg(long, value_name = "WIDTH", num_args = 0..=1, require_equals = true, default_missing_value = "4", help = "Turn leading tabs into spaces at WIDTH-column tab stops [default: 4] (breaks Makefile recipes, which need real tabs)")]
+    pub expand_tabs: Option<usize>,
+    #[arg(long, requires = "expand_tabs", help = "With --expand-tabs, also expand tabs after the first non-whitespace character")]
+    pub expand_all_tabs: bool,
+    #[arg(long, help = "Remove the indentation shared by all non-blank lines of each file")]
+    pub dedent: bool,
+    #[arg(long, value_name = "N", help = "Keep the header and first N rows of CSV/TSV files, and the first N elements/lines of JSON/JSONL files over 16 KB")]
+    pub sample_data: Option<usize>,
+    #[arg(long, help = "Remove ANSI escape sequences (colors, cursor moves, OSC titles/links) from text files; off by default")]
+    pub strip_ansi: bool,
+    #[arg(long, help = "Remove leading ---/+++ front-matter and HTML comments from .md/.mdx files")]
+    pub strip_frontmatter: bool,
+    #[arg(long, value_name = "TEXT|@FILE", help = "Text (or @file contents) placed before the output, separated by a blank line (repeatable)")]
+    pub prefix: Vec<String>,
+    #[arg(long, value_name = "TEXT|@FILE", help = "Text (or @file contents) placed after the output, separated by a blank line (repeatable)")]
+    pub suffix: Vec<String>,
+    #[arg(long, value_name = "N", help = "With --format json, emit chunks of at most N tokens split on line boundaries ({path, start_line, end_line, tokens, text})")]
+    pub chunk_tokens: Option<usize>,
+    #[arg(long, value_name = "M", default_value_t = 0, requires = "chunk_tokens", help = "Tokens each chunk repeats from the end of the previous one")]
+    pub chunk_overlap: usize,
+    #[arg(long, help = "Emit files whose content repeats an earlier file (in output order) as [identical to PATH]")]
+    pub dedupe_content: bool,
+    #[arg(long, value_name = "N", help = "Keep only the N most recently modified matching files, newest first")]
+    pub recent: Option<usize>,
+    #[arg(long, help = "Drop files that are empty or whitespace-only after transforms")]
+    pub skip_empty: bool,
+    #[arg(long, help = "Select files and print counts, sizes, a bytes/4 token estimate and the largest files, without reading content or writing output")]
+    pub dry_run: bool,
+    #[arg(long, help = "Compare the output with the existing -o FILE instead of writing it; exit 1 if it is missing or differs")]
+    pub check: bool,
+    #[arg(long, help = "With -o, remember the matched files' sizes and mtimes in FILE.lf-state and skip the run when nothing changed")]
+    pub incremental: bool,
+    #[arg(long, num_args = 2, value_names = ["OLD", "NEW"], help = "Compare two --format json/jsonl snapshots: files added, removed and changed, with token deltas")]
+    pub diff: Vec<PathBuf>,
+    #[arg(long, value_name = "OLD", help = "After generating, compare this run with a --format json/jsonl snapshot")]
+    pub diff_against: Option<PathBuf>,
+    #[arg(long, help = "Exit 1 when --diff or --diff-against finds any change")]
+    pub fail_on_change: bool,
+    #[arg(long, value_name = "N", help = "Stop selecting files after the first N (in output order); the rest are listed on stderr")]
+    pub max_files: Option<usize>,
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, help = "Stop selecting files once their on-disk sizes add up to more than SIZE (e.g. 50MB or 50MiB)")]
+    pub max_total_bytes: Option<u64>,
+    #[arg(long, value_name = "N", help = "Stop emitting files once their tokens add up to more than N")]
+    pub max_tokens: Option<usize>,
+    #[arg(long, value_name = "N", help = "Within each top-level directory, emit files in path order until their tokens add up to more than N; --max-tokens still caps the total")]
+    pub max_tokens_per_dir: Option<usize>,
+    #[arg(long, value_name = "N", requires = "max_tokens_per_dir", help = "Budget --max-tokens-per-dir over the first N directory levels instead of the top-level one")]
+    pub budget_depth: Option<usize>,
+    #[arg(long, help = "Exit 7 when --max-files, --max-total-bytes, --max-tokens or --max-tokens-per-dir left files out")]
+    pub strict_limits: bool,
+    #[arg(long, help = "Sort files by path and split the output into one section per top-level directory, each ending with line/token subtotals")]
+    pub group_by_dir: bool,
+    #[arg(long, value_name = "N", requires = "group_by_dir", help = "Group by the first N directory levels instead of the top-level one")]
+    pub group_depth: Option<usize>,
+    #[arg(long, help = "Print walk/read/transform/tokenize timings and the 10 slowest files to stderr")]
+    pub timings: bool,
+    #[arg(long, value_name = "N", help = "Warn on stderr about files whose token count exceeds N")]
+    pub warn_tokens_per_file: Option<usize>,
+    #[arg(long, value_name = "N", help = "Replace files whose token count exceeds N with a one-line placeholder")]
+    pub skip_tokens_per_file: Option<usize>,
+    #[arg(long, help = "Show sizes in 1000-based KB/MB instead of 1024-based KiB/MiB")]
+    pub si_units: bool,
+    #[arg(long, help = "After the run, list on stderr which files were left out and why (gitignore, ~ patterns, hidden, too large, binary)")]
+    pub show_skipped: bool,
+    #[arg(long, value_name = "N", num_args = 0..=1, require_equals = true, default_missing_value = "20", help = "After copying to the clipboard, show its first N lines (default 20) on stderr")]
+    pub preview: Option<usize>,
+    #[arg(long, help = "When copying to the clipboard fails, print the output to stdout instead of exiting with code 8")]
+    pub fallback_stdout: bool,
+    #[arg(long, help = "Also copy the files as HTML (a heading and code block per file) for pasting into rich-text editors")]
+    pub clipboard_html: bool,
+    #[arg(long, help = "Emit files in the order of the include pattern that matched them (then by path), instead of walk order")]
+    pub pattern_order: bool,
+    #[arg(long, help = "Fail (exit 2) when any include pattern matches no files, instead of warning")]
+    pub strict_patterns: bool,
+    #[arg(long, value_name = "NAME", default_value = "<stdin>", help = "Name shown for the file read from stdin by a '-' pattern (its extension picks the fence language)")]
+    pub stdin_name: String,
+    #[arg(long, num_args = 2, value_names = ["START", "END"], help = "Lines containing these markers enclose a region replaced by an elision note (default: lf:skip-start lf:skip-end)")]
+    pub region_markers: Option<Vec<String>>,
+    #[arg(long, conflicts_with = "region_markers", help = "Ignore region markers and include marked regions")]
+    pub no_region_markers: bool,
+    #[arg(long, help = "Prefix each emitted line with its 1-based line number (counted in tokens)")]
+    pub line_numbers: bool,
     #[arg(long)]
     pub mask_java_imports: bool,
+    #[arg(long, value_name = "EXT,...", value_delimiter = ',', help = "Read files with these extensions as text (invalid UTF-8 replaced), even if they look binary; wins over --treat-as-binary")]
+    pub treat_as_text: Vec<String>,
+    #[arg(long, value_name = "EXT,...", value_delimiter = ',', help = "Show a binary placeholder for files with these extensions instead of their content")]
+    pub treat_as_binary: Vec<String>,
+    #[arg(long, help = "Append the SHA-256 of each binary file to its placeholder")]
+    pub hash_binaries: bool,
+    #[arg(long, help = "Leave binary files out of the output and the per-file counts")]
+    pub skip_binary: bool,
+    #[arg(long, conflicts_with = "skip_binary", help = "Emit only binary files (placeholders), e.g. to inventory assets")]
+    pub binary_only: bool,
+    #[arg(long, help = "Emit the plain text of PDF and DOCX files instead of a placeholder (needs the doc-extract feature)")]
+    pub extract_documents: bool,
+    #[arg(long, help = "Emit .ipynb files as raw JSON instead of flattening them to their cell sources")]
+    pub raw_notebooks: bool,
+    #[arg(long, help = "Emit only signatures and structure for Rust, Python, Java and TS/JS files, eliding function bodies")]
+    pub outline: bool,
+    #[arg(long, help = "Remove #[cfg(test)] modules and #[test] functions from .rs files")]
+    pub strip_rust_tests: bool,
+    #[arg(long, help = "Replace a leading license comment block (Licensed under, Copyright (c), SPDX-License-Identifier) with a one-line marker")]
+    pub strip_license_headers: bool,
+    #[arg(long, num_args = 2, value_names = ["REGEX", "REPLACEMENT"], help = "Replace REGEX matches in text files (repeatable, applied in order; $1 refers to capture groups)")]
+    pub replace: Vec<String>,
+    #[arg(long, value_name = "NAME", help = "Apply the [profiles.NAME] section of the config file (its replacements run before --replace)")]
+    pub profile: Option<String>,
+    #[arg(long, help = "Emit only lines containing TODO, FIXME, HACK or XXX as path:line: text")]
+    pub todos: bool,
+    #[arg(long, value_name = "TAG,...", value_delimiter = ',', requires = "todos", help = "Tags searched by --todos (plain substrings, case-sensitive)")]
+    pub todo_tags: Vec<String>,
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, help = "Emit binary files of at most SIZE (e.g. 4KiB) as a base64 block instead of a placeholder")]
+    pub embed_binaries_under: Option<u64>,
+    #[arg(long, value_name = "FILE", help = "Write a path<TAB>size<TAB>sha256 line for every matched file to FILE")]
+    pub manifest: Option<PathBuf>,
+    #[arg(long, help = "Emit generated files in full: those marked linguist-generated/linguist-vendored in .gitattributes and those detected as minified or generated")]
+    pub include_generated: bool,
     #[arg(long)]
     pub no_gitignore: bool,
-}
\ No newline at end of file
+    #[arg(long, help = "Only consider files tracked by git (tracked files are kept even if .gitignore matches them)")]
+    pub git_only: bool,
+    #[arg(long, value_name = "REF", help = "Only consider files changed since REF (committed on this branch, staged or unstaged); deleted files are skipped")]
+    pub changed_since: Option<String>,
+    #[arg(long, conflicts_with_all = ["changed_since", "git_only"], help = "Only consider staged files and emit their staged (index) content rather than the working tree")]
+    pub staged: bool,
+    #[arg(long, value_name = "REV", conflicts_with_all = ["changed_since", "staged"], help = "List and read files from the tree of git revision REV instead of the working tree; headers show 'PATH @ REV'")]
+    pub at_rev: Option<String>,
+    #[arg(long, help = "Read the entries of zip/jar/tar/tar.gz archives named by a pattern; the other patterns then select entries, shown as ARCHIVE!ENTRY")]
+    pub expand_archives: bool,
+    #[arg(long, help = "Review the matched files in a prompt and toggle which ones to keep before aggregating")]
+    pub interactive: bool,
+    #[arg(long, help = "Only print file, line and token totals; no content is emitted or copied")]
+    pub count_only: bool,
+    #[arg(long, help = "Print a per-extension breakdown of files, lines, tokens and bytes to stderr")]
+    pub stats_by_ext: bool,
+    #[arg(long, help = "Print a per-language breakdown of files, lines, blank lines and tokens to stderr")]
+    pub lang_stats: bool,
+    #[arg(long, value_enum, default_value_t = Format::Plain, env = "LF_FORMAT", help = "Output format")]
+    pub format: Format,
+    #[arg(long, value_enum, default_value_t = PathStyle::Relative, help = "How file paths are shown in headers: relative to the cwd, absolute, or relative to the git repo root")]
+    pub path_style: PathStyle,
+    #[arg(long, help = "Start the output with a table of contents linking to each file (requires --format markdown)")]
+    pub toc: bool,
+    #[arg(long, help = "Number files [#1], [#2], ... in output order, in each header and in an index at the top (or in the --toc)")]
+    pub number_files: bool,
+    #[arg(long, value_name = "FIELDS", num_args = 0..=1, require_equals = true, default_missing_value = "all", value_parser = HeaderFields::parse, help = "Add metadata to each file header: all, or a comma list of lines,tokens,size,mtime (header text is not counted in token totals)")]
+    pub header_meta: Option<HeaderFields>,
+    #[arg(long, value_name = "TEMPLATE", help = "Per-file header for plain output, e.g. '===== FILE: {path} ====='; placeholders: {path} {lines} {tokens} {size} {ext} {index} {total}, '{{'/'}}' for literal braces")]
+    pub header_template: Option<String>,
+    #[arg(long, value_name = "TEMPLATE", help = "Per-file trailer for plain output, same placeholders as --header-template")]
+    pub footer_template: Option<String>,
+    #[arg(long, help = "Prepend a directory tree of the matched files to the output")]
+    pub tree: bool,
+    #[arg(long, conflicts_with = "tree", help = "Emit only the directory tree of the matched files")]
+    pub tree_only: bool,
+    #[arg(long, value_name = "PATH", num_args = 0..=1, require_equals = true, default_missing_value = "-", help = "Write run statistics as JSON to stderr, or to PATH with --stats-json=PATH")]
+    pub stats_json: Option<PathBuf>,
+}
+
+impl Args {
+    pub fn to_options(&self) -> anyhow::Result<Options> {
+        if let Some(rev) = self.changed_since.as_deref().filter(|r| r.starts_with('-')) {
+            return Err(LfError::InvalidOption(format!("--changed-since takes a git revision, not '{}'", rev)).into());
+        }
+        let template = |t: &Option<String>| t.as_deref().map(Template::parse).transpose().map_err(anyhow::Error::msg);
+        Ok(Options {
+            patterns: self.patterns.iter().cloned().chain(self.exclude.iter().filter(|p| !p.is_empty()).map(|p| format!("~{}", p.trim_start_matches('~')))).collect(),
+            dirs: self.dirs.clone(),
+            output: self.output.clone(),
+            append: self.append,
+            tee: self.tee,
+            // `--tee` asks for the clipboard copy explicitly, so it overrides a `--no-clipboard` default.
+            no_clipboard: self.no_clipboard && !self.tee,
+            clipboard_limit: Some(self.clipboard_limit),
+            clipboard_limit_strict: self.clipboard_limit_strict,
+            mask_java_imports: self.mask_java_imports,
+            no_gitignore: self.no_gitignore,
+            git_only: self.git_only,
+            changed_since: self.changed_since.clone(),
+            staged: self.staged,
+            at_rev: self.at_rev.clone(),
+            expand_archives: self.expand_archives,
+            interactive: self.interactive,
+            count_only: self.count_only,
+            stats_by_ext: self.stats_by_ext,
+            lang_stats: self.lang_stats,
+            tree: self.tree,
+            tree_only: self.tree_only,
+            format: self.format,
+            toc: self.toc,
+            number_files: self.number_files,
+            header_meta: self.header_meta,
+            templates: Templates { header: template(&self.header_template)?, footer: template(&self.footer_template)? },
+            head_lines: self.head_lines,
+            line_numbers: self.line_numbers,
+            path_style: self.path_style,
+            cancel: None,
+            stdin: None,
+            formatter: None,
+            transforms: Vec::new(),
+            explain: self.explain.clone(),
+            explain_all: self.explain_all,
+            include_generated: self.include_generated,
+            binary_overrides: BinaryOverrides::new(&self.treat_as_text, &self.treat_as_binary),
+            hash_binaries: self.hash_binaries,
+            skip_binary: self.skip_binary,
+            binary_only: self.binary_only,
+            extract_documents: self.extract_documents,
+            raw_notebooks: self.raw_notebooks,
+            outline: self.outline,
+            strip_rust_tests: self.strip_rust_tests,
+            strip_license_headers: self.strip_license_headers,
+            max_line_length: self.max_line_length,
+            expand_tabs: self.expand_tabs,
+            expand_all_tabs: self.expand_all_tabs,
+            dedent: self.dedent,
+            sample_data: self.sample_data,
+            strip_ansi: self.strip_ansi,
+            strip_frontmatter: self.strip_frontmatter,
+            chunk_tokens: self.chunk_tokens,
+            chunk_overlap: self.chunk_overlap,
+            dedupe_content: self.dedupe_content,
+            recent: self.recent,
+            skip_empty: self.skip_empty,
+            dry_run: self.dry_run,
+            check: self.check,
+            incremental: self.incremental,
+            diff_against: self.diff_against.clone(),
+            fail_on_change: self.fail_on_change,
+            max_files: self.max_files,
+            max_total_bytes: self.max_total_bytes,
+            max_tokens: self.max_tokens,
+            max_tokens_per_dir: self.max_tokens_per_dir,
+            budget_depth: self.budget_depth,
+            strict_limits: self.strict_limits,
+            group_by_dir: self.group_by_dir,
+            group_depth: self.group_depth,
+            timings: self.timings,
+            warn_tokens_per_file: self.warn_tokens_per_file,
+            skip_tokens_per_file: self.skip_tokens_per_file,
+            si_units: self.si_units,
+            show_skipped: self.show_skipped,
+            preview: self.preview,
+            fallback_stdout: self.fallback_stdout,
+            clipboard_html: self.clipboard_html,
+            pattern_order: self.pattern_order,
+            strict_patterns: self.strict_patterns,
+            region_markers: match self.region_markers.as_deref() {
+                _ if self.no_region_markers => None,
+                Some([start, end]) => Some((start.clone(), end.clone())),
+                _ => Some((DEFAULT_REGION_MARKERS.0.to_string(), DEFAULT_REGION_MARKERS.1.to_string())),
+            },
+            prefix: self.prefix.iter().map(|v| load_text("--prefix", v)).collect::<Result<_, _>>()?,
+            suffix: self.suffix.iter().map(|v| load_text("--suffix", v)).collect::<Result<_, _>>()?,
+            replacements: self.replace.chunks(2).map(|p| Replacement::new(&p[0], &p[1])).collect::<Result<_, _>>()?,
+            todos: self.todos,
+            todo_tags: self.todo_tags.clone(),
+            embed_binaries_under: self.embed_binaries_under,
+            manifest: self.manifest.clone(),
+        })
+    }
+
+    /// Fills in defaults from environment variables clap can't read itself: `LF_EXCLUDE` is a ':'-separated list,
+    /// but a `--exclude` value is a single pattern that may contain ':'.
+    pub fn merge_env(&mut self) {
+        if self.exclude.is_empty() && let Some(v) = std::env::var_os("LF_EXCLUDE") {
+            self.exclude = v.to_string_lossy().split(':').map(String::from).collect();
+        }
+    }
+
+    pub fn merge_config(&mut self, cfg: &Config) -> Result<(), LfError> {
+        if let Some(name) = &self.profile {
+            let profile = cfg.profiles.get(name).ok_or_else(|| LfError::InvalidOption(format!("unknown profile '{}' (not found in the config file)", name)))?;
+            self.replace.splice(0..0, profile.replace.iter().flat_map(|(re, rep)| [re.clone(), rep.clone()]));
+        }
+        if self.clipboard_cmd.is_none() { self.clipboard_cmd = cfg.clipboard_cmd.clone(); }
+        self.require_patterns |= cfg.require_patterns;
+        if self.header_template.is_none() { self.header_template = cfg.header_template.clone(); }
+        if self.footer_template.is_none() { self.footer_template = cfg.footer_template.clone(); }
+        Ok(())
+    }
+}
+
+fn load_text(flag: &str, value: &str) -> Result<String, LfError> {
+    match value.strip_prefix('@') {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| LfError::InvalidOption(format!("cannot read {} file '{}': {}", flag, path, e))),
+        None => Ok(value.to_string()),
+    }
+}
+
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let t = s.trim();
+    let split = t.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(t.len());
+    let (num, unit) = t.split_at(split);
+    let n: f64 = num.parse().map_err(|_| format!("invalid size: {}", s))?;
+    let mult: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1000,
+        "M" | "MB" => 1000 * 1000,
+        "G" | "GB" => 1000 * 1000 * 1000,
+        "KIB" => 1024,
+        "MIB" => 1024 * 1024,
+        "GIB" => 1024 * 1024 * 1024,
+        _ => return Err(format!("invalid size unit in: {}", s)),
+    };
+    Ok((n * mult as f64) as u64)
+}
+
+include!("cli_tests.rs");
@@ -1,12 +1,235 @@
+use base64::Engine;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+pub const OSC52_MAX_BYTES: usize = 100_000;
+
 pub trait ClipboardSink: Send + Sync {
     fn set_text(&self, text: String) -> Result<(), String>;
+
+    /// Sets an HTML flavor alongside `alt_text`; backends without HTML support copy just the plain text.
+    fn set_html(&self, _html: String, alt_text: String) -> Result<(), String> { self.set_text(alt_text) }
 }
 
-pub struct SystemClipboard;
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClipboardBackend {
+    #[default]
+    Auto,
+    System,
+    Osc52,
+}
 
-impl ClipboardSink for SystemClipboard {
+/// Tries `inner` up to `attempts` times, sleeping `backoff`, then twice that, and so on in between.
+pub struct RetryClipboard<S> {
+    pub inner: S,
+    pub attempts: u32,
+    pub backoff: Duration,
+}
+
+impl<S: ClipboardSink> RetryClipboard<S> {
+    fn retry(&self, mut copy: impl FnMut(&S) -> Result<(), String>) -> Result<(), String> {
+        let mut last = String::new();
+        for attempt in 1..=self.attempts.max(1) {
+            match copy(&self.inner) {
+                Ok(()) => return Ok(()),
+                Err(e) => { tracing::info!(attempt, error = %e, "clipboard attempt failed"); last = e; }
+            }
+            if attempt < self.attempts { std::thread::sleep(self.backoff * attempt); }
+        }
+        Err(if self.attempts > 1 { format!("{} (after {} attempts)", last, self.attempts) } else { last })
+    }
+}
+
+impl<S: ClipboardSink> ClipboardSink for RetryClipboard<S> {
+    fn set_text(&self, text: String) -> Result<(), String> { self.retry(|s| s.set_text(text.clone())) }
+
+    fn set_html(&self, html: String, alt_text: String) -> Result<(), String> { self.retry(|s| s.set_html(html.clone(), alt_text.clone())) }
+}
+
+// X11/Wayland clipboards intermittently refuse the first connection right after login or under XWayland.
+pub const SYSTEM_CLIPBOARD_ATTEMPTS: u32 = 3;
+pub const SYSTEM_CLIPBOARD_BACKOFF: Duration = Duration::from_millis(100);
+
+#[cfg(feature = "clipboard")]
+struct Arboard;
+
+#[cfg(feature = "clipboard")]
+impl ClipboardSink for Arboard {
     fn set_text(&self, text: String) -> Result<(), String> {
         let res = arboard::Clipboard::new().and_then(|mut c| c.set_text(text));
         res.map_err(|e| e.to_string())
     }
-}
\ No newline at end of file
+
+    fn set_html(&self, html: String, alt_text: String) -> Result<(), String> {
+        let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+        clipboard.set_html(html, Some(alt_text.clone())).or_else(|e| {
+            tracing::info!(error = %e, "HTML clipboard flavor unavailable, copying plain text");
+            clipboard.set_text(alt_text)
+        }).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "clipboard")]
+pub struct SystemClipboard;
+
+#[cfg(feature = "clipboard")]
+impl ClipboardSink for SystemClipboard {
+    fn set_text(&self, text: String) -> Result<(), String> {
+        RetryClipboard { inner: Arboard, attempts: SYSTEM_CLIPBOARD_ATTEMPTS, backoff: SYSTEM_CLIPBOARD_BACKOFF }.set_text(text)
+    }
+
+    fn set_html(&self, html: String, alt_text: String) -> Result<(), String> {
+        RetryClipboard { inner: Arboard, attempts: SYSTEM_CLIPBOARD_ATTEMPTS, backoff: SYSTEM_CLIPBOARD_BACKOFF }.set_html(html, alt_text)
+    }
+}
+
+pub struct Osc52Clipboard {
+    pub truncate: bool,
+}
+
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64::engine::general_purpose::STANDARD.encode(text))
+}
+
+fn truncate_for_osc52(text: &str) -> &str {
+    let envelope = osc52_sequence("").len();
+    let mut end = ((OSC52_MAX_BYTES - envelope) / 4 * 3).min(text.len());
+    while !text.is_char_boundary(end) { end -= 1; }
+    &text[..end]
+}
+
+impl Osc52Clipboard {
+    pub fn write_to(&self, w: &mut dyn Write, text: &str) -> Result<(), String> {
+        let mut seq = osc52_sequence(text);
+        if seq.len() > OSC52_MAX_BYTES {
+            if !self.truncate {
+                return Err(format!("OSC 52 payload is {} bytes, above the {} byte terminal limit (use --osc52-truncate to copy a prefix)", seq.len(), OSC52_MAX_BYTES));
+            }
+            let kept = truncate_for_osc52(text);
+            eprintln!("Warning: OSC 52 payload truncated to the first {} of {} bytes", kept.len(), text.len());
+            seq = osc52_sequence(kept);
+        }
+        w.write_all(seq.as_bytes()).and_then(|_| w.flush()).map_err(|e| e.to_string())
+    }
+}
+
+impl ClipboardSink for Osc52Clipboard {
+    fn set_text(&self, text: String) -> Result<(), String> {
+        #[cfg(unix)]
+        if let Ok(mut tty) = std::fs::OpenOptions::new().write(true).open("/dev/tty") {
+            return self.write_to(&mut tty, &text);
+        }
+        self.write_to(&mut std::io::stderr(), &text)
+    }
+}
+
+pub fn split_command(cmd: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut cur = String::new();
+    let mut in_word = false;
+    let mut chars = cmd.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => cur.push(c),
+                        None => return Err(format!("unterminated single quote in: {}", cmd)),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => cur.push(c),
+                            Some(c) => { cur.push('\\'); cur.push(c); }
+                            None => return Err(format!("unterminated double quote in: {}", cmd)),
+                        },
+                        Some(c) => cur.push(c),
+                        None => return Err(format!("unterminated double quote in: {}", cmd)),
+                    }
+                }
+            }
+            '\\' => { in_word = true; if let Some(c) = chars.next() { cur.push(c); } }
+            c if c.is_whitespace() => { if in_word { words.push(std::mem::take(&mut cur)); in_word = false; } }
+            c => { in_word = true; cur.push(c); }
+        }
+    }
+    if in_word { words.push(cur); }
+    if words.is_empty() { return Err("empty clipboard command".to_string()); }
+    Ok(words)
+}
+
+pub struct CommandClipboard {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl CommandClipboard {
+    pub fn parse(cmd: &str) -> Result<Self, String> {
+        let mut words = split_command(cmd)?;
+        let program = words.remove(0);
+        Ok(Self { program, args: words })
+    }
+}
+
+impl ClipboardSink for CommandClipboard {
+    fn set_text(&self, text: String) -> Result<(), String> {
+        let mut child = Command::new(&self.program).args(&self.args)
+            .stdin(Stdio::piped()).stdout(Stdio::null())
+            .spawn().map_err(|e| format!("failed to run {}: {}", self.program, e))?;
+        let written = child.stdin.take().map(|mut stdin| stdin.write_all(text.as_bytes())).unwrap_or(Ok(()));
+        let status = child.wait().map_err(|e| e.to_string())?;
+        written.map_err(|e| format!("failed to write to {}: {}", self.program, e))?;
+        if status.success() { Ok(()) } else { Err(format!("{} exited with {}", self.program, status)) }
+    }
+}
+
+pub struct ChainClipboard(pub Vec<Box<dyn ClipboardSink>>);
+
+impl ChainClipboard {
+    fn first_ok(&self, copy: impl Fn(&dyn ClipboardSink) -> Result<(), String>) -> Result<(), String> {
+        let mut errors = Vec::new();
+        for sink in &self.0 {
+            match copy(sink.as_ref()) {
+                Ok(()) => return Ok(()),
+                Err(e) => errors.push(e),
+            }
+        }
+        Err(errors.join("; "))
+    }
+}
+
+impl ClipboardSink for ChainClipboard {
+    fn set_text(&self, text: String) -> Result<(), String> { self.first_ok(|s| s.set_text(text.clone())) }
+
+    fn set_html(&self, html: String, alt_text: String) -> Result<(), String> { self.first_ok(|s| s.set_html(html.clone(), alt_text.clone())) }
+}
+
+pub fn backend_chain(backend: ClipboardBackend, has_tty: bool) -> Vec<ClipboardBackend> {
+    match backend {
+        ClipboardBackend::Auto if has_tty => vec![ClipboardBackend::System, ClipboardBackend::Osc52],
+        ClipboardBackend::Auto => vec![ClipboardBackend::System],
+        b => vec![b],
+    }
+}
+
+#[cfg(feature = "clipboard")]
+pub fn make_clipboard(backend: ClipboardBackend, osc52_truncate: bool) -> ChainClipboard {
+    use std::io::IsTerminal;
+    let has_tty = std::io::stderr().is_terminal() || std::io::stdout().is_terminal();
+    ChainClipboard(backend_chain(backend, has_tty).into_iter().map(|b| -> Box<dyn ClipboardSink> {
+        match b {
+            ClipboardBackend::Osc52 => Box::new(Osc52Clipboard { truncate: osc52_truncate }),
+            _ => Box::new(SystemClipboard),
+        }
+    }).collect())
+}
+
+include!("clipboard_tests.rs");
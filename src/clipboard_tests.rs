@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn osc52_sequence_is_base64_wrapped() {
+        assert_eq!(osc52_sequence("hello"), "\x1b]52;c;aGVsbG8=\x07");
+        assert_eq!(osc52_sequence(""), "\x1b]52;c;\x07");
+    }
+
+    #[test]
+    fn osc52_writes_sequence_to_terminal() {
+        let mut buf = Vec::new();
+        Osc52Clipboard { truncate: false }.write_to(&mut buf, "lf").unwrap();
+        assert_eq!(buf, b"\x1b]52;c;bGY=\x07");
+    }
+
+    #[test]
+    fn osc52_refuses_or_truncates_oversized_payloads() {
+        let big = "é".repeat(OSC52_MAX_BYTES);
+        let mut buf = Vec::new();
+        assert!(Osc52Clipboard { truncate: false }.write_to(&mut buf, &big).is_err());
+        assert!(buf.is_empty());
+        Osc52Clipboard { truncate: true }.write_to(&mut buf, &big).unwrap();
+        assert!(buf.len() <= OSC52_MAX_BYTES);
+        assert!(buf.starts_with(b"\x1b]52;c;") && buf.ends_with(b"\x07"));
+    }
+
+    #[test]
+    fn backend_selection() {
+        use ClipboardBackend::*;
+        assert_eq!(backend_chain(Auto, true), vec![System, Osc52]);
+        assert_eq!(backend_chain(Auto, false), vec![System]);
+        assert_eq!(backend_chain(System, true), vec![System]);
+        assert_eq!(backend_chain(Osc52, false), vec![Osc52]);
+    }
+
+    #[test]
+    fn splits_commands_without_a_shell() {
+        assert_eq!(split_command("wl-copy").unwrap(), vec!["wl-copy"]);
+        assert_eq!(split_command("xclip -selection  clipboard").unwrap(), vec!["xclip", "-selection", "clipboard"]);
+        assert_eq!(split_command(r#"tee 'my file.txt' "a \"b\"" c\ d"#).unwrap(), vec!["tee", "my file.txt", "a \"b\"", "c d"]);
+        assert_eq!(split_command("cmd ''").unwrap(), vec!["cmd", ""]);
+        assert!(split_command("tee 'oops").is_err());
+        assert!(split_command("   ").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_clipboard_pipes_text_and_reports_failure() {
+        let d = tempfile::tempdir().unwrap();
+        let out = d.path().join("copied.txt");
+        let cb = CommandClipboard { program: "tee".to_string(), args: vec![out.display().to_string()] };
+        cb.set_text("héllo\nworld\n".to_string()).unwrap();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "héllo\nworld\n");
+        assert!(CommandClipboard::parse("false").unwrap().set_text("x".to_string()).is_err());
+        assert!(CommandClipboard::parse("lf-no-such-command").unwrap().set_text("x".to_string()).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_FILE_NAME: &str = ".lf.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    pub clipboard_cmd: Option<String>,
+    pub header_template: Option<String>,
+    pub footer_template: Option<String>,
+    pub require_patterns: bool,
+    pub prices: BTreeMap<String, f64>,
+    pub models: BTreeMap<String, usize>,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Profile {
+    pub replace: Vec<(String, String)>,
+}
+
+impl Config {
+    pub fn from_toml(s: &str) -> Result<Self> {
+        Ok(toml::from_str(s)?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let s = std::fs::read_to_string(path).with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        Self::from_toml(&s).with_context(|| format!("Invalid config file: {}", path.display()))
+    }
+
+    pub fn discover(explicit: Option<&Path>) -> Result<Self> {
+        if let Some(p) = explicit { return Self::load(p); }
+        match default_locations().into_iter().find(|p| p.is_file()) {
+            Some(p) => Self::load(&p),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+fn default_locations() -> Vec<PathBuf> {
+    let mut v = vec![PathBuf::from(CONFIG_FILE_NAME)];
+    if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
+        v.push(Path::new(&xdg).join("lf").join("config.toml"));
+    }
+    if let Ok(home) = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")) {
+        v.push(Path::new(&home).join(".config").join("lf").join("config.toml"));
+    }
+    v
+}
+
+include!("config_tests.rs");
@@ -0,0 +1,26 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_known_keys_and_rejects_unknown() {
+        let c = Config::from_toml("clipboard-cmd = \"wl-copy\"\n").unwrap();
+        assert_eq!(c.clipboard_cmd.as_deref(), Some("wl-copy"));
+        assert!(Config::from_toml("").unwrap().clipboard_cmd.is_none());
+        assert!(Config::from_toml("no-such-key = 1\n").is_err());
+    }
+
+    #[test]
+    fn parses_profiles() {
+        let c = Config::from_toml("[profiles.share]\nreplace = [[\"corp\\\\.example\", \"HOST\"], [\"(\\\\d+)\", \"<$1>\"]]\n").unwrap();
+        assert_eq!(c.profiles["share"].replace, [("corp\\.example".to_string(), "HOST".to_string()), ("(\\d+)".to_string(), "<$1>".to_string())]);
+        assert!(Config::from_toml("[profiles.share]\nunknown = 1\n").is_err());
+    }
+
+    #[test]
+    fn parses_price_overrides() {
+        let c = Config::from_toml("[prices]\n\"gpt-4o\" = 5.0\nmy-model = 0.5\n").unwrap();
+        assert_eq!(c.prices.get("gpt-4o"), Some(&5.0));
+        assert_eq!(c.prices.get("my-model"), Some(&0.5));
+    }
+}
@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+pub const DEFAULT_PRICES: &[(&str, f64)] = &[
+    ("gpt-4o", 2.50),
+    ("gpt-4o-mini", 0.15),
+    ("o3-mini", 1.10),
+    ("claude-sonnet", 3.00),
+    ("claude-haiku", 0.80),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelPrice {
+    pub model: String,
+    pub usd_per_million: f64,
+}
+
+pub fn price_table(overrides: &BTreeMap<String, f64>) -> Vec<ModelPrice> {
+    let mut table: Vec<ModelPrice> = DEFAULT_PRICES.iter()
+        .map(|(m, p)| ModelPrice { model: m.to_string(), usd_per_million: overrides.get(*m).copied().unwrap_or(*p) })
+        .collect();
+    for (m, p) in overrides {
+        if !table.iter().any(|e| &e.model == m) { table.push(ModelPrice { model: m.clone(), usd_per_million: *p }); }
+    }
+    table
+}
+
+pub fn estimate_cost(tokens: usize, usd_per_million: f64) -> f64 {
+    tokens as f64 * usd_per_million / 1_000_000.0
+}
+
+pub fn render_estimates(tokens: usize, table: &[ModelPrice]) -> String {
+    let width = table.iter().map(|e| e.model.len()).max().unwrap_or(0);
+    let mut s = format!("Estimated input cost for {} tokens:\n", tokens);
+    for e in table {
+        s.push_str(&format!("  {:<width$}  ${:.2}  (${:.2}/1M tokens)\n", e.model, estimate_cost(tokens, e.usd_per_million), e.usd_per_million, width = width));
+    }
+    s
+}
+
+include!("cost_tests.rs");
@@ -0,0 +1,32 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_cost_per_million() {
+        assert_eq!(estimate_cost(1_000_000, 2.5), 2.5);
+        assert!((estimate_cost(91_203, 3.0) - 0.273609).abs() < 1e-9);
+        assert_eq!(estimate_cost(0, 15.0), 0.0);
+    }
+
+    #[test]
+    fn overrides_replace_and_extend_defaults() {
+        let mut o = BTreeMap::new();
+        o.insert("gpt-4o".to_string(), 5.0);
+        o.insert("local-model".to_string(), 0.0);
+        let t = price_table(&o);
+        assert_eq!(t.len(), DEFAULT_PRICES.len() + 1);
+        assert_eq!(t[0], ModelPrice { model: "gpt-4o".to_string(), usd_per_million: 5.0 });
+        assert_eq!(t.last().unwrap().model, "local-model");
+    }
+
+    #[test]
+    fn renders_two_decimal_places() {
+        let t = vec![
+            ModelPrice { model: "a".to_string(), usd_per_million: 2.5 },
+            ModelPrice { model: "bbb".to_string(), usd_per_million: 0.15 },
+        ];
+        let s = render_estimates(123_456, &t);
+        assert_eq!(s, "Estimated input cost for 123456 tokens:\n  a    $0.31  ($2.50/1M tokens)\n  bbb  $0.02  ($0.15/1M tokens)\n");
+    }
+}
@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LfError {
+    #[error("invalid pattern {pattern:?}: {source}")]
+    InvalidPattern { pattern: String, source: globset::Error },
+    #[error("{0}")]
+    InvalidOption(String),
+    #[error("No files found matching the patterns.")]
+    NoMatches,
+    #[error("{} matched no files", .0.iter().map(|p| format!("pattern '{}'", p)).collect::<Vec<_>>().join(", "))]
+    DeadPatterns(Vec<String>),
+    #[error("failed to write output {}: {source}", path.display())]
+    OutputIo { path: PathBuf, source: std::io::Error },
+    #[error("failed to read {}: {source}", path.display())]
+    ReadError { path: PathBuf, source: std::io::Error },
+    #[error("clipboard payload is {size} bytes, above the clipboard limit of {limit} bytes")]
+    ClipboardLimit { size: u64, limit: u64 },
+    #[error("{} is out of date: {detail}", path.display())]
+    OutOfDate { path: PathBuf, detail: String },
+    #[error("snapshots differ: {0}")]
+    Changed(String),
+    #[error("failed to copy to clipboard: {0} (use --fallback-stdout to print the output instead)")]
+    ClipboardFailed(String),
+    #[error("{omitted} files omitted by {cap}")]
+    LimitExceeded { cap: String, omitted: usize },
+    #[error("cancelled")]
+    Cancelled,
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl LfError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            LfError::NoMatches | LfError::DeadPatterns(_) => 2,
+            LfError::InvalidPattern { .. } | LfError::InvalidOption(_) => 3,
+            LfError::OutputIo { .. } => 4,
+            LfError::ReadError { .. } => 5,
+            LfError::ClipboardLimit { .. } => 6,
+            LfError::LimitExceeded { .. } => 7,
+            LfError::ClipboardFailed(_) => 8,
+            LfError::Cancelled => 130,
+            LfError::OutOfDate { .. } | LfError::Changed(_) | LfError::Other(_) => 1,
+        }
+    }
+}
+
+impl From<anyhow::Error> for LfError {
+    fn from(e: anyhow::Error) -> Self {
+        e.downcast::<LfError>().unwrap_or_else(LfError::Other)
+    }
+}
+
+pub(crate) fn io_error(e: anyhow::Error, wrap: impl FnOnce(std::io::Error) -> LfError) -> anyhow::Error {
+    match e.downcast::<std::io::Error>() {
+        Ok(source) => wrap(source).into(),
+        Err(e) => e,
+    }
+}
@@ -0,0 +1,65 @@
+use anyhow::Result;
+use std::path::Path;
+
+pub fn document_kind(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+        "pdf" => Some("PDF"),
+        "docx" => Some("DOCX"),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "doc-extract")]
+pub fn extract_document(path: &Path, kind: &str) -> Result<String> {
+    use anyhow::Context;
+    match kind {
+        "PDF" => {
+            let doc = lopdf::Document::load(path).with_context(|| format!("Failed to parse PDF: {}", path.display()))?;
+            let pages: Vec<u32> = doc.get_pages().keys().copied().collect();
+            let text = doc.extract_text(&pages).with_context(|| format!("Failed to extract text from: {}", path.display()))?;
+            Ok(text.lines().map(str::trim_end).collect::<Vec<_>>().join("\n").trim().to_string() + "\n")
+        }
+        _ => {
+            use std::io::Read;
+            let file = std::fs::File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+            let mut zip = zip::ZipArchive::new(file).with_context(|| format!("Failed to open DOCX: {}", path.display()))?;
+            let mut xml = String::new();
+            zip.by_name("word/document.xml").context("DOCX has no word/document.xml")?.read_to_string(&mut xml)?;
+            Ok(docx_text(&xml))
+        }
+    }
+}
+
+#[cfg(not(feature = "doc-extract"))]
+pub fn extract_document(_path: &Path, _kind: &str) -> Result<String> {
+    anyhow::bail!("document extraction needs a build with the doc-extract feature")
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+pub fn docx_text(xml: &str) -> String {
+    let mut out = String::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>').map(|e| start + e) else { break };
+        let tag = &rest[start + 1..end];
+        let name = tag.split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("");
+        rest = &rest[end + 1..];
+        match name {
+            "w:t" if !tag.ends_with('/') => {
+                let close = rest.find("</w:t>").unwrap_or(rest.len());
+                out.push_str(&unescape(&rest[..close]));
+                rest = &rest[close..];
+            }
+            "w:tab" => out.push('\t'),
+            "w:br" | "w:cr" => out.push('\n'),
+            "" if tag == "/w:p" => out.push('\n'),
+            _ => {}
+        }
+    }
+    out
+}
+
+include!("extract_tests.rs");
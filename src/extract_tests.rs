@@ -0,0 +1,17 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_document_kinds() {
+        assert_eq!(document_kind(Path::new("docs/Design.PDF")), Some("PDF"));
+        assert_eq!(document_kind(Path::new("spec.docx")), Some("DOCX"));
+        assert_eq!(document_kind(Path::new("old.doc")), None);
+    }
+
+    #[test]
+    fn docx_paragraphs_become_lines() {
+        let xml = r#"<?xml version="1.0"?><w:document><w:body><w:p><w:r><w:t>Cache &amp; queue</w:t></w:r><w:r><w:tab/><w:t xml:space="preserve"> design</w:t></w:r></w:p><w:p><w:r><w:t>Second</w:t><w:br/><w:t>line</w:t></w:r></w:p><w:p/></w:body></w:document>"#;
+        assert_eq!(docx_text(xml), "Cache & queue\t design\nSecond\nline\n");
+    }
+}
@@ -0,0 +1,140 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry<'a>(path: &'a str, content: &'a str, binary: bool, meta: Option<&'a HeaderMeta>) -> Entry<'a> {
+        Entry { path: Path::new(path), display: path, content, binary, note: None, meta, lines: 2, tokens: 9, size: 2048, index: 3, total: 12, units: SizeUnits::Binary, id: None }
+    }
+
+    fn plain(e: &Entry) -> String { format_entry(Format::Plain, &Templates::default(), e) }
+
+    #[test]
+    fn plain_entry_is_path_then_content() {
+        assert_eq!(plain(&entry("a.rs", "x\n", false, None)), "a.rs\nx\n\n\n");
+    }
+
+    #[test]
+    fn markdown_entry_is_fenced_with_language() {
+        let s = format_entry(Format::Markdown, &Templates::default(), &entry("src/a.rs", "fn a() {}\n", false, None));
+        assert_eq!(s, "### src/a.rs\n\n```rust\nfn a() {}\n```\n\n");
+        let s = format_entry(Format::Markdown, &Templates::default(), &entry("README.md", "```sh\nls\n```", false, None));
+        assert!(s.contains("````markdown\n```sh\nls\n```\n````\n"));
+        let s = format_entry(Format::Markdown, &Templates::default(), &entry("a.png", "[Image file: 3 bytes]", true, None));
+        assert_eq!(s, "### a.png\n\n[Image file: 3 bytes]\n\n");
+    }
+
+    #[test]
+    fn numbered_entries_lead_with_their_id() {
+        let e = Entry { id: Some(212), ..entry("src/a.rs", "x\n", false, None) };
+        assert_eq!(plain(&e), "[#212] src/a.rs\nx\n\n\n");
+        assert!(format_entry(Format::Markdown, &Templates::default(), &e).starts_with("### [#212] src/a.rs\n"));
+        assert_eq!(render_file_index(&["a", "b"]), "[#1] a\n[#2] b\n\n");
+    }
+
+    #[test]
+    fn html_entry_is_a_collapsible_section() {
+        let s = format_entry(Format::Html, &Templates::default(), &entry("src/a.rs", "fn a<T>() {}\n", false, None));
+        assert_eq!(s, "<details id=\"file-3\"><summary>src/a.rs (2 lines, 9 tokens)</summary>\n<pre>fn a&lt;T&gt;() {}\n</pre>\n</details>\n");
+        let s = format_entry(Format::Html, &Templates::default(), &entry("a.png", "[Image file: 3 bytes]\n", true, None));
+        assert!(s.contains("<p>[Image file: 3 bytes]</p>\n") && !s.contains("<pre>"));
+    }
+
+    #[test]
+    fn header_meta_in_every_format() {
+        let meta = HeaderMeta { lines: Some(142), tokens: Some(1038), size: Some(4301), modified: Some(UNIX_EPOCH + std::time::Duration::from_secs(1_717_200_000)) };
+        let s = plain(&entry("src/app.rs", "x\n", false, Some(&meta)));
+        assert!(s.starts_with("src/app.rs  (142 lines, 1,038 tokens, 4.2 KiB, modified 2024-06-01)\n"));
+        let s = format_entry(Format::Markdown, &Templates::default(), &entry("src/app.rs", "x\n", false, Some(&meta)));
+        assert!(s.starts_with("### src/app.rs\n\n_142 lines, 1,038 tokens, 4.2 KiB, modified 2024-06-01_\n\n"));
+        let s = format_entry(Format::Xml, &Templates::default(), &entry("src/app.rs", "a < b\n", false, Some(&meta)));
+        assert_eq!(s, "<file path=\"src/app.rs\" lines=\"142\" tokens=\"1038\" size=\"4301\" modified=\"2024-06-01\">\na &lt; b\n</file>\n");
+        let partial = HeaderMeta { lines: Some(3), ..HeaderMeta::default() };
+        assert!(plain(&entry("a", "", false, Some(&partial))).starts_with("a  (3 lines)\n"));
+    }
+
+    #[test]
+    fn parses_header_fields() {
+        assert_eq!(HeaderFields::parse("all").unwrap(), HeaderFields::ALL);
+        let f = HeaderFields::parse("lines,tokens").unwrap();
+        assert!(f.lines && f.tokens && !f.size && !f.mtime);
+        assert!(HeaderFields::parse("lines,colour").is_err());
+    }
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_date(UNIX_EPOCH), "1970-01-01");
+        assert_eq!(civil_date(UNIX_EPOCH + std::time::Duration::from_secs(951_782_400)), "2000-02-29");
+    }
+
+    #[test]
+    fn slugs_follow_github_rules_and_dedupe() {
+        assert_eq!(slugify("src/app.rs"), "srcapprs");
+        assert_eq!(slugify("My File_v2-final.md"), "my-file_v2-finalmd");
+        let mut s = Slugger::default();
+        assert_eq!(s.slug("a/b.rs"), "abrs");
+        assert_eq!(s.slug("ab.rs"), "abrs-1");
+        assert_eq!(s.slug("a/mod.rs"), "amodrs");
+        assert_eq!(s.slug("b/mod.rs"), "bmodrs");
+    }
+
+    #[test]
+    fn toc_links_each_heading() {
+        assert_eq!(render_toc(&["a/mod.rs", "b/mod.rs"]), "- [a/mod.rs](#amodrs)\n- [b/mod.rs](#bmodrs)\n\n");
+    }
+
+    #[test]
+    fn templates_render_placeholders_and_escaped_braces() {
+        let t = Template::parse("===== FILE: {path} [{index}/{total}] {{{ext}}} =====").unwrap();
+        assert_eq!(t.render(&entry("src/app.rs", "", false, None)), "===== FILE: src/app.rs [3/12] {rs} =====");
+        let t = Template::parse("// {path} ({lines} lines, {tokens} tokens, {size})").unwrap();
+        assert_eq!(t.render(&entry("a.rs", "", false, None)), "// a.rs (2 lines, 9 tokens, 2.0 KiB)");
+        assert!(t.uses("size") && !t.uses("ext"));
+    }
+
+    #[test]
+    fn templates_reject_unknown_or_unbalanced_placeholders() {
+        assert!(Template::parse("{path} {name}").unwrap_err().contains("{name}"));
+        assert!(Template::parse("{path").is_err());
+        assert!(Template::parse("path}").is_err());
+    }
+
+    #[test]
+    fn plain_entry_with_header_and_footer_templates() {
+        let templates = Templates { header: Some(Template::parse("--- {path}").unwrap()), footer: Some(Template::parse("--- end {index}").unwrap()) };
+        let s = format_entry(Format::Plain, &templates, &entry("a.rs", "x\n", false, None));
+        assert_eq!(s, "--- a.rs\nx\n--- end 3\n\n\n");
+    }
+
+    #[test]
+    fn path_styles_use_forward_slashes() {
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(PathDisplay::default().show(Path::new("./src/a.rs")), "src/a.rs");
+        let abs = PathDisplay { style: PathStyle::Absolute, ..PathDisplay::default() }.show(Path::new("./src/a.rs"));
+        assert_eq!(abs, format!("{}/src/a.rs", cwd.to_string_lossy().replace('\\', "/")));
+        let repo = PathDisplay { style: PathStyle::RepoRoot, base: PathBuf::new(), root: cwd.parent().map(Path::to_path_buf) };
+        let name = cwd.file_name().unwrap().to_string_lossy();
+        assert_eq!(repo.show(Path::new("./src/a.rs")), format!("{}/src/a.rs", name));
+    }
+
+    #[test]
+    fn json_records_are_one_line() {
+        let s = format_entry(Format::Json, &Templates::default(), &entry("src/a.rs", "fn a() {}\n\"q\"\n", false, None));
+        assert_eq!(s, "{\"path\":\"src/a.rs\",\"lines\":2,\"tokens\":9,\"content\":\"fn a() {}\\n\\\"q\\\"\\n\"}\n");
+        let s = format_entry(Format::Json, &Templates::default(), &entry("a.png", "[Image file: 3 bytes]", true, None));
+        assert!(s.contains("\"binary\":true"));
+        assert_eq!(json_array(&["{\"a\":1}\n".to_string(), "{\"b\":2}\n".to_string()]), "[\n  {\"a\":1},\n  {\"b\":2}\n]\n");
+        assert_eq!(json_array(&[]), "[]\n");
+    }
+
+    #[test]
+    fn sections_group_by_leading_directories() {
+        assert_eq!(group_key("src/api/v1/a.rs", 1), "src");
+        assert_eq!(group_key("src/api/v1/a.rs", 2), "src/api");
+        assert_eq!(group_key("src/a.rs", 3), "src");
+        assert_eq!(group_key("README.md", 1), "");
+        assert_eq!(section_header(Format::Plain, "src/api"), "======== src/api ========\n\n");
+        assert_eq!(section_header(Format::Markdown, ""), "## .\n\n");
+        assert_eq!(section_footer(Format::Plain, "src", 2, 1200, Some(34567)), "-------- src: 2 files, 1,200 lines, 34,567 tokens --------\n\n");
+        assert_eq!(section_footer(Format::Xml, "src", 2, 1200, None), "<subtotal files=\"2\" lines=\"1200\"/>\n</section>\n");
+    }
+}
@@ -0,0 +1,65 @@
+use crate::units::thousands;
+
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub struct HtmlFile<'a> {
+    pub display: &'a str,
+    pub content: &'a str,
+    pub binary: bool,
+}
+
+/// One `<h3>` heading per file followed by its escaped content; binary placeholders become plain paragraphs.
+pub fn render_fragment(files: &[HtmlFile]) -> String {
+    let mut s = String::new();
+    for f in files {
+        s.push_str(&format!("<h3>{}</h3>\n", escape(f.display)));
+        if f.binary { s.push_str(&format!("<p>{}</p>\n", escape(f.content.trim_end()))); }
+        else { s.push_str(&format!("<pre><code>{}</code></pre>\n", escape(f.content))); }
+    }
+    s
+}
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em auto;max-width:60em;padding:0 1em}\
+pre{background:#f6f8fa;padding:.75em;overflow-x:auto}summary{cursor:pointer;font-family:monospace}.totals{font-weight:bold}";
+
+/// One collapsible `--format html` section; `summary` is the "(lines, tokens)" note after the path.
+pub fn details(index: usize, display: &str, summary: &str, content: &str, binary: bool, meta: Option<&str>) -> String {
+    let mut s = format!("<details id=\"file-{}\"><summary>{} ({})</summary>\n", index, escape(display), escape(summary));
+    if let Some(m) = meta { s.push_str(&format!("<p><em>{}</em></p>\n", escape(m))); }
+    if binary { s.push_str(&format!("<p>{}</p>\n", escape(content.trim_end()))); }
+    else { s.push_str(&format!("<pre>{}</pre>\n", escape(content))); }
+    s.push_str("</details>\n");
+    s
+}
+
+pub const DOCUMENT_END: &str = "</body>\n</html>\n";
+
+/// The top of a standalone page: the totals and a linked index of the `details` sections that follow.
+pub fn document_start(displays: &[&str], totals: &str) -> String {
+    let mut s = format!("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>lf snapshot</title>\n<style>{}</style>\n</head>\n<body>\n", STYLE);
+    s.push_str(&format!("<p class=\"totals\">{}</p>\n<ul class=\"index\">\n", escape(totals)));
+    for (i, d) in displays.iter().enumerate() { s.push_str(&format!("<li><a href=\"#file-{}\">{}</a></li>\n", i + 1, escape(d))); }
+    s.push_str("</ul>\n");
+    s
+}
+
+pub fn totals(files: usize, lines: usize, tokens: Option<usize>) -> String {
+    let mut s = format!("{} files, {} lines", thousands(files), thousands(lines));
+    if let Some(t) = tokens { s.push_str(&format!(", {} tokens", thousands(t))); }
+    s
+}
+
+include!("html_tests.rs");
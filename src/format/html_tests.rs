@@ -0,0 +1,32 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_markup_and_quotes() {
+        assert_eq!(escape("Vec<Option<&str>>"), "Vec&lt;Option&lt;&amp;str&gt;&gt;");
+        assert_eq!(escape(r#"a="x" b='y'"#), "a=&quot;x&quot; b=&#39;y&#39;");
+        assert_eq!(escape("&amp;"), "&amp;amp;");
+        assert_eq!(escape("plain ü"), "plain ü");
+    }
+
+    #[test]
+    fn renders_headings_and_code_blocks() {
+        let files = [
+            HtmlFile { display: "src/<a>.rs", content: "fn f() -> Vec<u8> {}\n", binary: false },
+            HtmlFile { display: "logo.png", content: "[Image file: 2.0 KiB]\n", binary: true },
+        ];
+        assert_eq!(render_fragment(&files), "<h3>src/&lt;a&gt;.rs</h3>\n<pre><code>fn f() -&gt; Vec&lt;u8&gt; {}\n</code></pre>\n<h3>logo.png</h3>\n<p>[Image file: 2.0 KiB]</p>\n");
+    }
+
+    #[test]
+    fn document_has_totals_index_and_sections() {
+        let doc = document_start(&["a.rs", "b&c.rs"], &totals(2, 1200, Some(34))) + &details(1, "a.rs", "1 lines", "x\n", false, None)
+            + &details(2, "b&c.rs", "1 lines", "y\n", false, None) + DOCUMENT_END;
+        assert!(doc.starts_with("<!DOCTYPE html>\n"), "{}", doc);
+        assert!(doc.contains("<p class=\"totals\">2 files, 1,200 lines, 34 tokens</p>"));
+        assert!(doc.contains("<li><a href=\"#file-2\">b&amp;c.rs</a></li>") && doc.contains("<details id=\"file-2\">"));
+        assert!(doc.ends_with("</details>\n</body>\n</html>\n"));
+        assert_eq!(totals(1, 3, None), "1 files, 3 lines");
+    }
+}
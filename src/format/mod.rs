@@ -0,0 +1,540 @@
+use crate::app::Stats;
+use crate::units::{format_size, thousands, SizeUnits};
+use serde::Serialize;
+use std::collections::HashMap;
+use crate::fs::find_repo_root;
+use crate::lang::language;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub mod html;
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Plain,
+    Markdown,
+    Xml,
+    Json,
+    Jsonl,
+    Html,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathStyle {
+    #[default]
+    Relative,
+    Absolute,
+    RepoRoot,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PathDisplay {
+    pub style: PathStyle,
+    pub base: PathBuf,
+    pub root: Option<PathBuf>,
+}
+
+impl PathDisplay {
+    pub fn new(style: PathStyle, base: &Path) -> Self {
+        let start = if base.as_os_str().is_empty() { Path::new(".") } else { base };
+        let root = if style == PathStyle::RepoRoot { std::path::absolute(start).ok().and_then(|d| find_repo_root(&d)) } else { None };
+        PathDisplay { style, base: base.to_path_buf(), root }
+    }
+
+    pub fn show(&self, path: &Path) -> String {
+        let shown = match (self.style, &self.root) {
+            (PathStyle::Relative, _) | (PathStyle::RepoRoot, None) => path.strip_prefix(&self.base).unwrap_or(path).to_path_buf(),
+            (PathStyle::Absolute, _) => std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+            (PathStyle::RepoRoot, Some(root)) => {
+                let abs = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+                abs.strip_prefix(root).map(Path::to_path_buf).unwrap_or(abs)
+            }
+        };
+        let p = display_path(&shown);
+        p.strip_prefix("./").map(str::to_string).unwrap_or(p)
+    }
+}
+
+/// Forward-slash form of `path`. Bytes that are not UTF-8 show up as `\xNN` instead of all collapsing into U+FFFD.
+pub fn display_path(path: &Path) -> String {
+    if let Some(valid) = path.to_str() { return valid.replace('\\', "/"); }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let mut out = String::new();
+        for chunk in path.as_os_str().as_bytes().utf8_chunks() {
+            out.push_str(&chunk.valid().replace('\\', "/"));
+            for b in chunk.invalid() { out.push_str(&format!("\\x{:02x}", b)); }
+        }
+        out
+    }
+    #[cfg(not(unix))]
+    path.to_string_lossy().replace('\\', "/")
+}
+
+pub fn fence_language(path: &Path) -> &'static str {
+    language(path).map_or("", |l| l.fence)
+}
+
+fn fence_for(content: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in content.chars() {
+        if c == '`' { run += 1; longest = longest.max(run); } else { run = 0; }
+    }
+    "`".repeat(longest.max(2) + 1)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderFields {
+    pub lines: bool,
+    pub tokens: bool,
+    pub size: bool,
+    pub mtime: bool,
+}
+
+impl HeaderFields {
+    pub const ALL: HeaderFields = HeaderFields { lines: true, tokens: true, size: true, mtime: true };
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut f = HeaderFields { lines: false, tokens: false, size: false, mtime: false };
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part {
+                "all" => f = Self::ALL,
+                "lines" => f.lines = true,
+                "tokens" => f.tokens = true,
+                "size" => f.size = true,
+                "mtime" | "modified" => f.mtime = true,
+                other => return Err(format!("unknown header field '{}' (expected lines, tokens, size, mtime or all)", other)),
+            }
+        }
+        Ok(f)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HeaderMeta {
+    pub lines: Option<usize>,
+    pub tokens: Option<usize>,
+    pub size: Option<u64>,
+    pub modified: Option<SystemTime>,
+}
+
+pub struct Entry<'a> {
+    pub path: &'a Path,
+    pub display: &'a str,
+    pub content: &'a str,
+    pub binary: bool,
+    pub note: Option<&'a str>,
+    pub meta: Option<&'a HeaderMeta>,
+    pub lines: usize,
+    pub tokens: usize,
+    pub size: u64,
+    pub index: usize,
+    pub total: usize,
+    pub units: SizeUnits,
+    /// `--number-files` ID, shown before the path in plain and markdown headers.
+    pub id: Option<usize>,
+}
+
+pub const TEMPLATE_PLACEHOLDERS: &[&str] = &["path", "lines", "tokens", "size", "ext", "index", "total"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Var(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => { chars.next(); text.push('{'); }
+                '}' if chars.peek() == Some(&'}') => { chars.next(); text.push('}'); }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("unclosed '{{' in template: {}", s)),
+                        }
+                    }
+                    if !TEMPLATE_PLACEHOLDERS.contains(&name.as_str()) {
+                        return Err(format!("unknown placeholder '{{{}}}' in template (available: {})", name, TEMPLATE_PLACEHOLDERS.join(", ")));
+                    }
+                    if !text.is_empty() { parts.push(Part::Text(std::mem::take(&mut text))); }
+                    parts.push(Part::Var(name));
+                }
+                '}' => return Err(format!("unmatched '}}' in template (use '}}}}' for a literal brace): {}", s)),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() { parts.push(Part::Text(text)); }
+        Ok(Self { parts })
+    }
+
+    pub fn uses(&self, name: &str) -> bool {
+        self.parts.iter().any(|p| matches!(p, Part::Var(v) if v == name))
+    }
+
+    pub fn render(&self, e: &Entry) -> String {
+        let mut s = String::new();
+        for p in &self.parts {
+            match p {
+                Part::Text(t) => s.push_str(t),
+                Part::Var(v) => s.push_str(&match v.as_str() {
+                    "path" => e.display.to_string(),
+                    "lines" => e.lines.to_string(),
+                    "tokens" => e.tokens.to_string(),
+                    "size" => format_size(e.size, e.units),
+                    "ext" => e.path.extension().map(|x| x.to_string_lossy().to_string()).unwrap_or_default(),
+                    "index" => e.index.to_string(),
+                    "total" => e.total.to_string(),
+                    _ => unreachable!("placeholders are validated in Template::parse"),
+                }),
+            }
+        }
+        s
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Templates {
+    pub header: Option<Template>,
+    pub footer: Option<Template>,
+}
+
+impl Templates {
+    pub fn is_empty(&self) -> bool { self.header.is_none() && self.footer.is_none() }
+
+    pub fn uses(&self, name: &str) -> bool {
+        self.header.iter().chain(self.footer.iter()).any(|t| t.uses(name))
+    }
+}
+
+pub fn civil_date(t: SystemTime) -> String {
+    let days = t.duration_since(UNIX_EPOCH).map(|d| d.as_secs() / 86_400).unwrap_or(0) as i64;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+pub fn civil_datetime(t: SystemTime) -> String {
+    let secs = t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) % 86_400;
+    format!("{} {:02}:{:02}:{:02} UTC", civil_date(t), secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn meta_summary(meta: &HeaderMeta, units: SizeUnits) -> String {
+    let mut parts = Vec::new();
+    if let Some(n) = meta.lines { parts.push(format!("{} lines", thousands(n))); }
+    if let Some(n) = meta.tokens { parts.push(format!("{} tokens", thousands(n))); }
+    if let Some(n) = meta.size { parts.push(format_size(n, units)); }
+    if let Some(t) = meta.modified { parts.push(format!("modified {}", civil_date(t))); }
+    parts.join(", ")
+}
+
+pub fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn xml_attrs(meta: &HeaderMeta) -> String {
+    let mut s = String::new();
+    if let Some(n) = meta.lines { s.push_str(&format!(" lines=\"{}\"", n)); }
+    if let Some(n) = meta.tokens { s.push_str(&format!(" tokens=\"{}\"", n)); }
+    if let Some(n) = meta.size { s.push_str(&format!(" size=\"{}\"", n)); }
+    if let Some(t) = meta.modified { s.push_str(&format!(" modified=\"{}\"", civil_date(t))); }
+    s
+}
+
+#[derive(Serialize)]
+struct JsonFile<'a> {
+    path: &'a str,
+    lines: usize,
+    tokens: usize,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    binary: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<&'a str>,
+    content: &'a str,
+}
+
+/// Wraps one-line JSON records (as produced by `format_entry` with `Format::Json`) into an array.
+pub fn json_array(records: &[String]) -> String {
+    if records.is_empty() { return "[]\n".to_string(); }
+    format!("[\n  {}\n]\n", records.iter().map(|r| r.trim_end()).collect::<Vec<_>>().join(",\n  "))
+}
+
+pub fn format_entry(format: Format, templates: &Templates, e: &Entry) -> String {
+    let mut s = String::new();
+    let summary = e.meta.map(|m| meta_summary(m, e.units)).filter(|m| !m.is_empty());
+    match format {
+        Format::Plain => {
+            if let Some(id) = e.id { s.push_str(&format!("{} ", file_id(id))); }
+            match &templates.header {
+                Some(t) => s.push_str(&t.render(e)),
+                None => s.push_str(e.display),
+            }
+            if let Some(n) = e.note { s.push_str(&format!(" [{}]", n)); }
+            if let Some(m) = &summary { s.push_str(&format!("  ({})", m)); }
+            s.push('\n');
+            s.push_str(e.content);
+            if let Some(t) = &templates.footer {
+                if !e.content.ends_with('\n') { s.push('\n'); }
+                s.push_str(&t.render(e));
+                s.push('\n');
+            }
+            s.push_str("\n\n");
+        }
+        Format::Markdown => {
+            s.push_str("### ");
+            if let Some(id) = e.id { s.push_str(&format!("{} ", file_id(id))); }
+            s.push_str(e.display);
+            s.push_str("\n\n");
+            if let Some(m) = &summary { s.push_str(&format!("_{}_\n\n", m)); }
+            if let Some(n) = e.note { s.push_str(&format!("_[{}]_\n\n", n)); }
+            if e.binary {
+                s.push_str(e.content.trim_end());
+                s.push_str("\n\n");
+            } else {
+                let fence = fence_for(e.content);
+                s.push_str(&fence);
+                s.push_str(fence_language(e.path));
+                s.push('\n');
+                s.push_str(e.content);
+                if !e.content.ends_with('\n') { s.push('\n'); }
+                s.push_str(&fence);
+                s.push_str("\n\n");
+            }
+        }
+        Format::Xml => {
+            s.push_str(&format!("<file path=\"{}\"", xml_escape(e.display)));
+            if let Some(m) = e.meta { s.push_str(&xml_attrs(m)); }
+            if e.binary { s.push_str(" binary=\"true\""); }
+            if let Some(n) = e.note { s.push_str(&format!(" note=\"{}\"", xml_escape(n))); }
+            s.push_str(">\n");
+            s.push_str(&xml_escape(e.content));
+            if !e.content.ends_with('\n') { s.push('\n'); }
+            s.push_str("</file>\n");
+        }
+        Format::Html => {
+            let mut counts = format!("{} lines", thousands(e.lines));
+            if e.tokens > 0 { counts.push_str(&format!(", {} tokens", thousands(e.tokens))); }
+            let display = match e.note { Some(n) => format!("{} [{}]", e.display, n), None => e.display.to_string() };
+            s.push_str(&html::details(e.index, &display, &counts, e.content, e.binary, summary.as_deref()));
+        }
+        Format::Json | Format::Jsonl => {
+            let record = JsonFile { path: e.display, lines: e.lines, tokens: e.tokens, binary: e.binary, note: e.note, content: e.content };
+            s.push_str(&serde_json::to_string(&record).expect("file records always serialize"));
+            s.push('\n');
+        }
+    }
+    s
+}
+
+/// Known before the first entry: every displayed path in output order, and the run's line and token totals.
+pub struct RunMeta<'a> {
+    pub displays: &'a [&'a str],
+    pub lines: usize,
+    pub tokens: Option<usize>,
+}
+
+/// Renders a run: `begin` once, `entry` for each file in output order, then `finish` with the final stats.
+pub trait Formatter {
+    fn begin(&mut self, _out: &mut dyn Write, _meta: &RunMeta) -> io::Result<()> { Ok(()) }
+    fn entry(&mut self, out: &mut dyn Write, e: &Entry) -> io::Result<()>;
+    fn finish(&mut self, _out: &mut dyn Write, _stats: &Stats) -> io::Result<()> { Ok(()) }
+}
+
+pub struct PlainFormatter {
+    pub templates: Templates,
+}
+
+impl Formatter for PlainFormatter {
+    fn entry(&mut self, out: &mut dyn Write, e: &Entry) -> io::Result<()> { out.write_all(format_entry(Format::Plain, &self.templates, e).as_bytes()) }
+}
+
+pub struct MarkdownFormatter;
+
+impl Formatter for MarkdownFormatter {
+    fn entry(&mut self, out: &mut dyn Write, e: &Entry) -> io::Result<()> { out.write_all(format_entry(Format::Markdown, &Templates::default(), e).as_bytes()) }
+}
+
+pub struct XmlFormatter;
+
+impl Formatter for XmlFormatter {
+    fn entry(&mut self, out: &mut dyn Write, e: &Entry) -> io::Result<()> { out.write_all(format_entry(Format::Xml, &Templates::default(), e).as_bytes()) }
+}
+
+/// Writes the same array as `json_array`, one record at a time.
+#[derive(Default)]
+pub struct JsonFormatter {
+    written: usize,
+}
+
+impl Formatter for JsonFormatter {
+    fn begin(&mut self, out: &mut dyn Write, meta: &RunMeta) -> io::Result<()> { out.write_all(if meta.displays.is_empty() { b"[]\n" } else { b"[\n" }) }
+
+    fn entry(&mut self, out: &mut dyn Write, e: &Entry) -> io::Result<()> {
+        if self.written > 0 { out.write_all(b",\n")?; }
+        self.written += 1;
+        write!(out, "  {}", format_entry(Format::Json, &Templates::default(), e).trim_end())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write, _stats: &Stats) -> io::Result<()> {
+        if self.written > 0 { out.write_all(b"\n]\n")?; }
+        Ok(())
+    }
+}
+
+pub struct JsonlFormatter;
+
+impl Formatter for JsonlFormatter {
+    fn entry(&mut self, out: &mut dyn Write, e: &Entry) -> io::Result<()> { out.write_all(format_entry(Format::Jsonl, &Templates::default(), e).as_bytes()) }
+
+    fn finish(&mut self, out: &mut dyn Write, stats: &Stats) -> io::Result<()> {
+        writeln!(out, "{{\"type\":\"summary\",\"files\":{},\"lines\":{},\"tokens\":{}}}", stats.files, stats.lines, stats.tokens)
+    }
+}
+
+pub struct HtmlFormatter;
+
+impl Formatter for HtmlFormatter {
+    fn begin(&mut self, out: &mut dyn Write, meta: &RunMeta) -> io::Result<()> {
+        out.write_all(html::document_start(meta.displays, &html::totals(meta.displays.len(), meta.lines, meta.tokens)).as_bytes())
+    }
+
+    fn entry(&mut self, out: &mut dyn Write, e: &Entry) -> io::Result<()> { out.write_all(format_entry(Format::Html, &Templates::default(), e).as_bytes()) }
+
+    fn finish(&mut self, out: &mut dyn Write, _stats: &Stats) -> io::Result<()> { out.write_all(html::DOCUMENT_END.as_bytes()) }
+}
+
+pub fn formatter_for(format: Format, templates: &Templates) -> Box<dyn Formatter> {
+    match format {
+        Format::Plain => Box::new(PlainFormatter { templates: templates.clone() }),
+        Format::Markdown => Box::new(MarkdownFormatter),
+        Format::Xml => Box::new(XmlFormatter),
+        Format::Json => Box::new(JsonFormatter::default()),
+        Format::Jsonl => Box::new(JsonlFormatter),
+        Format::Html => Box::new(HtmlFormatter),
+    }
+}
+
+type MakeFormatter = dyn Fn() -> Box<dyn Formatter> + Send + Sync;
+
+/// Builds a fresh custom formatter for each run, in place of the one `--format` picks.
+#[derive(Clone)]
+pub struct FormatterFactory(pub Arc<MakeFormatter>);
+
+impl FormatterFactory {
+    pub fn make(&self) -> Box<dyn Formatter> { (self.0)() }
+}
+
+impl std::fmt::Debug for FormatterFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str("FormatterFactory(..)") }
+}
+
+/// The first `depth` directories of a display path; "" for files above that depth's root.
+pub fn group_key(display: &str, depth: usize) -> String {
+    let dirs: Vec<&str> = display.split('/').collect();
+    dirs[..dirs.len() - 1].iter().take(depth).copied().collect::<Vec<_>>().join("/")
+}
+
+pub fn section_header(format: Format, key: &str) -> String {
+    let key = if key.is_empty() { "." } else { key };
+    match format {
+        Format::Markdown => format!("## {}\n\n", key),
+        Format::Xml => format!("<section dir=\"{}\">\n", xml_escape(key)),
+        _ => format!("======== {} ========\n\n", key),
+    }
+}
+
+pub fn section_footer(format: Format, key: &str, files: usize, lines: usize, tokens: Option<usize>) -> String {
+    let key = if key.is_empty() { "." } else { key };
+    let mut totals = format!("{} files, {} lines", thousands(files), thousands(lines));
+    if let Some(t) = tokens { totals.push_str(&format!(", {} tokens", thousands(t))); }
+    match format {
+        Format::Markdown => format!("_{}: {}_\n\n", key, totals),
+        Format::Xml => format!("<subtotal files=\"{}\" lines=\"{}\"{}/>\n</section>\n", files, lines, tokens.map(|t| format!(" tokens=\"{}\"", t)).unwrap_or_default()),
+        _ => format!("-------- {}: {} --------\n\n", key, totals),
+    }
+}
+
+pub fn format_tree(format: Format, tree: &str) -> String {
+    match format {
+        Format::Plain | Format::Json | Format::Jsonl | Format::Html => format!("{}\n", tree),
+        Format::Markdown => format!("```text\n{}```\n\n", tree),
+        Format::Xml => format!("<tree>\n{}</tree>\n", xml_escape(tree)),
+    }
+}
+
+pub fn slugify(heading: &str) -> String {
+    heading.trim().to_lowercase().chars()
+        .filter_map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { Some(c) } else if c == ' ' { Some('-') } else { None })
+        .collect()
+}
+
+#[derive(Default)]
+pub struct Slugger {
+    seen: HashMap<String, usize>,
+}
+
+impl Slugger {
+    pub fn slug(&mut self, heading: &str) -> String {
+        let base = slugify(heading);
+        let n = self.seen.entry(base.clone()).or_insert(0);
+        let slug = if *n == 0 { base.clone() } else { format!("{}-{}", base, n) };
+        *n += 1;
+        slug
+    }
+}
+
+pub fn file_id(id: usize) -> String { format!("[#{}]", id) }
+
+/// The `--number-files` index printed above the files when there's no TOC to carry the IDs.
+pub fn render_file_index<S: AsRef<str>>(displays: &[S]) -> String {
+    let mut s = String::new();
+    for (i, d) in displays.iter().enumerate() { s.push_str(&format!("{} {}\n", file_id(i + 1), d.as_ref())); }
+    s.push('\n');
+    s
+}
+
+pub fn render_toc<S: AsRef<str>>(headings: &[S]) -> String {
+    let mut slugger = Slugger::default();
+    let mut s = String::new();
+    for h in headings {
+        s.push_str(&format!("- [{}](#{})\n", h.as_ref(), slugger.slug(h.as_ref())));
+    }
+    s.push('\n');
+    s
+}
+
+include!("format_tests.rs");
@@ -1,18 +1,113 @@
 use anyhow::{Context, Result};
 use ignore::WalkBuilder;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::borrow::Cow;
 use std::path::{Path, PathBuf};
 
+// `\\?\` turns off MAX_PATH (260 chars) parsing on Windows; it needs an absolute path with backslashes.
+#[cfg(any(windows, test))]
+fn verbatim_path(abs: &str) -> String {
+    let abs = abs.replace('/', "\\");
+    if abs.starts_with(r"\\?\") || abs.starts_with(r"\\.\") { abs }
+    else if let Some(unc) = abs.strip_prefix(r"\\") { format!(r"\\?\UNC\{}", unc) }
+    else { format!(r"\\?\{}", abs) }
+}
+
+/// The path to hand to the OS when opening `path`; headers and errors keep showing `path` itself.
+#[cfg(windows)]
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+    match std::path::absolute(path) {
+        Ok(abs) => Cow::Owned(PathBuf::from(verbatim_path(&abs.to_string_lossy()))),
+        Err(_) => Cow::Borrowed(path),
+    }
+}
+
+#[cfg(not(windows))]
+pub fn long_path(path: &Path) -> Cow<'_, Path> { Cow::Borrowed(path) }
+
+/// A walked file with the metadata the walk fetched for it; size filters, binary info and mtime sorting
+/// read it from here instead of stat-ing the file again. `None` when the stat failed.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub path: PathBuf,
+    pub metadata: Option<std::fs::Metadata>,
+    /// The size when there's no metadata to take it from, as for a git blob.
+    pub known_size: Option<u64>,
+}
+
+impl Candidate {
+    pub fn size(&self) -> u64 { self.metadata.as_ref().map_or(self.known_size.unwrap_or(0), |m| m.len()) }
+    pub fn modified(&self) -> Option<std::time::SystemTime> { self.metadata.as_ref().and_then(|m| m.modified().ok()) }
+}
+
 pub trait FileReader: Send + Sync {
     fn read_to_string(&self, path: &Path) -> Result<(String, usize)>;
+    fn read_lossy(&self, path: &Path) -> Result<(String, usize)> { self.read_to_string(path) }
+    fn read_candidate(&self, c: &Candidate, lossy: bool) -> Result<(String, usize)> {
+        if lossy { self.read_lossy(&c.path) } else { self.read_to_string(&c.path) }
+    }
 }
 
-pub struct StdFileReader;
+/// Files at least this large are memory-mapped by `StdFileReader` (with the `mmap` feature).
+pub const MMAP_THRESHOLD: u64 = 16 * 1024 * 1024;
 
-impl FileReader for StdFileReader {
+/// `text` with `\n` and `\r\n` line endings turned into `\n` and a final newline added, plus its line count:
+/// the same result as pushing each `BufRead::lines` line and a `\n`, without a String per line.
+pub fn normalize_lines(text: &str) -> (String, usize) {
+    let mut content = String::with_capacity(text.len() + 1);
+    let mut lines = 0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (line, next) = match rest.find('\n') {
+            Some(i) => (rest[..i].strip_suffix('\r').unwrap_or(&rest[..i]), &rest[i + 1..]),
+            None => (rest, ""),
+        };
+        content.push_str(line);
+        content.push('\n');
+        lines += 1;
+        rest = next;
+    }
+    (content, lines)
+}
+
+#[cfg(feature = "mmap")]
+pub struct MmapFileReader;
+
+#[cfg(feature = "mmap")]
+impl MmapFileReader {
+    fn map(path: &Path) -> Result<memmap2::Mmap> {
+        let file = File::open(long_path(path)).with_context(|| format!("Failed to open file: {}", path.display()))?;
+        // SAFETY: the map is read once and dropped before returning; a file truncated by another process
+        // mid-read can still fault, the accepted trade-off of mapping instead of copying.
+        unsafe { memmap2::Mmap::map(&file) }.with_context(|| format!("Failed to map file: {}", path.display()))
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl FileReader for MmapFileReader {
     fn read_to_string(&self, path: &Path) -> Result<(String, usize)> {
-        let file = File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+        let map = Self::map(path)?;
+        let text = std::str::from_utf8(&map).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+            .with_context(|| format!("Failed to read line from: {}", path.display()))?;
+        Ok(normalize_lines(text))
+    }
+
+    fn read_lossy(&self, path: &Path) -> Result<(String, usize)> {
+        Ok(normalize_lines(&String::from_utf8_lossy(&Self::map(path)?)))
+    }
+}
+
+#[cfg(feature = "mmap")]
+fn mmap_worthy(path: &Path) -> bool {
+    std::fs::metadata(long_path(path)).is_ok_and(|m| m.len() >= MMAP_THRESHOLD)
+}
+
+pub struct StdFileReader;
+
+impl StdFileReader {
+    fn read_buffered(path: &Path) -> Result<(String, usize)> {
+        let file = File::open(long_path(path)).with_context(|| format!("Failed to open file: {}", path.display()))?;
         let reader = BufReader::new(file);
         let mut content = String::new();
         let mut lines = 0usize;
@@ -24,33 +119,165 @@ impl FileReader for StdFileReader {
         }
         Ok((content, lines))
     }
+
+    fn read_lossy_buffered(path: &Path) -> Result<(String, usize)> {
+        let bytes = std::fs::read(long_path(path)).with_context(|| format!("Failed to open file: {}", path.display()))?;
+        let mut content = String::new();
+        let mut lines = 0usize;
+        for line in String::from_utf8_lossy(&bytes).lines() {
+            content.push_str(line);
+            content.push('\n');
+            lines += 1;
+        }
+        Ok((content, lines))
+    }
+}
+
+impl FileReader for StdFileReader {
+    fn read_to_string(&self, path: &Path) -> Result<(String, usize)> {
+        #[cfg(feature = "mmap")]
+        if mmap_worthy(path) { return MmapFileReader.read_to_string(path); }
+        Self::read_buffered(path)
+    }
+
+    fn read_lossy(&self, path: &Path) -> Result<(String, usize)> {
+        #[cfg(feature = "mmap")]
+        if mmap_worthy(path) { return MmapFileReader.read_lossy(path); }
+        Self::read_lossy_buffered(path)
+    }
+
+    fn read_candidate(&self, c: &Candidate, lossy: bool) -> Result<(String, usize)> {
+        #[cfg(feature = "mmap")]
+        if c.size() >= MMAP_THRESHOLD { return if lossy { MmapFileReader.read_lossy(&c.path) } else { MmapFileReader.read_to_string(&c.path) }; }
+        if lossy { Self::read_lossy_buffered(&c.path) } else { Self::read_buffered(&c.path) }
+    }
 }
 
 pub trait WalkerFactory: Send + Sync {
-    fn build(&self, no_gitignore: bool) -> ignore::Walk;
+    fn build(&self, root: &Path, no_gitignore: bool) -> ignore::Walk;
+    /// The one walk-time stat each matched file gets; text files are stat'ed again after reading to spot mid-run changes.
+    fn metadata(&self, entry: &ignore::DirEntry) -> Option<std::fs::Metadata> { entry.metadata().ok() }
 }
 
 pub struct StdWalkerFactory;
 
 impl WalkerFactory for StdWalkerFactory {
-    fn build(&self, no_gitignore: bool) -> ignore::Walk {
-        let mut wb = WalkBuilder::new(".");
+    fn build(&self, root: &Path, no_gitignore: bool) -> ignore::Walk {
+        let mut wb = WalkBuilder::new(root);
         wb.hidden(false)
             .follow_links(false)
             .git_ignore(!no_gitignore)
             .git_global(!no_gitignore)
             .git_exclude(!no_gitignore)
-            .parents(true);
+            .parents(true)
+            .sort_by_file_name(|a, b| a.cmp(b));
         wb.build()
     }
 }
 
+pub fn find_repo_root(start: &Path) -> Option<PathBuf> {
+    start.ancestors().find(|d| d.join(".git").exists()).map(Path::to_path_buf)
+}
+
+/// Groups of distinct paths that differ only in letter case, e.g. `README.md` and `Readme.md`. Both can
+/// live in a repo authored on Linux, but a case-insensitive checkout keeps only one of them.
+pub fn case_collisions<S: AsRef<str>>(paths: &[S]) -> Vec<Vec<String>> {
+    let mut groups: std::collections::BTreeMap<String, Vec<String>> = std::collections::BTreeMap::new();
+    for p in paths {
+        let group = groups.entry(p.as_ref().to_lowercase()).or_default();
+        if !group.iter().any(|q| q == p.as_ref()) { group.push(p.as_ref().to_string()); }
+    }
+    groups.into_values().filter(|g| g.len() > 1).map(|mut g| { g.sort(); g }).collect()
+}
+
+pub fn same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        matches!((std::fs::metadata(a), std::fs::metadata(b)), (Ok(x), Ok(y)) if x.dev() == y.dev() && x.ino() == y.ino())
+    }
+    #[cfg(not(unix))]
+    matches!((std::fs::canonicalize(a), std::fs::canonicalize(b)), (Ok(x), Ok(y)) if x.to_string_lossy().to_lowercase() == y.to_string_lossy().to_lowercase())
+}
+
 pub fn collect_files(factory: &dyn WalkerFactory) -> Vec<PathBuf> {
     factory
-        .build(false)
-        .into_iter()
+        .build(Path::new("."), false)
         .filter_map(|e| e.ok())
         .filter(|e| e.file_type().map(|ft| ft.is_file()).unwrap_or(false))
         .map(|e| e.into_path())
         .collect()
-}
\ No newline at end of file
+}
+
+pub struct AtomicOutput {
+    target: PathBuf,
+    tmp: PathBuf,
+    writer: Option<BufWriter<File>>,
+}
+
+impl AtomicOutput {
+    pub fn create(target: &Path, append: bool) -> Result<Self> {
+        // Write through a symlink to the file it names, so the link survives the rename.
+        let resolved = std::fs::canonicalize(target).ok();
+        let target = resolved.as_deref().unwrap_or(target);
+        let mut name = target.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+        name.push(format!(".tmp-{}", std::process::id()));
+        let tmp = target.with_file_name(name);
+        if append && target.exists() {
+            std::fs::copy(target, &tmp).with_context(|| format!("Failed to prepare output file for appending: {}", target.display()))?;
+        }
+        let f = std::fs::OpenOptions::new().create(true).write(true).append(append).truncate(!append).open(&tmp)
+            .with_context(|| format!("Failed to create output file: {}", tmp.display()))?;
+        if let Ok(meta) = std::fs::metadata(target) {
+            f.set_permissions(meta.permissions()).with_context(|| format!("Failed to copy permissions of: {}", target.display()))?;
+        }
+        Ok(Self { target: target.to_path_buf(), tmp, writer: Some(BufWriter::new(f)) })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        std::fs::metadata(&self.tmp).map(|m| m.len() == 0).unwrap_or(true)
+    }
+
+    pub fn commit(mut self) -> Result<()> {
+        if let Some(w) = self.writer.take() {
+            let f = w.into_inner().map_err(|e| e.into_error()).context("Failed to flush final output")?;
+            f.sync_all().context("Failed to flush final output")?;
+        }
+        replace_file(&self.tmp, &self.target).inspect_err(|_| { let _ = std::fs::remove_file(&self.tmp); })
+            .with_context(|| format!("Failed to move output into place: {}", self.target.display()))
+    }
+}
+
+#[cfg(not(windows))]
+fn replace_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::rename(from, to)
+}
+
+#[cfg(windows)]
+fn replace_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    match std::fs::rename(from, to) {
+        Err(e) if to.exists() => {
+            std::fs::remove_file(to).map_err(|_| e)?;
+            std::fs::rename(from, to)
+        }
+        r => r,
+    }
+}
+
+impl Write for AtomicOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writer.as_mut().expect("output already committed").write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.as_mut().expect("output already committed").flush()
+    }
+}
+
+impl Drop for AtomicOutput {
+    fn drop(&mut self) {
+        if self.writer.take().is_some() { let _ = std::fs::remove_file(&self.tmp); }
+    }
+}
+
+include!("fs_tests.rs");
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbatim_prefixes() {
+        assert_eq!(verbatim_path(r"C:\repo\node_modules\a.js"), r"\\?\C:\repo\node_modules\a.js");
+        assert_eq!(verbatim_path("C:/repo/src/main.rs"), r"\\?\C:\repo\src\main.rs");
+        assert_eq!(verbatim_path(r"\\server\share\a.txt"), r"\\?\UNC\server\share\a.txt");
+        assert_eq!(verbatim_path(r"\\?\C:\already\there"), r"\\?\C:\already\there");
+        assert_eq!(verbatim_path(r"\\.\pipe\x"), r"\\.\pipe\x");
+    }
+
+    #[test]
+    fn long_path_leaves_display_path_alone() {
+        let p = Path::new("src/main.rs");
+        if cfg!(windows) { assert!(long_path(p).to_string_lossy().starts_with(r"\\?\")); } else { assert_eq!(long_path(p), p); }
+    }
+
+    #[test]
+    fn finds_case_collisions() {
+        let paths = ["docs/README.md", "src/main.rs", "docs/Readme.md", "SRC/Main.rs", "docs/readme.MD", "src/main.rs"];
+        assert_eq!(case_collisions(&paths), vec![
+            vec!["docs/README.md".to_string(), "docs/Readme.md".to_string(), "docs/readme.MD".to_string()],
+            vec!["SRC/Main.rs".to_string(), "src/main.rs".to_string()],
+        ]);
+        assert!(case_collisions(&["a.txt", "b.txt", "a.txt"]).is_empty());
+        assert!(case_collisions::<&str>(&[]).is_empty());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_reader_matches_the_buffered_reader() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sample.txt");
+        let fixed = ["", "\n", "a", "a\n", "a\r\nb", "a\r\nb\r\n", "x\r", "\r\n\r\n", "one\ntwo\r\nthree", "é\r\r\nü\n\n"];
+        let mut seed = 0x2545_f491_u32;
+        let alphabet = ['a', 'b', ' ', '\n', '\r', 'é', '\t'];
+        let generated = (0..200).map(|_| (0..seed % 40).map(|_| {
+            seed ^= seed << 13; seed ^= seed >> 17; seed ^= seed << 5;
+            alphabet[seed as usize % alphabet.len()]
+        }).collect::<String>());
+        for text in fixed.iter().map(|s| s.to_string()).chain(generated) {
+            std::fs::write(&path, &text).unwrap();
+            let buffered = StdFileReader::read_buffered(&path).unwrap();
+            assert_eq!(MmapFileReader.read_to_string(&path).unwrap(), buffered, "{:?}", text);
+            assert_eq!(normalize_lines(&text), buffered, "{:?}", text);
+            assert_eq!(MmapFileReader.read_lossy(&path).unwrap(), StdFileReader.read_lossy(&path).unwrap(), "{:?}", text);
+        }
+        std::fs::write(&path, b"ok\n\xff\xfe\r\n").unwrap();
+        assert!(MmapFileReader.read_to_string(&path).is_err() && StdFileReader::read_buffered(&path).is_err());
+        assert_eq!(MmapFileReader.read_lossy(&path).unwrap(), StdFileReader.read_lossy(&path).unwrap());
+    }
+}
@@ -0,0 +1,17 @@
+use std::path::Path;
+
+pub const MINIFIED_SUFFIXES: [&str; 2] = [".min.js", ".min.css"];
+pub const LONG_LINE_AVERAGE: usize = 500;
+pub const LONG_LINE_MIN_BYTES: usize = 4096;
+pub const MARKER_LINES: usize = 5;
+pub const MARKERS: [&str; 2] = ["@generated", "DO NOT EDIT"];
+
+pub fn classify(path: &Path, content: &str, lines: usize) -> Option<&'static str> {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if MINIFIED_SUFFIXES.iter().any(|s| name.ends_with(s)) { return Some("minified"); }
+    if content.len() >= LONG_LINE_MIN_BYTES && content.len() / lines.max(1) > LONG_LINE_AVERAGE { return Some("long-lines"); }
+    if content.lines().take(MARKER_LINES).any(|l| MARKERS.iter().any(|m| l.contains(m))) { return Some("generated-marker"); }
+    None
+}
+
+include!("generated_tests.rs");
@@ -0,0 +1,29 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minified_extensions() {
+        assert_eq!(classify(Path::new("dist/bundle.min.js"), "x", 1), Some("minified"));
+        assert_eq!(classify(Path::new("Site.MIN.CSS"), "x", 1), Some("minified"));
+        assert_eq!(classify(Path::new("src/min.js"), "x", 1), None);
+    }
+
+    #[test]
+    fn long_average_lines() {
+        let blob = "a".repeat(3 * 1024 * 1024);
+        assert_eq!(classify(Path::new("dist/app.js"), &blob, 3), Some("long-lines"));
+        let short = "a".repeat(LONG_LINE_MIN_BYTES - 1);
+        assert_eq!(classify(Path::new("one_line.txt"), &short, 1), None);
+        let normal = "let x = 1;\n".repeat(1000);
+        assert_eq!(classify(Path::new("src/app.js"), &normal, 1000), None);
+    }
+
+    #[test]
+    fn generated_markers_in_the_first_lines() {
+        assert_eq!(classify(Path::new("schema.rs"), "// @generated by diesel\nfn a() {}\n", 2), Some("generated-marker"));
+        assert_eq!(classify(Path::new("x.pb.go"), "// Code generated by protoc-gen-go. DO NOT EDIT.\n", 1), Some("generated-marker"));
+        let late = format!("{}// DO NOT EDIT\n", "line\n".repeat(MARKER_LINES));
+        assert_eq!(classify(Path::new("notes.md"), &late, MARKER_LINES + 1), None);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::fs::FileReader;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn git(root: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let out = Command::new("git").arg("-C").arg(root).args(args).output().context("Failed to run git")?;
+    if !out.status.success() { anyhow::bail!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&out.stderr).trim()); }
+    Ok(out.stdout)
+}
+
+fn split_nul(out: &[u8]) -> impl Iterator<Item = PathBuf> + '_ {
+    out.split(|&b| b == 0).filter(|s| !s.is_empty()).map(|s| PathBuf::from(String::from_utf8_lossy(s).into_owned()))
+}
+
+pub fn tracked_files(root: &Path) -> Result<HashSet<PathBuf>> {
+    let out = git(root, &["ls-files", "-z"]).with_context(|| format!("--git-only needs a git repository, but {} is not inside one", root.display()))?;
+    Ok(split_nul(&out).collect())
+}
+
+pub fn changed_files(root: &Path, rev: &str) -> Result<HashSet<PathBuf>> {
+    let committed = git(root, &["diff", "--name-only", "-z", "--relative", "--diff-filter=d", "--end-of-options", &format!("{}...HEAD", rev), "--"])?;
+    let uncommitted = git(root, &["diff", "--name-only", "-z", "--relative", "--diff-filter=d", "HEAD", "--"])?;
+    Ok(split_nul(&committed).chain(split_nul(&uncommitted)).collect())
+}
+
+pub fn staged_files(root: &Path) -> Result<HashSet<PathBuf>> {
+    let out = git(root, &["diff", "--name-only", "-z", "--relative", "--cached", "--diff-filter=d", "--"])
+        .with_context(|| format!("--staged needs a git repository, but {} is not inside one", root.display()))?;
+    Ok(split_nul(&out).collect())
+}
+
+/// A file in the tree of a revision, as listed by `git ls-tree`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blob {
+    pub oid: String,
+    pub size: u64,
+}
+
+/// Every blob under `root` in `rev`'s tree, by path relative to `root`.
+pub fn tree_blobs(root: &Path, rev: &str) -> Result<Vec<(PathBuf, Blob)>> {
+    let out = git(root, &["ls-tree", "-r", "-l", "-z", "--end-of-options", rev])
+        .with_context(|| format!("--at-rev needs a git repository with revision '{}', but {} has none", rev, root.display()))?;
+    Ok(out.split(|&b| b == 0).filter(|s| !s.is_empty()).filter_map(|entry| {
+        let entry = String::from_utf8_lossy(entry);
+        let (info, path) = entry.split_once('\t')?;
+        let mut fields = info.split_whitespace().skip(1);
+        let (kind, oid, size) = (fields.next()?, fields.next()?, fields.next()?.parse().ok()?);
+        (kind == "blob").then(|| (PathBuf::from(path), Blob { oid: oid.to_string(), size }))
+    }).collect())
+}
+
+pub fn read_blob(root: &Path, oid: &str) -> Result<Vec<u8>> {
+    git(root, &["cat-file", "blob", oid])
+}
+
+pub struct GitIndexReader;
+
+impl FileReader for GitIndexReader {
+    fn read_to_string(&self, path: &Path) -> Result<(String, usize)> {
+        let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let name = path.file_name().with_context(|| format!("Not a file: {}", path.display()))?;
+        let blob = git(dir, &["show", &format!(":./{}", name.to_string_lossy())])
+            .with_context(|| format!("Failed to read staged content of: {}", path.display()))?;
+        let text = String::from_utf8_lossy(&blob);
+        let mut content = String::with_capacity(text.len() + 1);
+        let mut lines = 0usize;
+        for line in text.lines() {
+            content.push_str(line);
+            content.push('\n');
+            lines += 1;
+        }
+        Ok((content, lines))
+    }
+}
@@ -0,0 +1,49 @@
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Language {
+    pub name: &'static str,
+    /// Markdown fence info string; empty when there's no common one.
+    pub fence: &'static str,
+}
+
+const fn lang(name: &'static str, fence: &'static str) -> Language { Language { name, fence } }
+
+const LANGUAGES: &[(&[&str], Language)] = &[
+    (&["rs"], lang("Rust", "rust")),
+    (&["py"], lang("Python", "python")),
+    (&["js", "mjs", "cjs"], lang("JavaScript", "javascript")),
+    (&["ts", "mts", "cts"], lang("TypeScript", "typescript")),
+    (&["tsx"], lang("TSX", "tsx")),
+    (&["jsx"], lang("JSX", "jsx")),
+    (&["java"], lang("Java", "java")),
+    (&["kt", "kts"], lang("Kotlin", "kotlin")),
+    (&["go"], lang("Go", "go")),
+    (&["c", "h"], lang("C", "c")),
+    (&["cpp", "cc", "cxx", "hpp", "hh"], lang("C++", "cpp")),
+    (&["cs"], lang("C#", "csharp")),
+    (&["rb"], lang("Ruby", "ruby")),
+    (&["php"], lang("PHP", "php")),
+    (&["swift"], lang("Swift", "swift")),
+    (&["sh", "bash", "zsh"], lang("Shell", "bash")),
+    (&["ps1"], lang("PowerShell", "powershell")),
+    (&["sql"], lang("SQL", "sql")),
+    (&["html", "htm"], lang("HTML", "html")),
+    (&["css"], lang("CSS", "css")),
+    (&["scss"], lang("SCSS", "scss")),
+    (&["json"], lang("JSON", "json")),
+    (&["yaml", "yml"], lang("YAML", "yaml")),
+    (&["toml"], lang("TOML", "toml")),
+    (&["xml"], lang("XML", "xml")),
+    (&["md", "mdx"], lang("Markdown", "markdown")),
+    (&["txt"], lang("Text", "")),
+    (&["ipynb"], lang("Jupyter", "")),
+];
+
+/// The language of a file by extension (case-insensitive), None if unknown.
+pub fn language(path: &Path) -> Option<Language> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    LANGUAGES.iter().find(|(exts, _)| exts.contains(&ext.as_str())).map(|(_, l)| *l)
+}
+
+include!("lang_tests.rs");
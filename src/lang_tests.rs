@@ -0,0 +1,12 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_by_extension() {
+        assert_eq!(language(Path::new("src/main.RS")).map(|l| l.name), Some("Rust"));
+        assert_eq!(language(Path::new("a.mts")), Some(Language { name: "TypeScript", fence: "typescript" }));
+        assert_eq!(language(Path::new("notes.txt")).map(|l| l.fence), Some(""));
+        assert_eq!((language(Path::new("Makefile")), language(Path::new("x.zig"))), (None, None));
+    }
+}
@@ -1,10 +1,35 @@
 pub mod cli;
 pub mod tokenizer;
 pub mod binary;
+pub mod chunk;
 pub mod patterns;
 pub mod fs;
 pub mod clipboard;
 pub mod app;
+pub mod archive;
+#[cfg(any(feature = "bench", test))]
+pub mod bench;
+pub mod attributes;
+pub mod config;
+pub mod error;
+pub mod extract;
+pub mod format;
+pub mod generated;
+pub mod lang;
+pub mod git;
+pub mod limits;
+pub mod cost;
+pub mod models;
+pub mod picker;
+pub mod report;
+pub mod snapshot;
+pub mod state;
+pub mod transform;
+pub mod tree;
+pub mod units;
 
-pub use app::{run_app, Deps, Stats};
-pub use cli::Args;
\ No newline at end of file
+#[allow(deprecated)]
+pub use app::run_app_positional;
+pub use app::{collect, run_app, run_with, Aggregation, Deps, FileEntry, FileEvent, FileKind, FileStats, Options, Stats, StdinFile, Timing};
+pub use cli::Args;
+pub use error::LfError;
\ No newline at end of file
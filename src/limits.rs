@@ -0,0 +1,83 @@
+use crate::format::group_key;
+use crate::units::{format_size, thousands, SizeUnits};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cap {
+    Files(usize),
+    Bytes(u64),
+    Tokens(usize),
+    TokensPerDir(usize),
+}
+
+impl Cap {
+    pub fn describe(&self, units: SizeUnits) -> String {
+        match self {
+            Cap::Files(n) => format!("--max-files {}", n),
+            Cap::Bytes(n) => format!("--max-total-bytes {}", format_size(*n, units)),
+            Cap::Tokens(n) => format!("--max-tokens {}", n),
+            Cap::TokensPerDir(n) => format!("--max-tokens-per-dir {}", n),
+        }
+    }
+}
+
+/// The cap that stopped selection and the files it left out, in selection order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capped {
+    pub cap: Cap,
+    pub omitted: Vec<String>,
+}
+
+/// How many leading files fit under both caps, and the cap that stopped the selection if one did.
+pub fn fit_selection(sizes: &[u64], max_files: Option<usize>, max_bytes: Option<u64>) -> (usize, Option<Cap>) {
+    let mut total = 0u64;
+    for (i, &size) in sizes.iter().enumerate() {
+        if let Some(n) = max_files && i >= n { return (i, Some(Cap::Files(n))); }
+        total += size;
+        if let Some(b) = max_bytes && total > b { return (i, Some(Cap::Bytes(b))); }
+    }
+    (sizes.len(), None)
+}
+
+pub fn fit_tokens(tokens: &[usize], max: usize) -> usize {
+    let mut total = 0;
+    tokens.iter().take_while(|&&t| { total += t; total <= max }).count()
+}
+
+/// The files `--max-tokens-per-dir` left out of one directory group, in sorted order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirCapped {
+    pub dir: String,
+    pub omitted: Vec<String>,
+}
+
+/// Groups `files` (display path and tokens) by their first `depth` directories and fits each group to `max`
+/// tokens on its own, taking its files in path order. Returns the omitted indexes of each group that went over,
+/// in group order.
+pub fn fit_tokens_per_dir(files: &[(&str, usize)], depth: usize, max: usize) -> Vec<(String, Vec<usize>)> {
+    let mut order: Vec<(String, usize)> = files.iter().enumerate().map(|(i, (path, _))| (group_key(path, depth), i)).collect();
+    order.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| files[a.1].0.cmp(files[b.1].0)));
+    let mut over = Vec::new();
+    for group in order.chunk_by(|a, b| a.0 == b.0) {
+        let fit = fit_tokens(&group.iter().map(|&(_, i)| files[i].1).collect::<Vec<_>>(), max);
+        if fit < group.len() { over.push((group[0].0.clone(), group[fit..].iter().map(|&(_, i)| i).collect())); }
+    }
+    over
+}
+
+pub fn render_omitted(capped: &Capped, units: SizeUnits) -> String {
+    let mut s = format!("Stopped at {}: omitted {} files\n", capped.cap.describe(units), thousands(capped.omitted.len()));
+    for p in &capped.omitted { s.push_str(&format!("  {}\n", p)); }
+    s
+}
+
+pub fn render_dir_omitted(max: usize, groups: &[DirCapped]) -> String {
+    let mut s = String::new();
+    for g in groups {
+        let dir = if g.dir.is_empty() { "." } else { g.dir.as_str() };
+        s.push_str(&format!("Stopped {}/ at {}: omitted {} files\n", dir, Cap::TokensPerDir(max).describe(SizeUnits::Binary), thousands(g.omitted.len())));
+        for p in &g.omitted { s.push_str(&format!("  {}\n", p)); }
+    }
+    s
+}
+
+include!("limits_tests.rs");
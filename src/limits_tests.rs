@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_stops_at_the_first_cap_hit() {
+        assert_eq!(fit_selection(&[10, 20, 30], None, None), (3, None));
+        assert_eq!(fit_selection(&[10, 20, 30], Some(2), None), (2, Some(Cap::Files(2))));
+        assert_eq!(fit_selection(&[10, 20, 30], None, Some(30)), (2, Some(Cap::Bytes(30))));
+        assert_eq!(fit_selection(&[10, 20, 30], Some(1), Some(30)), (1, Some(Cap::Files(1))));
+        assert_eq!(fit_selection(&[10, 20, 30], Some(3), Some(29)), (1, Some(Cap::Bytes(29))));
+        assert_eq!(fit_selection(&[10, 20, 30], Some(3), Some(60)), (3, None));
+    }
+
+    #[test]
+    fn tokens_fit_as_a_prefix() {
+        assert_eq!(fit_tokens(&[5, 5, 5], 10), 2);
+        assert_eq!(fit_tokens(&[5, 1, 1], 4), 0);
+        assert_eq!(fit_tokens(&[], 4), 0);
+    }
+
+    #[test]
+    fn omitted_files_are_listed() {
+        let capped = Capped { cap: Cap::Bytes(2048), omitted: vec!["b.txt".to_string(), "c.txt".to_string()] };
+        assert_eq!(render_omitted(&capped, SizeUnits::Binary), "Stopped at --max-total-bytes 2.0 KiB: omitted 2 files\n  b.txt\n  c.txt\n");
+    }
+
+    #[test]
+    fn each_directory_fits_its_own_budget() {
+        let files = [("src/b.rs", 50), ("docs/guide.md", 80), ("src/a.rs", 60), ("README.md", 500), ("src/c/d.rs", 10), ("docs/api.md", 30)];
+        assert_eq!(fit_tokens_per_dir(&files, 1, 100), vec![("".to_string(), vec![3]), ("docs".to_string(), vec![1]), ("src".to_string(), vec![0, 4])]);
+        assert_eq!(fit_tokens_per_dir(&files, 2, 100), vec![("".to_string(), vec![3]), ("docs".to_string(), vec![1]), ("src".to_string(), vec![0])]);
+        assert_eq!(fit_tokens_per_dir(&files, 1, 500), vec![]);
+        assert_eq!(fit_tokens_per_dir(&[], 1, 0), vec![]);
+    }
+
+    #[test]
+    fn dir_omissions_are_listed_per_group() {
+        let groups = [DirCapped { dir: String::new(), omitted: vec!["README.md".to_string()] }, DirCapped { dir: "src".to_string(), omitted: vec!["src/b.rs".to_string(), "src/c.rs".to_string()] }];
+        assert_eq!(render_dir_omitted(100, &groups), "Stopped ./ at --max-tokens-per-dir 100: omitted 1 files\n  README.md\nStopped src/ at --max-tokens-per-dir 100: omitted 2 files\n  src/b.rs\n  src/c.rs\n");
+    }
+}
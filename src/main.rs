@@ -1,36 +1,107 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
-use lf::{run_app, Args, Deps};
-use lf::clipboard::SystemClipboard;
-use lf::fs::{StdFileReader, StdWalkerFactory};
+use lf::{run_app, Args, Deps, LfError};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-
-#[cfg(feature = "token-counting")]
-use lf::tokenizer::O200kTokenizer as TokenImpl;
-#[cfg(not(feature = "token-counting"))]
-use lf::tokenizer::DummyTokenizer as TokenImpl;
+#[cfg(feature = "clipboard")]
+use lf::clipboard::{make_clipboard, ClipboardSink, CommandClipboard};
+use lf::config::Config;
+use lf::models::{context_limit, context_warning};
+use lf::fs::{StdFileReader, StdWalkerFactory};
+use lf::git::GitIndexReader;
+use lf::snapshot::{diff_snapshots, read_snapshot, render_diff};
+use lf::tokenizer::{make_tokenizer, NO_TOKENIZER};
 
 fn main() -> Result<()> {
-    let args = Args::parse();
-    if args.patterns.is_empty() {
-        eprintln!("Error: At least one pattern must be provided");
-        std::process::exit(1);
+    let mut args = Args::parse();
+    let level = match args.verbose { 0 => tracing::Level::WARN, 1 => tracing::Level::INFO, _ => tracing::Level::DEBUG };
+    tracing_subscriber::fmt().with_max_level(level).with_writer(std::io::stderr).without_time().init();
+    args.merge_env();
+    let config = Config::discover(args.config.as_deref())?;
+    let merged = args.merge_config(&config);
+    if !cfg!(feature = "clipboard") { args.no_clipboard = true; }
+    if let [old, new] = args.diff.as_slice() {
+        let diff = read_snapshot(old).and_then(|o| Ok(diff_snapshots(&o, &read_snapshot(new)?)));
+        let result = diff.and_then(|d| {
+            print!("{}", render_diff(&d));
+            if args.fail_on_change && !d.is_empty() { Err(LfError::Changed(d.summary())) } else { Ok(()) }
+        });
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            std::process::exit(e.exit_code());
+        }
+        return Ok(());
     }
-    let tokenizer = Arc::new(TokenImpl::new().unwrap_or_else(|_| unreachable!()));
+    // `-` is stdin, read once however often it's given, not a glob.
+    let stdin = args.patterns.iter().any(|p| p == "-").then(|| {
+        let mut buf = Vec::new();
+        std::io::stdin().read_to_end(&mut buf).map(|_| String::from_utf8_lossy(&buf).into_owned())
+    }).transpose().context("Failed to read stdin")?;
+    args.patterns.retain(|p| p != "-");
+    if args.patterns.is_empty() && stdin.is_none() {
+        if args.require_patterns {
+            eprintln!("Error: At least one pattern must be provided");
+            std::process::exit(1);
+        }
+        eprintln!("No patterns given; defaulting to '**/*'");
+        args.patterns.push("**/*".to_string());
+    }
+    let target = match (&args.target_model, args.target_context) {
+        (Some(m), _) => Some((m.clone(), context_limit(m, &config.models)?)),
+        (None, Some(n)) => Some(("the target".to_string(), n)),
+        (None, None) => None,
+    };
+    let tokenizer = make_tokenizer(args.tokenizer)?;
+    let counting = tokenizer.name() != NO_TOKENIZER;
+    #[cfg(feature = "clipboard")]
+    let clipboard: Box<dyn ClipboardSink> = match args.clipboard_cmd.as_deref() {
+        Some(cmd) => Box::new(CommandClipboard::parse(cmd).map_err(anyhow::Error::msg)?),
+        None => Box::new(make_clipboard(args.clipboard_backend, args.osc52_truncate)),
+    };
     let deps = Deps {
         walker: &StdWalkerFactory,
-        reader: &StdFileReader,
+        reader: if args.staged { &GitIndexReader } else { &StdFileReader },
         tokenizer,
-        clipboard: Some(&SystemClipboard),
+        #[cfg(feature = "clipboard")]
+        clipboard: Some(clipboard.as_ref()),
+        #[cfg(not(feature = "clipboard"))]
+        clipboard: None,
     };
-    let _stats = run_app(
-        deps,
-        &args.patterns,
-        args.output.as_deref(),
-        args.no_clipboard,
-        args.mask_java_imports,
-        args.no_gitignore,
-    )?;
+    let cancel = Arc::new(AtomicBool::new(false));
+    let flag = cancel.clone();
+    ctrlc::set_handler(move || if flag.swap(true, Ordering::Relaxed) { std::process::exit(130) }).context("Failed to install Ctrl-C handler")?;
+    let options = args.to_options().map(|o| match stdin { Some(content) => o.stdin(args.stdin_name.clone(), content), None => o });
+    let result = merged.and_then(|_| options.map_err(LfError::from)).and_then(|opts| run_app(deps, &opts.cancel(cancel)));
+    let stats = match result {
+        Ok(stats) => stats,
+        Err(LfError::NoMatches) => {
+            println!("No files found matching the patterns.");
+            std::process::exit(2);
+        }
+        Err(LfError::Cancelled) => {
+            eprintln!("Interrupted");
+            std::process::exit(130);
+        }
+        Err(e) => {
+            let code = e.exit_code();
+            eprintln!("Error: {:?}", anyhow::Error::from(e));
+            std::process::exit(code);
+        }
+    };
+    if let Some(path) = &args.stats_json {
+        let json = serde_json::to_string_pretty(&stats)?;
+        if path.as_os_str() == "-" { eprintln!("{}", json); }
+        else { std::fs::write(path, json + "\n").with_context(|| format!("Failed to write stats JSON: {}", path.display()))?; }
+    }
+    if args.estimate_cost {
+        if counting { eprint!("{}", lf::cost::render_estimates(stats.tokens, &lf::cost::price_table(&config.prices))); }
+        else { eprintln!("Cost estimate unavailable: token counting disabled (use --tokenizer approx or build with the token-counting feature)"); }
+    }
+    if let Some((label, limit)) = target
+        && let Some(w) = context_warning(stats.tokens, &label, limit) {
+        eprintln!("{}", w);
+        if args.fit { std::process::exit(1); }
+    }
     Ok(())
 }
-
@@ -0,0 +1,30 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+pub const DEFAULT_CONTEXTS: &[(&str, usize)] = &[
+    ("gpt-4o", 128_000),
+    ("gpt-4o-mini", 128_000),
+    ("o3", 200_000),
+    ("o3-mini", 200_000),
+    ("claude-3-5-sonnet", 200_000),
+    ("claude-sonnet", 200_000),
+    ("gemini-1.5-pro", 2_000_000),
+];
+
+pub fn context_limit(model: &str, overrides: &BTreeMap<String, usize>) -> Result<usize> {
+    if let Some(n) = overrides.get(model) { return Ok(*n); }
+    if let Some((_, n)) = DEFAULT_CONTEXTS.iter().find(|(m, _)| *m == model) { return Ok(*n); }
+    let mut known: Vec<&str> = DEFAULT_CONTEXTS.iter().map(|(m, _)| *m).collect();
+    known.extend(overrides.keys().map(|k| k.as_str()));
+    anyhow::bail!("unknown model '{}'; known models: {}", model, known.join(", "))
+}
+
+fn kilo(n: usize) -> String {
+    if n < 1000 { n.to_string() } else { format!("{}k", (n + 500) / 1000) }
+}
+
+pub fn context_warning(tokens: usize, label: &str, limit: usize) -> Option<String> {
+    (tokens > limit).then(|| format!("WARNING: {} tokens exceeds {}'s {} context by {}", kilo(tokens), label, kilo(limit), kilo(tokens - limit)))
+}
+
+include!("models_tests.rs");
@@ -0,0 +1,23 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_builtin_and_overridden_limits() {
+        let mut o = BTreeMap::new();
+        assert_eq!(context_limit("claude-3-5-sonnet", &o).unwrap(), 200_000);
+        o.insert("gpt-4o".to_string(), 1000);
+        o.insert("tiny".to_string(), 10);
+        assert_eq!(context_limit("gpt-4o", &o).unwrap(), 1000);
+        assert_eq!(context_limit("tiny", &o).unwrap(), 10);
+        let err = context_limit("nope", &o).unwrap_err().to_string();
+        assert!(err.contains("gpt-4o") && err.contains("tiny"));
+    }
+
+    #[test]
+    fn warns_only_over_the_limit() {
+        assert_eq!(context_warning(200_000, "claude-3-5-sonnet", 200_000), None);
+        assert_eq!(context_warning(231_000, "claude-3-5-sonnet", 200_000).unwrap(), "WARNING: 231k tokens exceeds claude-3-5-sonnet's 200k context by 31k");
+        assert_eq!(context_warning(120, "100-token", 100).unwrap(), "WARNING: 120 tokens exceeds 100-token's 100 context by 20");
+    }
+}
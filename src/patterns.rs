@@ -1,10 +1,48 @@
+use crate::error::LfError;
 use anyhow::Result;
 use globset::{Glob, GlobSet, GlobSetBuilder};
-use std::path::Path;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use unicode_normalization::UnicodeNormalization;
 
+// Hidden paths (any component starting with `.`) only match patterns that ask for hidden names:
+//
+// | pattern             | `.env` | `.env.local` | `src/.env` | `config/.secrets/key` | `.config/a.env` | `app.env` |
+// |---------------------|--------|--------------|------------|-----------------------|-----------------|-----------|
+// | `.env`              | yes    | -            | yes        | -                     | -               | -         |
+// | `**/.env`           | yes    | -            | yes        | -                     | -               | -         |
+// | `*.env*`            | yes    | yes          | yes        | -                     | -               | yes       |
+// | `config/.secrets/*` | -      | -            | -          | yes                   | -               | -         |
+// | `.*`                | yes    | yes          | yes        | -                     | yes             | -         |
+// | `**/*`              | -      | -            | -          | -                     | -               | yes       |
+//
+// A pattern with a component starting with `.` is hidden-aware as written (`is_hidden_glob`). One whose last
+// component is `*.name` also matches hidden files as if written `.name` (`hidden_variant`), with the wildcard
+// standing for nothing, so `*.env*` finds `.env` but `*.js` never picks up `.eslintrc.js`.
 fn is_hidden_glob(glob: &str) -> bool {
-    let g = glob.trim_start_matches("./");
-    (g.starts_with('.') && g.len() > 1) || g.contains("/.")
+    glob.trim_start_matches("./").split('/').any(|c| c.starts_with('.') && c != "." && c != "..")
+}
+
+fn hidden_variant(glob: &str) -> Option<String> {
+    let (dir, last) = glob.rsplit_once('/').map_or(("", glob), |(d, l)| (d, l));
+    let name = last.trim_start_matches('*');
+    let named = name.len() < last.len() && name.starts_with('.') && name[1..].starts_with(|c: char| !"*?[{/".contains(c));
+    named.then(|| if dir.is_empty() { name.to_string() } else { format!("{}/{}", dir, name) })
+}
+
+/// An absolute pattern, or one starting with `~/` (expanded against `$HOME`), names a file or directory to take
+/// as is instead of a glob over the walk; returns that path. `~name` without the slash stays an exclude.
+pub fn explicit_path(pattern: &str) -> Option<PathBuf> {
+    if let Some(rest) = pattern.strip_prefix("~/") {
+        let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+        return Some(Path::new(&home).join(rest));
+    }
+    Path::new(pattern).is_absolute().then(|| PathBuf::from(pattern))
+}
+
+/// Whether any include pattern is a glob over the walked roots rather than an `explicit_path`.
+pub fn has_globs(patterns: &[String]) -> bool {
+    patterns.iter().any(|p| !p.starts_with('~') && explicit_path(p).is_none())
 }
 
 fn normalize_pattern(p: &str) -> String {
@@ -12,13 +50,20 @@ fn normalize_pattern(p: &str) -> String {
         "." | "./" => "**/*".to_string(),
         _ => {
             if p.ends_with('/') { format!("{}**/*", p) }
-            else if p.starts_with('.') && !p.contains(['*','/']) { format!("{}/**", p) }
+            // a bare dotted name is usually a dotfile (`.env`) but may be a directory (`.github`)
+            else if p.starts_with('.') && !p.contains(['*','/']) { format!("{{{},{}/**}}", p, p) }
             else if !p.contains(['*','/','.']) { format!("{}/**", p) }
             else { p.to_string() }
         }
     }
 }
 
+// Patterns typed or tab-completed on Windows use `\`; globset and `Candidates` only deal in `/`, so a
+// `\` is always a separator here, never a glob escape (use `[*]` for a literal metacharacter).
+fn normalize_separators(p: &str) -> String {
+    p.replace('\\', "/").nfc().collect()
+}
+
 fn gitignore_line_to_glob(p: &str) -> String {
     let mut pat = p.trim().to_string();
     if pat.starts_with('/') { pat = pat.trim_start_matches('/').to_string(); }
@@ -33,60 +78,263 @@ fn gitignore_line_to_glob(p: &str) -> String {
     format!("**/{}/**", pat)
 }
 
-pub fn build_glob_sets(patterns: &[String], honor_gitignore: bool) -> Result<(GlobSet, GlobSet, GlobSet)> {
-    let mut vis_inc = GlobSetBuilder::new();
-    let mut hid_inc = GlobSetBuilder::new();
-    let mut exc = GlobSetBuilder::new();
-
-    for p in patterns {
-        if let Some(raw) = p.strip_prefix('~') { exc.add(Glob::new(raw)?); continue; }
-        let norm = normalize_pattern(p);
-        if is_hidden_glob(&norm) { hid_inc.add(Glob::new(&norm)?); } else { vis_inc.add(Glob::new(&norm)?); }
-    }
-
-    if honor_gitignore {
-        let mut add_ignore_file = |path: &Path| -> Result<()> {
-            if path.exists() {
-                let s = std::fs::read_to_string(path)?;
-                for line in s.lines() {
-                    let trimmed = line.trim();
-                    if trimmed.is_empty() || trimmed.starts_with('#') { continue; }
-                    if trimmed.starts_with('!') { continue; }
-                    if let Some(rest) = trimmed.strip_prefix('/') {
-                        let dir_pat = if rest.ends_with('/') { format!("{}**/*", rest) } else { format!("{}/**", rest) };
-                        let any_pat = if rest.ends_with('/') { format!("**/{}**/*", rest) } else { format!("**/{}/**", rest) };
-                        exc.add(Glob::new(&dir_pat)?);
-                        exc.add(Glob::new(&any_pat)?);
-                    } else {
-                        let glob_pat = gitignore_line_to_glob(trimmed);
-                        exc.add(Glob::new(&glob_pat)?);
+// `$GIT_DIR` only counts when `root` is itself a work tree, so one repo's excludes never leak into another root.
+fn git_dir(root: &Path, env_git_dir: Option<OsString>) -> Option<PathBuf> {
+    let dot_git = root.join(".git");
+    if !dot_git.exists() { return None; }
+    if let Some(dir) = env_git_dir { return Some(root.join(dir)); }
+    if dot_git.is_dir() { return Some(dot_git); }
+    // worktrees and submodules: `.git` is a file holding `gitdir: <path>`
+    let text = std::fs::read_to_string(&dot_git).ok()?;
+    text.trim().strip_prefix("gitdir:").map(|d| root.join(d.trim()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleOrigin {
+    Pattern,
+    IgnoreFile(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub pattern: String,
+    pub origin: RuleOrigin,
+    /// Position of the pattern in the list given to `PatternSet::compile`, or the 1-based line of an ignore rule.
+    pub index: usize,
+}
+
+impl Rule {
+    fn describe(&self) -> String {
+        match &self.origin {
+            RuleOrigin::Pattern => format!("pattern {:?}", self.pattern),
+            RuleOrigin::IgnoreFile(file) => format!("ignore rule {:?} from {}", self.pattern, file.display()),
+        }
+    }
+}
+
+struct SetBuilder {
+    globs: GlobSetBuilder,
+    rules: Vec<Rule>,
+}
+
+impl SetBuilder {
+    fn new() -> Self { SetBuilder { globs: GlobSetBuilder::new(), rules: Vec::new() } }
+
+    fn add(&mut self, glob: Glob, pattern: &str, origin: &RuleOrigin, index: usize) {
+        self.globs.add(glob);
+        self.rules.push(Rule { pattern: pattern.to_string(), origin: origin.clone(), index });
+    }
+
+    fn build(self) -> Result<(GlobSet, Vec<Rule>)> {
+        Ok((self.globs.build()?, self.rules))
+    }
+}
+
+/// Where `PatternSet::compile` looks for ignore files, and whether it reads them at all.
+#[derive(Debug, Clone)]
+pub struct PatternOptions {
+    pub gitignore: bool,
+    pub root: PathBuf,
+}
+
+impl Default for PatternOptions {
+    fn default() -> Self { PatternOptions { gitignore: false, root: PathBuf::from(".") } }
+}
+
+/// Compiled include/exclude patterns with lf's semantics: `~` excludes, a bare name means a directory,
+/// and hidden paths only match patterns that name something hidden (`.env`, `**/.github/**`, `*.env*`).
+///
+/// ```
+/// use lf::patterns::{MatchResult, PatternOptions, PatternSet};
+/// use std::path::Path;
+///
+/// let patterns = vec!["src".to_string(), "~**/*_test.rs".to_string(), ".github".to_string()];
+/// let set = PatternSet::compile(&patterns, &PatternOptions::default()).unwrap();
+///
+/// assert!(set.matches(Path::new("src/main.rs")).is_match());
+/// assert!(set.matches(Path::new(".github/workflows/ci.yml")).is_match());
+/// match set.matches(Path::new("src/app_test.rs")) {
+///     MatchResult::Excluded { rule, .. } => assert_eq!(rule.pattern, "~**/*_test.rs"),
+///     other => panic!("{:?}", other),
+/// }
+/// assert_eq!(set.matches(Path::new("src/.env")), MatchResult::HiddenSkipped);
+/// assert_eq!(set.matches(Path::new("README.md")), MatchResult::Unmatched);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PatternSet {
+    pub include: GlobSet,
+    pub hidden_include: GlobSet,
+    pub exclude: GlobSet,
+    include_rules: Vec<Rule>,
+    hidden_rules: Vec<Rule>,
+    exclude_rules: Vec<Rule>,
+}
+
+/// How a path fared against a `PatternSet`, with the rule that decided it (the first one, in pattern order).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchResult<'a> {
+    Matched { rule: &'a Rule, hidden: bool },
+    Excluded { rule: &'a Rule, include: &'a Rule, hidden: bool },
+    /// A hidden path that no hidden-aware include pattern (`.env`, `**/.github/**`, ...) matches.
+    HiddenSkipped,
+    Unmatched,
+}
+
+/// Why a walked path was left out, as recorded for `--show-skipped`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    Gitignore,
+    ExcludePattern(String),
+    Hidden,
+    NotMatched,
+}
+
+impl MatchResult<'_> {
+    pub fn is_match(&self) -> bool { matches!(self, MatchResult::Matched { .. }) }
+
+    pub fn rejection(&self) -> Option<Rejection> {
+        match self {
+            MatchResult::Matched { .. } => None,
+            MatchResult::Excluded { rule: Rule { origin: RuleOrigin::IgnoreFile(_), .. }, .. } => Some(Rejection::Gitignore),
+            MatchResult::Excluded { rule, .. } => Some(Rejection::ExcludePattern(rule.pattern.clone())),
+            MatchResult::HiddenSkipped => Some(Rejection::Hidden),
+            MatchResult::Unmatched => Some(Rejection::NotMatched),
+        }
+    }
+
+    pub fn set(&self) -> &'static str {
+        match self {
+            MatchResult::Excluded { .. } => "exclude",
+            MatchResult::Matched { hidden: true, .. } | MatchResult::HiddenSkipped => "hidden",
+            MatchResult::Matched { hidden: false, .. } | MatchResult::Unmatched => "visible",
+        }
+    }
+
+    pub fn explain(&self) -> String {
+        match self {
+            MatchResult::Matched { rule, hidden } => format!("included by pattern {:?}{}", rule.pattern, if *hidden { " (hidden path)" } else { "" }),
+            MatchResult::Excluded { rule, include, .. } => format!("excluded by {} (include pattern {:?} matched)", rule.describe(), include.pattern),
+            MatchResult::HiddenSkipped => "not matched: hidden path, and no include pattern naming hidden files ('.env', '**/.github/**', '*.env*') matches it".to_string(),
+            MatchResult::Unmatched => "not matched by any include pattern".to_string(),
+        }
+    }
+}
+
+impl PatternSet {
+    pub fn compile(patterns: &[String], opts: &PatternOptions) -> Result<PatternSet> {
+        let mut vis_inc = SetBuilder::new();
+        let mut hid_inc = SetBuilder::new();
+        let mut exc = SetBuilder::new();
+
+        for (i, p) in patterns.iter().enumerate().filter(|(_, p)| explicit_path(p).is_none()) {
+            let compile = |g: &str| Glob::new(g).map_err(|source| LfError::InvalidPattern { pattern: p.clone(), source });
+            let slashed = normalize_separators(p);
+            if let Some(raw) = slashed.strip_prefix('~') {
+                let raw = if raw.ends_with('/') { format!("{}**", raw) } else { raw.to_string() };
+                exc.add(compile(&raw)?, p, &RuleOrigin::Pattern, i);
+                continue;
+            }
+            let norm = normalize_pattern(&slashed);
+            if is_hidden_glob(&slashed) { hid_inc.add(compile(&norm)?, p, &RuleOrigin::Pattern, i); continue; }
+            vis_inc.add(compile(&norm)?, p, &RuleOrigin::Pattern, i);
+            if let Some(hidden) = hidden_variant(&norm) { hid_inc.add(compile(&hidden)?, p, &RuleOrigin::Pattern, i); }
+        }
+
+        if opts.gitignore {
+            let mut add_ignore_file = |path: &Path| -> Result<()> {
+                if path.exists() {
+                    let origin = RuleOrigin::IgnoreFile(path.to_path_buf());
+                    let s = std::fs::read_to_string(path)?;
+                    for (n, line) in s.lines().enumerate() {
+                        let trimmed = line.trim();
+                        if trimmed.is_empty() || trimmed.starts_with('#') { continue; }
+                        if trimmed.starts_with('!') { continue; }
+                        if let Some(rest) = trimmed.strip_prefix('/') {
+                            let dir_pat = if rest.ends_with('/') { format!("{}**/*", rest) } else { format!("{}/**", rest) };
+                            let any_pat = if rest.ends_with('/') { format!("**/{}**/*", rest) } else { format!("**/{}/**", rest) };
+                            exc.add(Glob::new(&dir_pat)?, trimmed, &origin, n + 1);
+                            exc.add(Glob::new(&any_pat)?, trimmed, &origin, n + 1);
+                        } else {
+                            let glob_pat = gitignore_line_to_glob(trimmed);
+                            exc.add(Glob::new(&glob_pat)?, trimmed, &origin, n + 1);
+                        }
                     }
                 }
+                Ok(())
+            };
+            let _ = add_ignore_file(&opts.root.join(".gitignore"));
+            if let Some(dir) = git_dir(&opts.root, std::env::var_os("GIT_DIR")) { let _ = add_ignore_file(&dir.join("info").join("exclude")); }
+            if let Ok(home) = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")) {
+                let h = Path::new(&home);
+                let _ = add_ignore_file(&h.join(".gitignore_global"));
+                let _ = add_ignore_file(&h.join(".config").join("git").join("ignore"));
             }
-            Ok(())
+        }
+
+        let (include, include_rules) = vis_inc.build()?;
+        let (hidden_include, hidden_rules) = hid_inc.build()?;
+        let (exclude, exclude_rules) = exc.build()?;
+        Ok(PatternSet { include, hidden_include, exclude, include_rules, hidden_rules, exclude_rules })
+    }
+
+    pub fn matches(&self, path: &Path) -> MatchResult<'_> {
+        let c = Candidates::new(path);
+        let (set, rules) = if c.hidden { (&self.hidden_include, &self.hidden_rules) } else { (&self.include, &self.include_rules) };
+        let Some(include) = c.first_rule(set, rules) else {
+            return if c.hidden { MatchResult::HiddenSkipped } else { MatchResult::Unmatched };
         };
-        let cwd = Path::new(".");
-        let _ = add_ignore_file(&cwd.join(".gitignore"));
-        let _ = add_ignore_file(&cwd.join(".git").join("info").join("exclude"));
-        if let Ok(home) = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")) {
-            let h = Path::new(&home);
-            let _ = add_ignore_file(&h.join(".gitignore_global"));
-            let _ = add_ignore_file(&h.join(".config").join("git").join("ignore"));
+        match c.first_rule(&self.exclude, &self.exclude_rules) {
+            Some(rule) => MatchResult::Excluded { rule, include, hidden: c.hidden },
+            None => MatchResult::Matched { rule: include, hidden: c.hidden },
         }
     }
 
-    Ok((vis_inc.build()?, hid_inc.build()?, exc.build()?))
+    pub fn explain(&self, path: &Path) -> String { self.matches(path).explain() }
+
+    /// Indices of every include pattern matching `path`, not just the deciding one.
+    pub fn matching_patterns(&self, path: &Path) -> Vec<usize> {
+        let c = Candidates::new(path);
+        let (set, rules) = if c.hidden { (&self.hidden_include, &self.hidden_rules) } else { (&self.include, &self.include_rules) };
+        let mut found: Vec<usize> = [&c.full, &c.stripped, &c.file].iter().flat_map(|s| set.matches(s)).map(|i| rules[i].index).collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+pub fn build_glob_sets(patterns: &[String], honor_gitignore: bool, root: &Path) -> Result<PatternSet> {
+    PatternSet::compile(patterns, &PatternOptions { gitignore: honor_gitignore, root: root.to_path_buf() })
+}
+
+struct Candidates {
+    full: String,
+    stripped: String,
+    file: String,
+    hidden: bool,
+}
+
+impl Candidates {
+    fn new(path: &Path) -> Self {
+        // NFC so a pattern typed as `café` matches a name macOS stored decomposed (`cafe\u{301}`).
+        let full: String = path.to_string_lossy().replace('\\', "/").nfc().collect();
+        let stripped = full.strip_prefix("./").unwrap_or(&full).to_string();
+        let file = path.file_name().map(|f| f.to_string_lossy().nfc().collect()).unwrap_or_default();
+        let hidden = stripped.split('/').any(|c| c.starts_with('.') && c != "." && c != "..");
+        Candidates { full, stripped, file, hidden }
+    }
+
+    fn is_match(&self, set: &GlobSet) -> bool {
+        set.is_match(&self.full) || set.is_match(&self.stripped) || set.is_match(&self.file)
+    }
+
+    fn first_rule<'a>(&self, set: &GlobSet, rules: &'a [Rule]) -> Option<&'a Rule> {
+        [&self.full, &self.stripped, &self.file].iter().flat_map(|c| set.matches(c)).min().map(|i| &rules[i])
+    }
 }
 
 pub fn path_matches(path: &Path, include_set: &GlobSet, hidden_include_set: &GlobSet, exclude_set: &GlobSet) -> bool {
-    let path_str = path.to_string_lossy().replace('\\', "/");
-    let stripped = path_str.strip_prefix("./").unwrap_or(&path_str);
-    let file = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
-    let hidden = stripped.split('/').any(|c| c.starts_with('.') && c != "." && c != "..");
-    let inc = if hidden {
-        hidden_include_set.is_match(&path_str) || hidden_include_set.is_match(stripped) || hidden_include_set.is_match(&file)
-    } else {
-        include_set.is_match(&path_str) || include_set.is_match(stripped) || include_set.is_match(&file)
-    };
-    inc && !exclude_set.is_match(&path_str) && !exclude_set.is_match(stripped) && !exclude_set.is_match(&file)
-}
\ No newline at end of file
+    let c = Candidates::new(path);
+    c.is_match(if c.hidden { hidden_include_set } else { include_set }) && !c.is_match(exclude_set)
+}
+
+include!("patterns_tests.rs");
@@ -13,22 +13,106 @@ mod tests {
         assert_eq!(normalize_pattern("src/"), "src/**/*");
     }
 
+    #[test]
+    fn hidden_matrix() {
+        let paths = [".env", ".env.local", "src/.env", "config/.secrets/key", ".config/a.env", ".envrc", "app.env", "src/main.rs", ".eslintrc.js"];
+        let table: &[(&str, [bool; 9])] = &[
+            (".env",              [true,  false, true,  false, false, false, false, false, false]),
+            ("**/.env",           [true,  false, true,  false, false, false, false, false, false]),
+            ("**/.env*",          [true,  true,  true,  false, false, true,  false, false, false]),
+            ("*.env*",            [true,  true,  true,  false, false, true,  true,  false, false]),
+            ("*.envrc",           [false, false, false, false, false, true,  false, false, false]),
+            ("config/.secrets/*", [false, false, false, true,  false, false, false, false, false]),
+            (".*",                [true,  true,  true,  false, true,  true,  false, false, true ]),
+            ("**/*",              [false, false, false, false, false, false, true,  true,  false]),
+            ("*.js",              [false, false, false, false, false, false, false, false, false]),
+        ];
+        for (pattern, expected) in table {
+            let set = PatternSet::compile(&[pattern.to_string()], &PatternOptions::default()).unwrap();
+            for (path, want) in paths.iter().zip(expected) {
+                assert_eq!(set.matches(Path::new(path)).is_match(), *want, "{} vs {}", pattern, path);
+            }
+        }
+        assert_eq!(hidden_variant("**/*.env*").as_deref(), Some("**/.env*"));
+        assert_eq!((hidden_variant("*.*"), hidden_variant("src/**")), (None, None));
+        assert!(!is_hidden_glob("../src/*.rs") && !is_hidden_glob("./src"));
+    }
+
     #[test]
     fn build_globs_respects_gitignore_like() {
         let d = tempdir().unwrap();
         fs::write(d.path().join(".gitignore"), "/build\n/bin\nsecret.txt\n").unwrap();
         let patterns = vec!["**/*".to_string()];
-        let (inc, hid, exc) = {
-            let cwd = std::env::current_dir().unwrap();
-            std::env::set_current_dir(d.path()).unwrap();
-            let r = build_glob_sets(&patterns, true).unwrap();
-            std::env::set_current_dir(cwd).unwrap();
-            r
-        };
+        let PatternSet { include: inc, hidden_include: hid, exclude: exc, .. } =
+            PatternSet::compile(&patterns, &PatternOptions { gitignore: true, root: d.path().to_path_buf() }).unwrap();
         assert!(exc.is_match("build/file"));
         assert!(exc.is_match("x/build/file"));
         assert!(exc.is_match("bin/run"));
         assert!(exc.is_match("x/bin/run"));
         assert!(inc.is_match("src/main.rs") || hid.is_match("src/main.rs"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn info_exclude_comes_from_the_roots_git_dir() {
+        let d = tempdir().unwrap();
+        let patterns = vec!["**/*".to_string()];
+        let compile = || PatternSet::compile(&patterns, &PatternOptions { gitignore: true, root: d.path().to_path_buf() }).unwrap();
+        fs::create_dir_all(d.path().join("elsewhere/info")).unwrap();
+        fs::write(d.path().join("elsewhere/info/exclude"), "scratch/\n").unwrap();
+        assert_eq!(git_dir(d.path(), Some("elsewhere".into())), None);
+        assert!(compile().matches(Path::new("scratch/notes.md")).is_match());
+
+        fs::write(d.path().join(".git"), "gitdir: elsewhere\n").unwrap();
+        assert_eq!(git_dir(d.path(), None), Some(d.path().join("elsewhere")));
+        assert!(!compile().matches(Path::new("scratch/notes.md")).is_match());
+
+        fs::remove_file(d.path().join(".git")).unwrap();
+        fs::create_dir_all(d.path().join(".git")).unwrap();
+        assert_eq!(git_dir(d.path(), None), Some(d.path().join(".git")));
+        assert_eq!(git_dir(d.path(), Some("elsewhere".into())), Some(d.path().join("elsewhere")));
+    }
+
+    #[test]
+    fn rejections_name_the_deciding_rule() {
+        let d = tempdir().unwrap();
+        fs::write(d.path().join(".gitignore"), "dist/\n").unwrap();
+        let patterns = vec!["**/*".to_string(), "~*.log".to_string()];
+        let set = PatternSet::compile(&patterns, &PatternOptions { gitignore: true, root: d.path().to_path_buf() }).unwrap();
+        assert_eq!(set.matches(Path::new("src/a.rs")).rejection(), None);
+        assert_eq!(set.matches(Path::new("dist/a.js")).rejection(), Some(Rejection::Gitignore));
+        assert_eq!(set.matches(Path::new("out.log")).rejection(), Some(Rejection::ExcludePattern("~*.log".to_string())));
+        assert_eq!(set.matches(Path::new(".env")).rejection(), Some(Rejection::Hidden));
+        let narrow = PatternSet::compile(&["*.md".to_string()], &PatternOptions::default()).unwrap();
+        assert_eq!(narrow.matches(Path::new("a.rs")).rejection(), Some(Rejection::NotMatched));
+    }
+
+    #[test]
+    fn absolute_and_home_patterns_are_explicit_paths() {
+        let abs = std::env::temp_dir().join("x.conf");
+        assert_eq!(explicit_path(abs.to_str().unwrap()), Some(abs.clone()));
+        assert_eq!(explicit_path("src/**"), None);
+        assert_eq!(explicit_path("~target"), None);
+        if let Some(home) = std::env::var_os("HOME") { assert_eq!(explicit_path("~/notes/a.md"), Some(Path::new(&home).join("notes/a.md"))); }
+        assert!(!has_globs(&[abs.to_string_lossy().into_owned(), "~*.log".to_string()]));
+        assert!(has_globs(&["src".to_string(), abs.to_string_lossy().into_owned()]));
+        let set = PatternSet::compile(&[abs.to_string_lossy().into_owned()], &PatternOptions::default()).unwrap();
+        assert!(set.include.is_empty() && set.exclude.is_empty());
+    }
+
+    #[test]
+    fn matches_report_the_first_matching_pattern() {
+        let patterns: Vec<String> = ["docs/", "~*.log", "**/*.md", "*.env*"].iter().map(|s| s.to_string()).collect();
+        let set = PatternSet::compile(&patterns, &PatternOptions::default()).unwrap();
+        let index = |p: &str| match set.matches(Path::new(p)) { MatchResult::Matched { rule, .. } => Some(rule.index), _ => None };
+        assert_eq!((index("docs/a.md"), index("src/b.md"), index(".env"), index("docs/x.log")), (Some(0), Some(2), Some(3), None));
+    }
+
+    #[test]
+    fn matching_patterns_lists_every_include() {
+        let patterns: Vec<String> = ["src/", "**/*.rs", "~**/gen.rs", "*.env*", "docs/"].iter().map(|s| s.to_string()).collect();
+        let set = PatternSet::compile(&patterns, &PatternOptions::default()).unwrap();
+        assert_eq!(set.matching_patterns(Path::new("src/a.rs")), vec![0, 1]);
+        assert_eq!(set.matching_patterns(Path::new("src/.env")), vec![3]);
+        assert!(set.matching_patterns(Path::new("README.md")).is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+use crate::units::thousands;
+use anyhow::{Context, Result};
+use std::io::{BufRead, Write};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickItem {
+    pub label: String,
+    pub tokens: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Continue,
+    Done,
+    Abort,
+}
+
+pub struct Picker {
+    items: Vec<PickItem>,
+    kept: Vec<bool>,
+}
+
+const HELP: &str = "Toggle with numbers (3), ranges (2-5) or directories (src/); a = keep all, n = drop all, enter = confirm, q = abort";
+
+impl Picker {
+    pub fn new(items: Vec<PickItem>) -> Self {
+        let kept = vec![true; items.len()];
+        Picker { items, kept }
+    }
+
+    pub fn kept(&self) -> Vec<usize> {
+        (0..self.items.len()).filter(|&i| self.kept[i]).collect()
+    }
+
+    pub fn render(&self) -> String {
+        let width = self.items.len().to_string().len();
+        let mut out = String::new();
+        for (i, item) in self.items.iter().enumerate() {
+            let mark = if self.kept[i] { 'x' } else { ' ' };
+            out.push_str(&format!("[{}] {:>w$}  {}  (~{} tokens)\n", mark, i + 1, item.label, thousands(item.tokens), w = width));
+        }
+        let kept = self.kept();
+        let tokens: usize = kept.iter().map(|&i| self.items[i].tokens).sum();
+        out.push_str(&format!("{} of {} files kept, ~{} tokens\n", kept.len(), self.items.len(), thousands(tokens)));
+        out
+    }
+
+    fn toggle_dir(&mut self, dir: &str) -> Result<(), String> {
+        let under: Vec<usize> = (0..self.items.len()).filter(|&i| self.items[i].label.starts_with(dir)).collect();
+        if under.is_empty() { return Err(format!("no files under {}", dir)); }
+        let keep = !under.iter().any(|&i| self.kept[i]);
+        for i in under { self.kept[i] = keep; }
+        Ok(())
+    }
+
+    fn index(&self, s: &str) -> Result<usize, String> {
+        match s.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= self.items.len() => Ok(n - 1),
+            _ => Err(format!("not a file number: {}", s)),
+        }
+    }
+
+    pub fn handle(&mut self, input: &str) -> Result<Step, String> {
+        let input = input.trim();
+        match input {
+            "" => return Ok(Step::Done),
+            "q" => return Ok(Step::Abort),
+            "a" => { self.kept.iter_mut().for_each(|k| *k = true); return Ok(Step::Continue); }
+            "n" => { self.kept.iter_mut().for_each(|k| *k = false); return Ok(Step::Continue); }
+            _ => {}
+        }
+        for tok in input.split(|c: char| c == ',' || c.is_whitespace()).filter(|t| !t.is_empty()) {
+            if tok.ends_with('/') { self.toggle_dir(tok)?; }
+            else if let Some((a, b)) = tok.split_once('-') {
+                let (a, b) = (self.index(a)?, self.index(b)?);
+                for i in a.min(b)..=a.max(b) { self.kept[i] = !self.kept[i]; }
+            } else {
+                let i = self.index(tok)?;
+                self.kept[i] = !self.kept[i];
+            }
+        }
+        Ok(Step::Continue)
+    }
+}
+
+pub fn pick<R: BufRead, W: Write>(items: Vec<PickItem>, mut input: R, mut out: W) -> Result<Option<Vec<usize>>> {
+    let mut picker = Picker::new(items);
+    write!(out, "{}", picker.render()).context("Failed to write picker")?;
+    loop {
+        write!(out, "{}\n> ", HELP).context("Failed to write picker")?;
+        out.flush().context("Failed to write picker")?;
+        let mut line = String::new();
+        if input.read_line(&mut line).context("Failed to read selection")? == 0 { return Ok(None); }
+        match picker.handle(&line) {
+            Ok(Step::Done) => return Ok(Some(picker.kept())),
+            Ok(Step::Abort) => return Ok(None),
+            Ok(Step::Continue) => write!(out, "{}", picker.render()).context("Failed to write picker")?,
+            Err(e) => writeln!(out, "{}", e).context("Failed to write picker")?,
+        }
+    }
+}
+
+include!("picker_tests.rs");
@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<PickItem> {
+        ["README.md", "src/a.rs", "src/b.rs", "src/gen/c.rs", "tests/t.rs"].iter()
+            .map(|l| PickItem { label: l.to_string(), tokens: 100 }).collect()
+    }
+
+    #[test]
+    fn toggles_numbers_ranges_and_directories() {
+        let mut p = Picker::new(items());
+        assert_eq!(p.handle("1"), Ok(Step::Continue));
+        assert_eq!(p.kept(), vec![1, 2, 3, 4]);
+        p.handle("src/").unwrap();
+        assert_eq!(p.kept(), vec![4]);
+        p.handle("src/gen/ 1").unwrap();
+        assert_eq!(p.kept(), vec![0, 3, 4]);
+        p.handle("3-1").unwrap();
+        assert_eq!(p.kept(), vec![1, 2, 3, 4]);
+        p.handle("n").unwrap();
+        assert!(p.kept().is_empty());
+        p.handle("a").unwrap();
+        assert_eq!(p.kept().len(), 5);
+        assert!(p.handle("9").is_err());
+        assert!(p.handle("docs/").is_err());
+        assert_eq!(p.handle("\n"), Ok(Step::Done));
+        assert_eq!(p.handle("q"), Ok(Step::Abort));
+    }
+
+    #[test]
+    fn scripted_session() {
+        let mut out = Vec::new();
+        let kept = pick(items(), "2,4\nbogus\n\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(kept, Some(vec![0, 2, 4]));
+        let shown = String::from_utf8(out).unwrap();
+        assert!(shown.contains("[ ] 2  src/a.rs  (~100 tokens)"));
+        assert!(shown.contains("3 of 5 files kept, ~300 tokens"));
+        assert!(shown.contains("not a file number: bogus"));
+
+        assert_eq!(pick(items(), "1\nq\n".as_bytes(), Vec::new()).unwrap(), None);
+        assert_eq!(pick(items(), "1\n".as_bytes(), Vec::new()).unwrap(), None);
+    }
+}
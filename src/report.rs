@@ -0,0 +1,192 @@
+use crate::app::{FileEntry, FileMetrics, Timing};
+use crate::lang::language;
+use crate::units::{format_size, thousands, SizeUnits};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtRow {
+    pub ext: String,
+    pub files: usize,
+    pub lines: usize,
+    pub tokens: usize,
+    pub bytes: u64,
+    pub percent: f64,
+}
+
+pub(crate) fn ext_stats(files: &[FileEntry]) -> Vec<ExtRow> {
+    let mut by_ext: HashMap<String, ExtRow> = HashMap::new();
+    for f in files {
+        let ext = if f.is_binary() { "(binary)".to_string() } else {
+            f.path.extension().map(|e| format!(".{}", e.to_string_lossy().to_lowercase())).unwrap_or_else(|| "(none)".to_string())
+        };
+        let row = by_ext.entry(ext.clone()).or_insert_with(|| ExtRow { ext, ..ExtRow::default() });
+        row.files += 1;
+        row.lines += f.lines;
+        row.tokens += f.tokens;
+        row.bytes += f.content.len() as u64;
+    }
+    let total: usize = by_ext.values().map(|r| r.tokens).sum();
+    let mut rows: Vec<ExtRow> = by_ext.into_values().collect();
+    for r in &mut rows {
+        r.percent = if total == 0 { 0.0 } else { r.tokens as f64 * 100.0 / total as f64 };
+    }
+    rows.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.ext.cmp(&b.ext)));
+    rows
+}
+
+pub fn render_ext_stats(rows: &[ExtRow], units: SizeUnits) -> String {
+    let mut s = format!("{:<12} {:>7} {:>10} {:>10} {:>11} {:>7}\n", "Extension", "Files", "Lines", "Tokens", "Bytes", "Tokens%");
+    for r in rows {
+        s.push_str(&format!("{:<12} {:>7} {:>10} {:>10} {:>11} {:>6.1}%\n", r.ext, thousands(r.files), thousands(r.lines), thousands(r.tokens), format_size(r.bytes, units), r.percent));
+    }
+    s
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LangRow {
+    pub language: String,
+    pub files: usize,
+    pub lines: usize,
+    pub blank: usize,
+    pub tokens: usize,
+}
+
+pub(crate) fn lang_stats(files: &[FileEntry]) -> Vec<LangRow> {
+    let mut by_lang: HashMap<&str, LangRow> = HashMap::new();
+    for f in files {
+        let name = if f.is_binary() { "(binary)" } else { language(&f.path).map_or("Other", |l| l.name) };
+        let row = by_lang.entry(name).or_insert_with(|| LangRow { language: name.to_string(), ..LangRow::default() });
+        row.files += 1;
+        row.lines += f.lines;
+        if !f.is_binary() { row.blank += f.content.lines().filter(|l| l.trim().is_empty()).count(); }
+        row.tokens += f.tokens;
+    }
+    let mut rows: Vec<LangRow> = by_lang.into_values().collect();
+    rows.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.language.cmp(&b.language)));
+    rows
+}
+
+pub fn render_lang_stats(rows: &[LangRow]) -> String {
+    let mut s = format!("{:<12} {:>7} {:>10} {:>10} {:>10}\n", "Language", "Files", "Lines", "Blank", "Tokens");
+    for r in rows {
+        s.push_str(&format!("{:<12} {:>7} {:>10} {:>10} {:>10}\n", r.language, thousands(r.files), thousands(r.lines), thousands(r.blank), thousands(r.tokens)));
+    }
+    s
+}
+
+/// None when `existing` matches `generated` byte for byte, otherwise a short description of the first difference.
+pub fn describe_difference(generated: &str, existing: Option<&[u8]>) -> Option<String> {
+    let existing = match existing {
+        None => return Some("the file does not exist".to_string()),
+        Some(e) if e == generated.as_bytes() => return None,
+        Some(e) => e,
+    };
+    let common = generated.as_bytes().iter().zip(existing).take_while(|(a, b)| a == b).count();
+    let line = generated.as_bytes()[..common].iter().filter(|&&b| b == b'\n').count() + 1;
+    let delta = generated.len() as i64 - existing.len() as i64;
+    let size = match delta {
+        0 => "same size".to_string(),
+        d if d > 0 => format!("regenerating adds {} bytes", thousands(d as usize)),
+        d => format!("regenerating removes {} bytes", thousands(d.unsigned_abs() as usize)),
+    };
+    Some(format!("first difference at line {}, {}", line, size))
+}
+
+pub const DRY_RUN_LARGEST: usize = 10;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DryRun {
+    pub files: usize,
+    pub bytes: u64,
+    pub binary: usize,
+    pub largest: Vec<(String, u64)>,
+}
+
+impl DryRun {
+    pub fn estimated_tokens(&self) -> usize { (self.bytes as usize).div_ceil(4) }
+}
+
+pub(crate) fn dry_run(items: Vec<(String, u64, bool)>) -> DryRun {
+    let mut d = DryRun { files: items.len(), ..DryRun::default() };
+    for (_, size, binary) in &items {
+        d.bytes += size;
+        if *binary { d.binary += 1; }
+    }
+    let mut largest: Vec<(String, u64)> = items.into_iter().map(|(path, size, _)| (path, size)).collect();
+    largest.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    largest.truncate(DRY_RUN_LARGEST);
+    d.largest = largest;
+    d
+}
+
+pub fn render_dry_run(d: &DryRun, units: SizeUnits) -> String {
+    let mut s = format!("Would emit {} files ({} binary), {}, ~{} tokens (bytes/4)\n", thousands(d.files), thousands(d.binary), format_size(d.bytes, units), thousands(d.estimated_tokens()));
+    if !d.largest.is_empty() { s.push_str("Largest files:\n"); }
+    for (path, size) in &d.largest { s.push_str(&format!("{:>11}  {}\n", format_size(*size, units), path)); }
+    s
+}
+
+/// The first `n` lines of what went to the clipboard, and how many more there are.
+pub fn render_preview(content: &str, n: usize) -> String {
+    let mut s: String = content.lines().take(n).map(|l| format!("{}\n", l)).collect();
+    let rest = content.lines().count().saturating_sub(n);
+    if rest > 0 { s.push_str(&format!("… (+ {} more lines copied to clipboard)\n", thousands(rest))); }
+    s
+}
+
+pub const SKIPPED_EXAMPLES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SkipCategory {
+    Gitignore,
+    ExcludePattern,
+    Hidden,
+    TooLarge,
+    Binary,
+}
+
+impl SkipCategory {
+    fn label(self) -> &'static str {
+        match self {
+            SkipCategory::Gitignore => "Excluded by gitignore",
+            SkipCategory::ExcludePattern => "Excluded by ~ patterns",
+            SkipCategory::Hidden => "Hidden and not explicitly included",
+            SkipCategory::TooLarge => "Too large (over a size or token limit)",
+            SkipCategory::Binary => "Binary (--skip-binary)",
+        }
+    }
+}
+
+/// One block per category that has files, in `SkipCategory` order, each listing the first few paths.
+pub fn render_skipped(skipped: &[(SkipCategory, String)]) -> String {
+    let mut sorted: Vec<&(SkipCategory, String)> = skipped.iter().collect();
+    sorted.sort();
+    let mut s = String::new();
+    for group in sorted.chunk_by(|a, b| a.0 == b.0) {
+        s.push_str(&format!("{}: {} files\n", group[0].0.label(), thousands(group.len())));
+        for (_, path) in group.iter().take(SKIPPED_EXAMPLES) { s.push_str(&format!("  {}\n", path)); }
+        if group.len() > SKIPPED_EXAMPLES { s.push_str(&format!("  … and {} more\n", thousands(group.len() - SKIPPED_EXAMPLES))); }
+    }
+    s
+}
+
+pub const SLOWEST_FILES: usize = 10;
+
+fn ms(us: u64) -> String { format!("{:.1} ms", us as f64 / 1000.0) }
+
+/// Phase totals followed by the slowest files. Per-file phases run on worker threads, so they can add up to more than the wall-clock time.
+pub fn render_timings(t: &Timing, slowest: &[(&str, FileMetrics)]) -> String {
+    let phases = t.phases.unwrap_or_default();
+    let mut s = "Timings:\n".to_string();
+    for (name, value) in [("walk", ms(t.walk_ms * 1000)), ("read", ms(phases.read_us)), ("transform", ms(phases.transform_us)), ("tokenize", ms(phases.tokenize_us)), ("wall", ms(t.process_ms * 1000 + t.walk_ms * 1000))] {
+        s.push_str(&format!("  {:<10}{:>12}\n", name, value));
+    }
+    if slowest.is_empty() { return s; }
+    s.push_str(&format!("Slowest files:\n{:>12}{:>12}{:>12}{:>12}  path\n", "read", "transform", "tokenize", "total"));
+    for (path, m) in slowest {
+        s.push_str(&format!("{:>12}{:>12}{:>12}{:>12}  {}\n", ms(m.read_us), ms(m.transform_us), ms(m.tokenize_us), ms(m.total_us()), path));
+    }
+    s
+}
+
+include!("report_tests.rs");
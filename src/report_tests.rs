@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::FileKind;
+    use std::path::PathBuf;
+
+    fn file(path: &str, lines: usize, tokens: usize, binary: bool) -> FileEntry {
+        let kind = if binary { FileKind::Binary } else { FileKind::Text };
+        FileEntry { path: PathBuf::from(path), display: path.to_string(), content: "x".repeat(tokens), lines, tokens, kind, omitted: None, note: None, skipped_tokens: None }
+    }
+
+    #[test]
+    fn groups_by_extension_sorted_by_tokens() {
+        let files = vec![
+            file("src/a.rs", 10, 30, false),
+            file("src/b.RS", 5, 20, false),
+            file("README.md", 3, 40, false),
+            file("Makefile", 2, 7, false),
+            file("logo.png", 0, 3, true),
+        ];
+        let rows = ext_stats(&files);
+        let names: Vec<&str> = rows.iter().map(|r| r.ext.as_str()).collect();
+        assert_eq!(names, vec![".rs", ".md", "(none)", "(binary)"]);
+        assert_eq!((rows[0].files, rows[0].lines, rows[0].tokens, rows[0].bytes), (2, 15, 50, 50));
+        let sum: f64 = rows.iter().map(|r| r.percent).sum();
+        assert!((sum - 100.0).abs() < 0.01);
+        assert!(render_ext_stats(&rows, SizeUnits::Binary).lines().nth(1).unwrap().starts_with(".rs"));
+    }
+
+    #[test]
+    fn zero_tokens_gives_zero_percent() {
+        let rows = ext_stats(&[file("a.txt", 1, 0, false)]);
+        assert_eq!(rows[0].percent, 0.0);
+    }
+
+    #[test]
+    fn dry_run_totals_and_largest() {
+        let mut items: Vec<(String, u64, bool)> = (0..12).map(|i| (format!("f{:02}.txt", i), i * 100, false)).collect();
+        items.push(("logo.png".to_string(), 1100, true));
+        let d = dry_run(items);
+        assert_eq!((d.files, d.bytes, d.binary), (13, 7700, 1));
+        assert_eq!(d.estimated_tokens(), 1925);
+        assert_eq!(d.largest.len(), DRY_RUN_LARGEST);
+        assert_eq!(d.largest[..2], [("f11.txt".to_string(), 1100), ("logo.png".to_string(), 1100)]);
+        let s = render_dry_run(&d, SizeUnits::Binary);
+        assert!(s.starts_with("Would emit 13 files (1 binary), "), "{}", s);
+        assert!(s.contains("~1,925 tokens (bytes/4)\nLargest files:\n"));
+    }
+
+    #[test]
+    fn describes_output_differences() {
+        assert_eq!(describe_difference("a\nb\n", Some(b"a\nb\n")), None);
+        assert_eq!(describe_difference("a\nb\n", None).unwrap(), "the file does not exist");
+        assert_eq!(describe_difference("a\nB\nc\n", Some(b"a\nb\n")).unwrap(), "first difference at line 2, regenerating adds 2 bytes");
+        assert_eq!(describe_difference("a\n", Some(b"a\nold\n")).unwrap(), "first difference at line 2, regenerating removes 4 bytes");
+        assert_eq!(describe_difference("x", Some(b"y")).unwrap(), "first difference at line 1, same size");
+    }
+
+    #[test]
+    fn timings_list_phases_then_slowest_files() {
+        let timing = Timing { walk_ms: 2, process_ms: 5, total_ms: 7, phases: Some(FileMetrics { read_us: 1500, transform_us: 250, tokenize_us: 3000 }) };
+        let slow = FileMetrics { read_us: 1000, transform_us: 200, tokenize_us: 2500 };
+        let s = render_timings(&timing, &[("src/big.rs", slow)]);
+        assert!(s.starts_with("Timings:\n  walk            2.0 ms\n  read            1.5 ms\n  transform       0.2 ms\n  tokenize        3.0 ms\n  wall            7.0 ms\nSlowest files:\n"), "{}", s);
+        assert!(s.ends_with("      1.0 ms      0.2 ms      2.5 ms      3.7 ms  src/big.rs\n"), "{}", s);
+        assert!(!render_timings(&timing, &[]).contains("Slowest"));
+    }
+
+    #[test]
+    fn preview_shows_the_first_lines_and_counts_the_rest() {
+        assert_eq!(render_preview("a\nb\nc\n", 2), "a\nb\n… (+ 1 more lines copied to clipboard)\n");
+        assert_eq!(render_preview("a\nb\n", 2), "a\nb\n");
+        assert_eq!(render_preview(&"x\n".repeat(1020), 0), "… (+ 1,020 more lines copied to clipboard)\n");
+    }
+
+    #[test]
+    fn groups_by_language_with_blank_lines() {
+        let mut files = vec![file("a.rs", 3, 5, false), file("b.RS", 1, 2, false), file("c.mts", 2, 4, false), file("Makefile", 1, 1, false), file("x.zig", 4, 1, false), file("logo.png", 0, 3, true)];
+        files[0].content = "fn a() {}\n\n  \n".to_string();
+        let rows = lang_stats(&files);
+        let names: Vec<&str> = rows.iter().map(|r| r.language.as_str()).collect();
+        assert_eq!(names, vec!["Other", "Rust", "TypeScript", "(binary)"]);
+        assert_eq!((rows[1].files, rows[1].lines, rows[1].blank, rows[1].tokens), (2, 4, 2, 7));
+        assert_eq!((rows[0].files, rows[0].lines), (2, 5));
+        assert!(render_lang_stats(&rows).lines().nth(2).unwrap().starts_with("Rust "));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::error::LfError;
+use crate::units::thousands;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotFile {
+    pub path: String,
+    pub tokens: usize,
+    pub content: String,
+}
+
+/// Reads the file records of a `--format json` array or a `--format jsonl` stream (its summary line is skipped).
+pub fn parse_snapshot(text: &str) -> Result<Vec<SnapshotFile>, LfError> {
+    let invalid = |msg: String| LfError::InvalidOption(format!("not an lf --format json/jsonl snapshot: {}", msg));
+    let values: Vec<serde_json::Value> = if text.trim_start().starts_with('[') {
+        serde_json::from_str(text).map_err(|e| invalid(e.to_string()))?
+    } else {
+        text.lines().filter(|l| !l.trim().is_empty()).map(serde_json::from_str).collect::<Result<_, _>>().map_err(|e| invalid(e.to_string()))?
+    };
+    let mut files = Vec::new();
+    for v in values.iter().filter(|v| v.get("type").is_none_or(|t| t != "summary")) {
+        let (Some(path), Some(content)) = (v.get("path").and_then(|p| p.as_str()), v.get("content").and_then(|c| c.as_str())) else {
+            return Err(invalid("records need \"path\" and \"content\" (chunked output cannot be diffed)".to_string()));
+        };
+        let tokens = v.get("tokens").and_then(|t| t.as_u64()).unwrap_or(0) as usize;
+        files.push(SnapshotFile { path: path.to_string(), tokens, content: content.to_string() });
+    }
+    Ok(files)
+}
+
+pub fn read_snapshot(path: &Path) -> Result<Vec<SnapshotFile>, LfError> {
+    let text = std::fs::read_to_string(path).map_err(|source| LfError::ReadError { path: path.to_path_buf(), source })?;
+    parse_snapshot(&text).map_err(|e| LfError::InvalidOption(format!("{}: {}", path.display(), e)))
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    pub added: Vec<(String, usize)>,
+    pub removed: Vec<(String, usize)>,
+    pub changed: Vec<(String, usize, usize)>,
+    pub unchanged: usize,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool { self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty() }
+
+    pub fn token_delta(&self) -> i64 {
+        let added: i64 = self.added.iter().map(|(_, t)| *t as i64).sum();
+        let removed: i64 = self.removed.iter().map(|(_, t)| *t as i64).sum();
+        let changed: i64 = self.changed.iter().map(|(_, old, new)| *new as i64 - *old as i64).sum();
+        added - removed + changed
+    }
+
+    pub fn summary(&self) -> String {
+        format!("{} added, {} removed, {} changed", self.added.len(), self.removed.len(), self.changed.len())
+    }
+}
+
+pub fn diff_snapshots(old: &[SnapshotFile], new: &[SnapshotFile]) -> SnapshotDiff {
+    let old: BTreeMap<&str, &SnapshotFile> = old.iter().map(|f| (f.path.as_str(), f)).collect();
+    let new: BTreeMap<&str, &SnapshotFile> = new.iter().map(|f| (f.path.as_str(), f)).collect();
+    let mut d = SnapshotDiff::default();
+    for (path, f) in &new {
+        match old.get(path) {
+            None => d.added.push((path.to_string(), f.tokens)),
+            Some(o) if o.content != f.content => d.changed.push((path.to_string(), o.tokens, f.tokens)),
+            Some(_) => d.unchanged += 1,
+        }
+    }
+    d.removed = old.iter().filter(|(p, _)| !new.contains_key(*p)).map(|(p, f)| (p.to_string(), f.tokens)).collect();
+    d
+}
+
+fn signed(n: i64) -> String {
+    format!("{}{}", if n < 0 { "-" } else { "+" }, thousands(n.unsigned_abs() as usize))
+}
+
+pub fn render_diff(d: &SnapshotDiff) -> String {
+    if d.is_empty() { return format!("No changes ({} files)\n", thousands(d.unchanged)); }
+    let mut s = String::new();
+    for (path, tokens) in &d.added { s.push_str(&format!("+ {} ({} tokens)\n", path, signed(*tokens as i64))); }
+    for (path, tokens) in &d.removed { s.push_str(&format!("- {} ({} tokens)\n", path, signed(-(*tokens as i64)))); }
+    for (path, old, new) in &d.changed { s.push_str(&format!("~ {} ({} tokens)\n", path, signed(*new as i64 - *old as i64))); }
+    s.push_str(&format!("Total: {} tokens ({}, {} unchanged)\n", signed(d.token_delta()), d.summary(), thousands(d.unchanged)));
+    s
+}
+
+include!("snapshot_tests.rs");
@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, tokens: usize, content: &str) -> SnapshotFile {
+        SnapshotFile { path: path.to_string(), tokens, content: content.to_string() }
+    }
+
+    #[test]
+    fn parses_json_and_jsonl() {
+        let array = "[\n  {\"path\":\"a.rs\",\"lines\":1,\"tokens\":3,\"content\":\"fn a\\n\"}\n]\n";
+        assert_eq!(parse_snapshot(array).unwrap(), vec![file("a.rs", 3, "fn a\n")]);
+        let lines = "{\"path\":\"a.rs\",\"lines\":1,\"tokens\":3,\"content\":\"x\"}\n{\"type\":\"summary\",\"files\":1,\"lines\":1,\"tokens\":3}\n";
+        assert_eq!(parse_snapshot(lines).unwrap(), vec![file("a.rs", 3, "x")]);
+        assert_eq!(parse_snapshot("[]").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        assert!(matches!(parse_snapshot("src/a.rs\nfn a() {}\n"), Err(LfError::InvalidOption(_))));
+        let chunks = "[{\"path\":\"a.rs\",\"start_line\":1,\"end_line\":2,\"tokens\":3,\"text\":\"x\"}]";
+        assert!(parse_snapshot(chunks).unwrap_err().to_string().contains("chunked output cannot be diffed"));
+    }
+
+    #[test]
+    fn classifies_added_removed_and_changed() {
+        let old = vec![file("keep.rs", 5, "same"), file("gone.rs", 7, "old"), file("edit.rs", 10, "v1")];
+        let new = vec![file("edit.rs", 14, "v2"), file("keep.rs", 5, "same"), file("new.rs", 4, "hi")];
+        let d = diff_snapshots(&old, &new);
+        assert_eq!(d.added, vec![("new.rs".to_string(), 4)]);
+        assert_eq!(d.removed, vec![("gone.rs".to_string(), 7)]);
+        assert_eq!(d.changed, vec![("edit.rs".to_string(), 10, 14)]);
+        assert_eq!((d.unchanged, d.token_delta()), (1, 1));
+        assert_eq!(render_diff(&d), "+ new.rs (+4 tokens)\n- gone.rs (-7 tokens)\n~ edit.rs (+4 tokens)\nTotal: +1 tokens (1 added, 1 removed, 1 changed, 1 unchanged)\n");
+        assert_eq!(render_diff(&diff_snapshots(&old, &old)), "No changes (3 files)\n");
+    }
+}
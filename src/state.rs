@@ -0,0 +1,61 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+pub const STATE_SUFFIX: &str = ".lf-state";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub path: String,
+    pub size: u64,
+    pub mtime_secs: u64,
+    pub mtime_nanos: u32,
+}
+
+impl FileStamp {
+    pub fn of(shown: &str, meta: Option<&std::fs::Metadata>) -> Self {
+        let mtime = meta.and_then(|m| m.modified().ok()).and_then(|t| t.duration_since(UNIX_EPOCH).ok()).unwrap_or_default();
+        FileStamp { path: shown.to_string(), size: meta.map_or(0, |m| m.len()), mtime_secs: mtime.as_secs(), mtime_nanos: mtime.subsec_nanos() }
+    }
+}
+
+/// What an `--incremental` run remembers about the inputs that produced its output.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct State {
+    pub fingerprint: String,
+    pub files: Vec<FileStamp>,
+    pub output_size: u64,
+    pub tokens: usize,
+}
+
+impl State {
+    /// True when `current` was built from the same options and files, and the output still has the size this state wrote.
+    pub fn matches(&self, current: &State, output: &Path) -> bool {
+        self.fingerprint == current.fingerprint && self.files == current.files
+            && std::fs::metadata(output).is_ok_and(|m| m.len() == self.output_size)
+    }
+}
+
+pub fn fingerprint(options: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(format!("{}\n{}", env!("CARGO_PKG_VERSION"), options).as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn state_path(output: &Path) -> PathBuf {
+    let mut name = output.as_os_str().to_os_string();
+    name.push(STATE_SUFFIX);
+    PathBuf::from(name)
+}
+
+pub fn load(path: &Path) -> Option<State> {
+    serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+pub fn save(path: &Path, state: &State) -> Result<()> {
+    let json = serde_json::to_string_pretty(state)?;
+    std::fs::write(path, json + "\n").with_context(|| format!("Failed to write state file: {}", path.display()))
+}
+
+include!("state_tests.rs");
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn stamp(path: &str, size: u64, secs: u64) -> FileStamp {
+        FileStamp { path: path.to_string(), size, mtime_secs: secs, mtime_nanos: 0 }
+    }
+
+    #[test]
+    fn state_path_sits_next_to_output() {
+        assert_eq!(state_path(Path::new("context/dump.txt")), PathBuf::from("context/dump.txt.lf-state"));
+    }
+
+    #[test]
+    fn fingerprint_depends_on_options() {
+        assert_eq!(fingerprint("a"), fingerprint("a"));
+        assert_ne!(fingerprint("a"), fingerprint("b"));
+        assert_eq!(fingerprint("a").len(), 64);
+    }
+
+    #[test]
+    fn round_trips_and_matches() {
+        let d = tempdir().unwrap();
+        let output = d.path().join("dump.txt");
+        std::fs::write(&output, "12345").unwrap();
+        let saved = State { fingerprint: fingerprint("opts"), files: vec![stamp("a.rs", 10, 1), stamp("b.rs", 20, 2)], output_size: 5, tokens: 42 };
+        save(&state_path(&output), &saved).unwrap();
+        let loaded = load(&state_path(&output)).unwrap();
+        assert_eq!(loaded, saved);
+
+        let current = State { tokens: 0, output_size: 0, ..saved.clone() };
+        assert!(loaded.matches(&current, &output));
+        assert!(!loaded.matches(&State { fingerprint: fingerprint("other"), ..current.clone() }, &output));
+        assert!(!loaded.matches(&State { files: vec![stamp("a.rs", 10, 1), stamp("b.rs", 20, 3)], ..current.clone() }, &output));
+        assert!(!loaded.matches(&State { files: vec![stamp("a.rs", 10, 1)], ..current.clone() }, &output));
+        std::fs::write(&output, "edited by hand").unwrap();
+        assert!(!loaded.matches(&current, &output));
+        std::fs::remove_file(&output).unwrap();
+        assert!(!loaded.matches(&current, &output));
+    }
+
+    #[test]
+    fn missing_or_corrupt_state_is_none() {
+        let d = tempdir().unwrap();
+        assert!(load(&d.path().join("nope")).is_none());
+        std::fs::write(d.path().join("bad"), "{not json").unwrap();
+        assert!(load(&d.path().join("bad")).is_none());
+    }
+
+    #[test]
+    fn stamps_real_files() {
+        let d = tempdir().unwrap();
+        let p = d.path().join("a.txt");
+        std::fs::write(&p, "hello").unwrap();
+        let s = FileStamp::of("a.txt", std::fs::metadata(&p).ok().as_ref());
+        assert_eq!((s.path.as_str(), s.size), ("a.txt", 5));
+        assert!(s.mtime_secs > 0);
+    }
+}
@@ -1,7 +1,83 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
+#[cfg(feature = "token-counting")]
+use anyhow::Context;
+use std::sync::{Arc, OnceLock};
+
+pub const NO_TOKENIZER: &str = "none";
 
 pub trait Tokenizer: Send + Sync {
     fn count_tokens(&self, text: &str) -> usize;
+    fn name(&self) -> &str { "custom" }
+
+    /// Loads whatever counting needs, so a failure is reported once before files are counted in parallel.
+    fn prepare(&self) -> Result<()> { Ok(()) }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenizerKind {
+    O200k,
+    Cl100k,
+    Approx,
+}
+
+pub fn make_tokenizer(kind: Option<TokenizerKind>) -> Result<Arc<dyn Tokenizer>> {
+    match kind {
+        Some(TokenizerKind::Approx) => Ok(Arc::new(ApproxTokenizer)),
+        #[cfg(feature = "token-counting")]
+        None | Some(TokenizerKind::O200k) => Ok(Arc::new(LazyTokenizer::new("o200k_base", || Ok(Arc::new(O200kTokenizer::new()?))))),
+        #[cfg(feature = "token-counting")]
+        Some(TokenizerKind::Cl100k) => Ok(Arc::new(LazyTokenizer::new("cl100k_base", || Ok(Arc::new(Cl100kTokenizer::new()?))))),
+        #[cfg(not(feature = "token-counting"))]
+        None => Ok(Arc::new(DummyTokenizer)),
+        #[cfg(not(feature = "token-counting"))]
+        Some(k) => anyhow::bail!("tokenizer {:?} requires the token-counting feature; available tokenizers: approx", k),
+    }
+}
+
+type MakeTokenizer = Box<dyn Fn() -> Result<Arc<dyn Tokenizer>> + Send + Sync>;
+
+/// Builds the wrapped tokenizer on `prepare` or the first `count_tokens` call, so runs that never count (no matches,
+/// `--dry-run`, `--explain`) don't pay for loading BPE ranks. A failed build is kept and returned by `prepare`.
+pub struct LazyTokenizer {
+    name: String,
+    init: MakeTokenizer,
+    inner: OnceLock<Result<Arc<dyn Tokenizer>, String>>,
+}
+
+impl LazyTokenizer {
+    pub fn new(name: impl Into<String>, init: impl Fn() -> Result<Arc<dyn Tokenizer>> + Send + Sync + 'static) -> Self {
+        LazyTokenizer { name: name.into(), init: Box::new(init), inner: OnceLock::new() }
+    }
+
+    pub fn is_initialized(&self) -> bool { self.inner.get().is_some_and(Result::is_ok) }
+
+    fn built(&self) -> Result<&Arc<dyn Tokenizer>, &str> {
+        self.inner.get_or_init(|| (self.init)().map_err(|e| format!("failed to initialize the {} tokenizer: {:#}", self.name, e)))
+            .as_ref().map_err(String::as_str)
+    }
+}
+
+impl Tokenizer for LazyTokenizer {
+    // Counts 0 when the build failed; callers learn of that from `prepare`.
+    fn count_tokens(&self, text: &str) -> usize {
+        self.built().map_or(0, |t| t.count_tokens(text))
+    }
+
+    fn name(&self) -> &str { &self.name }
+
+    fn prepare(&self) -> Result<()> {
+        self.built().map(|_| ()).map_err(|e| anyhow::anyhow!("{}", e))
+    }
+}
+
+pub struct ApproxTokenizer;
+
+impl Tokenizer for ApproxTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(4)
+    }
+
+    fn name(&self) -> &str { "approx" }
 }
 
 #[cfg(feature = "token-counting")]
@@ -22,6 +98,30 @@ impl Tokenizer for O200kTokenizer {
     fn count_tokens(&self, text: &str) -> usize {
         self.bpe.encode_with_special_tokens(text).len()
     }
+
+    fn name(&self) -> &str { "o200k_base" }
+}
+
+#[cfg(feature = "token-counting")]
+pub struct Cl100kTokenizer {
+    bpe: tiktoken_rs::CoreBPE,
+}
+
+#[cfg(feature = "token-counting")]
+impl Cl100kTokenizer {
+    pub fn new() -> Result<Self> {
+        let bpe = tiktoken_rs::cl100k_base().context("Failed to initialize cl100k_base tokenizer")?;
+        Ok(Self { bpe })
+    }
+}
+
+#[cfg(feature = "token-counting")]
+impl Tokenizer for Cl100kTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        self.bpe.encode_with_special_tokens(text).len()
+    }
+
+    fn name(&self) -> &str { "cl100k_base" }
 }
 
 #[cfg(not(feature = "token-counting"))]
@@ -32,9 +132,13 @@ impl Tokenizer for DummyTokenizer {
     fn count_tokens(&self, _text: &str) -> usize {
         0
     }
+
+    fn name(&self) -> &str { NO_TOKENIZER }
 }
 
 #[cfg(not(feature = "token-counting"))]
 impl DummyTokenizer {
     pub fn new() -> anyhow::Result<Self> { Ok(Self) }
-}
\ No newline at end of file
+}
+
+include!("tokenizer_tests.rs");
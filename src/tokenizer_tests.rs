@@ -10,4 +10,62 @@ mod tests {
             assert_eq!(t.count_tokens("hello"), 0);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn approx_is_chars_over_four() {
+        let t = ApproxTokenizer;
+        assert_eq!(t.count_tokens(""), 0);
+        assert_eq!(t.count_tokens("abcd"), 1);
+        assert_eq!(t.count_tokens("abcde"), 2);
+        assert_eq!(t.count_tokens("éééé"), 1);
+        assert_eq!(t.name(), "approx");
+    }
+
+    #[cfg(feature = "token-counting")]
+    #[test]
+    fn approx_tracks_exact_counts() {
+        let text = "fn main() {\n    println!(\"Hello, world!\");\n}\n".repeat(20);
+        let approx = ApproxTokenizer.count_tokens(&text) as f64;
+        for t in [make_tokenizer(Some(TokenizerKind::O200k)).unwrap(), make_tokenizer(Some(TokenizerKind::Cl100k)).unwrap()] {
+            let exact = t.count_tokens(&text) as f64;
+            assert!(approx > exact * 0.5 && approx < exact * 2.0, "{} vs {} for {}", approx, exact, t.name());
+        }
+    }
+
+    #[test]
+    fn selection_names() {
+        assert_eq!(make_tokenizer(Some(TokenizerKind::Approx)).unwrap().name(), "approx");
+        #[cfg(feature = "token-counting")]
+        {
+            assert_eq!(make_tokenizer(None).unwrap().name(), "o200k_base");
+            assert_eq!(make_tokenizer(Some(TokenizerKind::Cl100k)).unwrap().name(), "cl100k_base");
+        }
+        #[cfg(not(feature = "token-counting"))]
+        {
+            assert_eq!(make_tokenizer(None).unwrap().name(), NO_TOKENIZER);
+            assert!(make_tokenizer(Some(TokenizerKind::Cl100k)).is_err());
+        }
+    }
+
+    #[test]
+    fn lazy_tokenizer_builds_once_on_first_count() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let built = Arc::new(AtomicUsize::new(0));
+        let counter = built.clone();
+        let t = LazyTokenizer::new("approx", move || { counter.fetch_add(1, Ordering::SeqCst); Ok(Arc::new(ApproxTokenizer)) });
+        assert_eq!(t.name(), "approx");
+        assert!(!t.is_initialized());
+        assert_eq!(t.count_tokens("abcdefgh"), 2);
+        assert_eq!(t.count_tokens("abcd"), 1);
+        assert!(t.is_initialized());
+        assert_eq!(built.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn lazy_tokenizer_reports_a_failed_build_without_panicking() {
+        let t = LazyTokenizer::new("broken", || anyhow::bail!("ranks missing"));
+        assert_eq!(t.count_tokens("abcd"), 0);
+        assert_eq!(t.prepare().unwrap_err().to_string(), "failed to initialize the broken tokenizer: ranks missing");
+        assert!(!t.is_initialized());
+    }
+}
@@ -0,0 +1,650 @@
+use crate::app::Options;
+use crate::error::LfError;
+use crate::units::thousands;
+use std::path::Path;
+use std::sync::Arc;
+
+pub fn head_lines(content: &str, n: usize) -> (String, usize) {
+    let total = content.lines().count();
+    if total <= n { return (content.to_string(), 0); }
+    let mut out = String::new();
+    for line in content.lines().take(n) {
+        out.push_str(line);
+        out.push('\n');
+    }
+    (out, total - n)
+}
+
+pub fn truncation_marker(omitted: usize) -> String {
+    format!("… ({} more lines)\n", omitted)
+}
+
+pub const DEFAULT_REGION_MARKERS: (&str, &str) = ("lf:skip-start", "lf:skip-end");
+
+pub fn region_placeholder(n: usize) -> String {
+    format!("[… {} {} elided (lf:skip region) …]\n", thousands(n), if n == 1 { "line" } else { "lines" })
+}
+
+/// Replaces the lines between a `start` and the next `end` marker line with a placeholder, keeping the markers.
+/// Markers don't nest: a second start inside a region is elided with it, and a stray end (or a line naming both) is kept as text.
+/// Returns the line of an unclosed start marker as the error, so the file can be kept whole.
+pub fn elide_regions(content: &str, start: &str, end: &str) -> Result<String, usize> {
+    let mut out = String::with_capacity(content.len());
+    let mut open: Option<(usize, usize)> = None;
+    for (i, line) in content.lines().enumerate() {
+        if let Some((at, n)) = open {
+            if !line.contains(end) { open = Some((at, n + 1)); continue; }
+            if n > 0 { out.push_str(&region_placeholder(n)); }
+            open = None;
+        } else if line.contains(start) && !line.contains(end) {
+            open = Some((i + 1, 0));
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    if let Some((at, _)) = open { return Err(at); }
+    if !content.ends_with('\n') { out.pop(); }
+    Ok(out)
+}
+
+pub fn truncate_long_lines(content: &str, max: usize) -> String {
+    let mut out = String::with_capacity(content.len());
+    for line in content.lines() {
+        match line.char_indices().nth(max) {
+            Some((cut, _)) => {
+                out.push_str(&line[..cut]);
+                out.push_str(&format!(" …[truncated {} chars]", thousands(line[cut..].chars().count())));
+            }
+            None => out.push_str(line),
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub fn expand_tabs(content: &str, width: usize, all: bool) -> String {
+    let width = width.max(1);
+    let mut out = String::with_capacity(content.len());
+    for line in content.lines() {
+        let mut col = 0;
+        let mut leading = true;
+        for c in line.chars() {
+            leading &= c == ' ' || c == '\t';
+            if c == '\t' && (leading || all) {
+                let n = width - col % width;
+                out.extend(std::iter::repeat_n(' ', n));
+                col += n;
+            } else {
+                out.push(c);
+                col += 1;
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+pub fn dedent(content: &str) -> String {
+    let indent = |l: &str| l.len() - l.trim_start_matches([' ', '\t']).len();
+    let mut common: Option<&str> = None;
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        let ws = &line[..indent(line)];
+        common = Some(match common {
+            None => ws,
+            Some(c) => &c[..c.bytes().zip(ws.bytes()).take_while(|(a, b)| a == b).count()],
+        });
+    }
+    let common = common.unwrap_or("");
+    if common.is_empty() { return content.to_string(); }
+    let mut out = String::with_capacity(content.len());
+    for line in content.lines() {
+        out.push_str(line.strip_prefix(common).unwrap_or(line.trim_start_matches([' ', '\t'])));
+        out.push('\n');
+    }
+    out
+}
+
+pub const SAMPLE_JSON_MIN_BYTES: usize = 16 * 1024;
+
+fn keep_lines(content: &str, n: usize, unit: &str) -> String {
+    let total = content.lines().count();
+    let mut out: String = content.lines().take(n).map(|l| format!("{}\n", l)).collect();
+    if total > n { out.push_str(&format!("… ({} more {})\n", thousands(total - n), unit)); }
+    out
+}
+
+pub fn sample_data(path: &Path, content: &str, n: usize) -> Option<String> {
+    match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+        "csv" | "tsv" => Some(keep_lines(content, n + 1, "rows")),
+        "jsonl" | "ndjson" if content.len() >= SAMPLE_JSON_MIN_BYTES => Some(keep_lines(content, n, "lines")),
+        "json" if content.len() >= SAMPLE_JSON_MIN_BYTES => match serde_json::from_str::<serde_json::Value>(content) {
+            Ok(serde_json::Value::Array(items)) => {
+                let mut out = serde_json::to_string_pretty(&items[..n.min(items.len())]).ok()?;
+                out.push('\n');
+                if items.len() > n { out.push_str(&format!("… ({} more elements)\n", thousands(items.len() - n))); }
+                Some(out)
+            }
+            Ok(_) => None,
+            Err(_) => { let (mut out, omitted) = head_lines(content, n); if omitted > 0 { out.push_str(&truncation_marker(omitted)); } Some(out) }
+        },
+        _ => None,
+    }
+}
+
+pub fn strip_ansi(content: &str) -> String {
+    #[derive(PartialEq)]
+    enum State { Text, Escape, Csi, Osc, OscEscape }
+    let mut out = String::with_capacity(content.len());
+    let mut state = State::Text;
+    for c in content.chars() {
+        state = match state {
+            State::Text if c == '\x1b' => State::Escape,
+            State::Text if c == '\u{9b}' => State::Csi,
+            State::Text => { out.push(c); State::Text }
+            State::Escape if c == '[' => State::Csi,
+            State::Escape if c == ']' => State::Osc,
+            State::Escape if ('\x20'..='\x2f').contains(&c) => State::Escape,
+            State::Escape => State::Text,
+            State::Csi if ('\x40'..='\x7e').contains(&c) => State::Text,
+            State::Csi if ('\x20'..='\x3f').contains(&c) => State::Csi,
+            State::Csi => { out.push(c); State::Text }
+            State::Osc if c == '\x07' => State::Text,
+            State::Osc if c == '\x1b' => State::OscEscape,
+            State::Osc => State::Osc,
+            State::OscEscape if c == '\\' => State::Text,
+            State::OscEscape => State::Osc,
+        };
+    }
+    out
+}
+
+pub fn strip_frontmatter(content: &str) -> String {
+    let mut body = content;
+    let first = content.lines().next().map(str::trim_end);
+    if let Some(delim @ ("---" | "+++")) = first {
+        let mut offset = content.find('\n').map_or(content.len(), |i| i + 1);
+        for line in content[offset..].split_inclusive('\n') {
+            offset += line.len();
+            let t = line.trim_end();
+            if t == delim || (delim == "---" && t == "...") { body = &content[offset..]; break; }
+        }
+    }
+    let mut out = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(start) = rest.find("<!--") {
+        let Some(len) = rest[start..].find("-->") else { break };
+        let mut end = start + len + 3;
+        let at_line_start = start == 0 && (out.is_empty() || out.ends_with('\n')) || rest[..start].ends_with('\n');
+        if at_line_start && rest[end..].starts_with('\n') { end += 1; }
+        out.push_str(&rest[..start]);
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+pub fn number_lines(content: &str) -> String {
+    let count = content.lines().count();
+    let width = count.max(1).to_string().len();
+    let mut out = String::with_capacity(content.len() + count * (width + 3));
+    for (i, line) in content.lines().enumerate() {
+        out.push_str(&format!("{:>width$} | {}\n", i + 1, line, width = width));
+    }
+    out
+}
+
+pub fn flatten_notebook(json: &str) -> Option<String> {
+    let nb: serde_json::Value = serde_json::from_str(json).ok()?;
+    let mut out = String::new();
+    for (i, cell) in nb.get("cells")?.as_array()?.iter().enumerate() {
+        let marker = match cell.get("cell_type")?.as_str()? {
+            "code" => "",
+            "markdown" => " [markdown]",
+            _ => continue,
+        };
+        let source = match cell.get("source")? {
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Array(parts) => parts.iter().map(|p| p.as_str()).collect::<Option<String>>()?,
+            _ => return None,
+        };
+        if !out.is_empty() { out.push('\n'); }
+        out.push_str(&format!("# %% [cell {}]{}\n", i + 1, marker));
+        out.push_str(&source);
+        if !source.is_empty() && !source.ends_with('\n') { out.push('\n'); }
+    }
+    Some(out)
+}
+
+#[derive(Debug, Clone)]
+pub struct Replacement {
+    pub regex: regex::Regex,
+    pub replacement: String,
+}
+
+impl Replacement {
+    pub fn new(pattern: &str, replacement: &str) -> Result<Self, LfError> {
+        let regex = regex::Regex::new(pattern).map_err(|e| LfError::InvalidOption(format!("invalid --replace pattern '{}': {}", pattern, e)))?;
+        Ok(Replacement { regex, replacement: replacement.to_string() })
+    }
+}
+
+pub fn apply_replacements(content: &str, replacements: &[Replacement]) -> String {
+    replacements.iter().fold(content.to_string(), |s, r| r.regex.replace_all(&s, r.replacement.as_str()).into_owned())
+}
+
+pub const TESTS_OMITTED: &str = "// tests omitted";
+
+fn is_test_attribute(t: &str) -> bool {
+    let Some(attr) = t.strip_prefix("#[") else { return false };
+    let name = attr.split([']', '(']).next().unwrap_or("");
+    attr.starts_with("cfg(test)") || name == "test" || name.ends_with("::test")
+}
+
+pub fn strip_rust_tests(content: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut depth = 0;
+    let mut skipping = false;
+    let mut opened = false;
+    for line in content.lines() {
+        let t = line.trim_start();
+        if !skipping && is_test_attribute(t) {
+            while out.last().is_some_and(|l| { let l = l.trim_start(); l.starts_with("#[") || l.starts_with("///") }) { out.pop(); }
+            let marker = format!("{}{}", &line[..line.len() - t.len()], TESTS_OMITTED);
+            if out.last() != Some(&marker) { out.push(marker); }
+            (skipping, depth, opened) = (true, 0, false);
+        }
+        if !skipping { out.push(line.to_string()); continue; }
+        depth += brace_delta(line, false);
+        let bare = t.strip_prefix("#[").and_then(|a| a.split_once(']')).map_or(t, |(_, rest)| rest.trim());
+        opened |= depth > 0 || bare.contains('{');
+        if if opened { depth <= 0 } else { bare.ends_with(';') } { skipping = false; }
+    }
+    let mut s = out.join("\n");
+    if !s.is_empty() && content.ends_with('\n') { s.push('\n'); }
+    s
+}
+
+pub const LICENSE_MARKERS: &[&str] = &["licensed under", "copyright (c)", "spdx-license-identifier"];
+
+fn leading_comment(lines: &[&str], start: usize) -> Option<(usize, &'static str, &'static str)> {
+    let first = lines.get(start)?.trim_start();
+    let line_style = |prefix: &str| lines[start..].iter().take_while(|l| l.trim_start().starts_with(prefix)).count();
+    let block_style = |close: &str| lines[start..].iter().position(|l| l.contains(close)).map(|i| i + 1);
+    if first.starts_with("//") { return Some((line_style("//"), "// ", "")); }
+    if first.starts_with('#') && !first.starts_with("#!") && !first.starts_with("#[") { return Some((line_style("#"), "# ", "")); }
+    if first.starts_with("/*") { return Some((block_style("*/")?, "/* ", " */")); }
+    if first.starts_with("<!--") { return Some((block_style("-->")?, "<!-- ", " -->")); }
+    None
+}
+
+pub fn strip_license_header(content: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut start = 0;
+    if lines.first().is_some_and(|l| l.starts_with("#!")) { start = 1; }
+    while lines.get(start).is_some_and(|l| l.trim().is_empty()) { start += 1; }
+    let (len, open, close) = leading_comment(&lines, start)?;
+    let block = lines[start..start + len].join("\n").to_lowercase();
+    if !LICENSE_MARKERS.iter().any(|m| block.contains(m)) { return None; }
+    let mut out: String = lines[..start].iter().map(|l| format!("{}\n", l)).collect();
+    out.push_str(&format!("{}license header omitted{}\n", open, close));
+    for l in &lines[start + len..] { out.push_str(l); out.push('\n'); }
+    Some(out)
+}
+
+pub const DEFAULT_TODO_TAGS: &[&str] = &["TODO", "FIXME", "HACK", "XXX"];
+
+pub fn find_todos<'a, S: AsRef<str>>(content: &'a str, tags: &[S]) -> Vec<(usize, &'a str)> {
+    content.lines().enumerate()
+        .filter(|(_, line)| tags.iter().any(|t| line.contains(t.as_ref())))
+        .map(|(i, line)| (i + 1, line.trim()))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lang { Rust, Java, Script, Python }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block { Data, Code }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line { Fn, Container(Block), Keep, Other }
+
+pub fn outline(path: &Path, content: &str) -> Option<String> {
+    let lang = match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+        "rs" => Lang::Rust,
+        "java" => Lang::Java,
+        "ts" | "tsx" | "mts" | "cts" | "js" | "jsx" | "mjs" | "cjs" => Lang::Script,
+        "py" | "pyi" => Lang::Python,
+        _ => return None,
+    };
+    Some(if lang == Lang::Python { outline_python(content) } else { outline_braces(content, lang) })
+}
+
+pub(crate) fn brace_delta(line: &str, single_quote_strings: bool) -> i32 {
+    let chars: Vec<char> = line.chars().collect();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '/' if chars.get(i + 1) == Some(&'/') => break,
+            q @ ('"' | '`') | q @ '\'' if q != '\'' || single_quote_strings => {
+                i += 1;
+                while i < chars.len() && chars[i] != q { if chars[i] == '\\' { i += 1; } i += 1; }
+            }
+            '\'' => {
+                if chars.get(i + 1) == Some(&'\\') { i += 2; while i < chars.len() && chars[i] != '\'' { i += 1; } }
+                else if chars.get(i + 2) == Some(&'\'') { i += 2; }
+            }
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    depth
+}
+
+fn strip_words<'a>(mut t: &'a str, words: &[&str]) -> &'a str {
+    loop {
+        let before = t;
+        for w in words {
+            if let Some(rest) = t.strip_prefix(w).filter(|r| r.starts_with(' ')) { t = rest.trim_start(); }
+        }
+        if let Some(rest) = t.strip_prefix("pub(").and_then(|r| r.split_once(')')).map(|(_, r)| r.trim_start()) { t = rest; }
+        if t == before { return t; }
+    }
+}
+
+fn starts_with_word(t: &str, words: &[&str]) -> bool {
+    words.iter().any(|w| t.strip_prefix(w).is_some_and(|r| r.starts_with([' ', '<', '{', '(']) || r.is_empty()))
+}
+
+fn is_ident(s: &str) -> bool { !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$') }
+
+const CONTROL: &[&str] = &["if", "for", "while", "switch", "catch", "return", "new", "else", "do", "try", "throw", "await", "yield", "super", "this"];
+
+fn classify_line(t: &str, lang: Lang, inside: Option<Block>) -> Line {
+    match lang {
+        Lang::Rust => {
+            let t = strip_words(t, &["pub", "async", "unsafe", "default", "extern \"C\""]);
+            let t = t.strip_prefix("const ").filter(|r| starts_with_word(r, &["fn", "unsafe", "async"])).map(|r| strip_words(r, &["async", "unsafe"])).unwrap_or(t);
+            if starts_with_word(t, &["fn"]) { Line::Fn }
+            else if starts_with_word(t, &["struct", "enum", "union"]) { Line::Container(Block::Data) }
+            else if starts_with_word(t, &["impl", "trait", "mod"]) { Line::Container(Block::Code) }
+            else if starts_with_word(t, &["type", "const", "static"]) { Line::Keep }
+            else { Line::Other }
+        }
+        Lang::Java => {
+            let t = strip_words(t, &["public", "protected", "private", "static", "final", "abstract", "sealed", "non-sealed", "strictfp", "synchronized", "native", "default"]);
+            if starts_with_word(t, &["class", "interface", "@interface"]) { return Line::Container(Block::Code); }
+            if starts_with_word(t, &["enum", "record"]) { return Line::Container(Block::Data); }
+            let Some((head, _)) = t.split_once('(') else { return Line::Other };
+            let words: Vec<&str> = head.split_whitespace().collect();
+            let name = words.last().copied().unwrap_or("");
+            if inside.is_some() && is_ident(name) && !head.contains('=') && !CONTROL.contains(&words[0]) && !head.contains('.') { Line::Fn } else { Line::Other }
+        }
+        Lang::Script => {
+            let t = strip_words(t, &["export", "default", "declare", "abstract", "async"]);
+            if starts_with_word(t, &["function", "function*"]) { return Line::Fn; }
+            if starts_with_word(t, &["class", "namespace", "module"]) { return Line::Container(Block::Code); }
+            if starts_with_word(t, &["interface", "enum"]) || (starts_with_word(t, &["type"]) && t.trim_end().ends_with('{')) { return Line::Container(Block::Data); }
+            if starts_with_word(t, &["type"]) { return Line::Keep; }
+            if starts_with_word(t, &["const", "let", "var"]) && t.contains("=>") { return Line::Fn; }
+            if inside == Some(Block::Code) {
+                let m = strip_words(t, &["public", "private", "protected", "static", "readonly", "override", "async", "get", "set"]);
+                if let Some((head, _)) = m.split_once('(') && is_ident(head.trim_end_matches(['?', '*']).trim()) && !CONTROL.contains(&head.trim()) { return Line::Fn; }
+            }
+            Line::Other
+        }
+        Lang::Python => Line::Other,
+    }
+}
+
+fn elided(line: &str) -> String {
+    let head = line.split_once('{').map_or(line, |(h, _)| h).trim_end();
+    format!("{} {{ ... }}\n", head)
+}
+
+fn outline_braces(content: &str, lang: Lang) -> String {
+    let js = lang != Lang::Rust;
+    let mut out = String::new();
+    let mut stack: Vec<Block> = Vec::new();
+    let mut skip = 0;
+    let mut pending: Option<Line> = None;
+    for line in content.lines() {
+        let delta = brace_delta(line, js);
+        if skip > 0 { skip = (skip + delta).max(0); continue; }
+        let t = line.trim();
+        if let Some(kind) = pending {
+            match kind {
+                Line::Fn if line.contains('{') => { out.push_str(&elided(line)); skip = delta.max(0); pending = None; }
+                Line::Container(block) if line.contains('{') => { out.push_str(line); out.push('\n'); if delta > 0 { stack.push(block); } pending = None; }
+                _ => { out.push_str(line); out.push('\n'); if t.ends_with(';') { pending = None; } }
+            }
+            continue;
+        }
+        if t.is_empty() || t.starts_with("//") || t.starts_with("/*") || t.starts_with('*') || t.starts_with("#[") || t.starts_with('@') { continue; }
+        if t.starts_with('}') {
+            if stack.pop().is_some() { out.push_str(line); out.push('\n'); }
+            continue;
+        }
+        match classify_line(t, lang, stack.last().copied()) {
+            Line::Fn if line.contains('{') => { out.push_str(&elided(line)); skip = delta.max(0); }
+            Line::Fn | Line::Container(_) if t.ends_with(';') => { out.push_str(line); out.push('\n'); }
+            kind @ (Line::Fn | Line::Container(_)) if !line.contains('{') => { out.push_str(line); out.push('\n'); pending = Some(kind); }
+            Line::Container(block) => { out.push_str(line); out.push('\n'); if delta > 0 { stack.push(block); } }
+            Line::Keep => { out.push_str(line); out.push('\n'); skip = delta.max(0); }
+            _ if stack.last() == Some(&Block::Data) => { out.push_str(line); out.push('\n'); if delta > 0 { stack.push(Block::Data); } }
+            _ if stack.last() == Some(&Block::Code) && t.starts_with("public ") && t.ends_with(';') => { out.push_str(line); out.push('\n'); }
+            _ => skip = delta.max(0),
+        }
+    }
+    out
+}
+
+fn indent_of(line: &str) -> usize {
+    line.chars().take_while(|c| c.is_whitespace()).map(|c| if c == '\t' { 4 } else { 1 }).sum()
+}
+
+fn paren_delta(line: &str) -> i32 {
+    line.chars().fold(0, |d, c| match c { '(' | '[' => d + 1, ')' | ']' => d - 1, _ => d })
+}
+
+fn outline_python(content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut out = String::new();
+    let mut classes: Vec<usize> = Vec::new();
+    let mut skip_above: Option<usize> = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        let t = line.trim();
+        if t.is_empty() { continue; }
+        let indent = indent_of(line);
+        if let Some(limit) = skip_above { if indent > limit { continue; } skip_above = None; }
+        while classes.last().is_some_and(|&c| c >= indent) { classes.pop(); }
+        if let Some(q) = ["\"\"\"", "\'\'\'"].into_iter().find(|q| t.starts_with(q)) {
+            if t.len() < 6 || !t.ends_with(q) { while i < lines.len() && !lines[i].contains(q) { i += 1; } i += 1; }
+            continue;
+        }
+        if t.starts_with('@') { out.push_str(line); out.push('\n'); continue; }
+        if t.starts_with("def ") || t.starts_with("async def ") {
+            out.push_str(line);
+            out.push('\n');
+            let mut depth = paren_delta(line);
+            while depth > 0 && i < lines.len() {
+                out.push_str(lines[i]);
+                out.push('\n');
+                depth += paren_delta(lines[i]);
+                i += 1;
+            }
+            out.push_str(&format!("{}    ...\n", &line[..line.len() - line.trim_start().len()]));
+            skip_above = Some(indent);
+        } else if t.starts_with("class ") {
+            out.push_str(line);
+            out.push('\n');
+            classes.push(indent);
+        } else if classes.last().is_some_and(|&c| indent > c) && !t.starts_with('_') && t.split_once([':', '=']).is_some_and(|(name, _)| is_ident(name.trim())) {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// A content rewrite applied to each text file after it is read and before it is counted.
+pub trait Transform: Send + Sync {
+    fn apply(&self, path: &Path, content: String) -> String;
+
+    /// Like `apply`, but also returns a warning about the file for the caller to report under its display name.
+    fn apply_or_warn(&self, path: &Path, content: String) -> (String, Option<String>) { (self.apply(path, content), None) }
+
+    /// Whether line counts describe the text after this transform rather than the file as read.
+    fn recounts_lines(&self) -> bool { false }
+}
+
+impl<T: Transform + ?Sized> Transform for Arc<T> {
+    fn apply(&self, path: &Path, content: String) -> String { (**self).apply(path, content) }
+    fn apply_or_warn(&self, path: &Path, content: String) -> (String, Option<String>) { (**self).apply_or_warn(path, content) }
+    fn recounts_lines(&self) -> bool { (**self).recounts_lines() }
+}
+
+/// Where a transform runs in `pipeline`. Stages run in declaration order, so redaction always sees the full text
+/// before anything is truncated; custom transforms run after the built-in ones of their stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Decode,
+    StripFrontmatter,
+    StripComments,
+    MaskImports,
+    Redact,
+    Truncate,
+}
+
+#[derive(Clone)]
+pub struct CustomTransform {
+    pub stage: Stage,
+    pub transform: Arc<dyn Transform>,
+}
+
+impl std::fmt::Debug for CustomTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "CustomTransform({:?})", self.stage) }
+}
+
+fn has_ext(path: &Path, exts: &[&str]) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| exts.iter().any(|x| e.eq_ignore_ascii_case(x)))
+}
+
+/// `--mask-java-imports`: collapses every `import` line of a `.java` file into a single `import ...`.
+pub struct MaskJavaImports;
+
+impl Transform for MaskJavaImports {
+    fn apply(&self, path: &Path, content: String) -> String {
+        if !has_ext(path, &["java"]) { return content; }
+        let mut out = String::new();
+        let mut added = false;
+        for line in content.lines() {
+            if line.trim_start().starts_with("import ") {
+                if !added { out.push_str("import ...\n"); added = true; }
+            } else {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        if added { out } else { content }
+    }
+}
+
+pub struct StripFrontmatter;
+
+impl Transform for StripFrontmatter {
+    fn apply(&self, path: &Path, content: String) -> String { if has_ext(path, &["md", "mdx"]) { strip_frontmatter(&content) } else { content } }
+}
+
+/// Replaces regions between marker lines with a placeholder; a file with an unclosed region is kept whole.
+/// Line counts follow the elided text.
+pub struct ElideRegions {
+    pub start: String,
+    pub end: String,
+}
+
+impl Transform for ElideRegions {
+    fn apply(&self, path: &Path, content: String) -> String { self.apply_or_warn(path, content).0 }
+
+    fn apply_or_warn(&self, _path: &Path, content: String) -> (String, Option<String>) {
+        if !content.contains(self.start.as_str()) { return (content, None); }
+        match elide_regions(&content, &self.start, &self.end) {
+            Ok(elided) => (elided, None),
+            Err(at) => (content, Some(format!("{}: '{}' has no matching '{}'; including the whole file", at, self.start, self.end))),
+        }
+    }
+
+    fn recounts_lines(&self) -> bool { true }
+}
+
+pub struct StripLicenseHeaders;
+
+impl Transform for StripLicenseHeaders {
+    fn apply(&self, _path: &Path, content: String) -> String { strip_license_header(&content).unwrap_or(content) }
+}
+
+pub struct StripRustTests;
+
+impl Transform for StripRustTests {
+    fn apply(&self, path: &Path, content: String) -> String { if has_ext(path, &["rs"]) { strip_rust_tests(&content) } else { content } }
+}
+
+pub struct Outline;
+
+impl Transform for Outline {
+    fn apply(&self, path: &Path, content: String) -> String { outline(path, &content).unwrap_or(content) }
+}
+
+pub struct Replace(pub Vec<Replacement>);
+
+impl Transform for Replace {
+    fn apply(&self, _path: &Path, content: String) -> String { apply_replacements(&content, &self.0) }
+}
+
+pub struct ExpandTabs {
+    pub width: usize,
+    pub all: bool,
+}
+
+impl Transform for ExpandTabs {
+    fn apply(&self, _path: &Path, content: String) -> String { expand_tabs(&content, self.width, self.all) }
+}
+
+pub struct Dedent;
+
+impl Transform for Dedent {
+    fn apply(&self, _path: &Path, content: String) -> String { dedent(&content) }
+}
+
+pub struct TruncateLongLines(pub usize);
+
+impl Transform for TruncateLongLines {
+    fn apply(&self, _path: &Path, content: String) -> String { truncate_long_lines(&content, self.0) }
+}
+
+/// The transforms `opts` asks for, in `Stage` order. `--head-lines` and `--line-numbers` are applied after the
+/// pipeline, since they work on its final line count.
+pub fn pipeline(opts: &Options) -> Vec<Box<dyn Transform>> {
+    let mut steps: Vec<(Stage, Box<dyn Transform>)> = Vec::new();
+    if opts.strip_frontmatter { steps.push((Stage::StripFrontmatter, Box::new(StripFrontmatter))); }
+    if let Some((start, end)) = &opts.region_markers { steps.push((Stage::StripComments, Box::new(ElideRegions { start: start.clone(), end: end.clone() }))); }
+    if opts.strip_license_headers { steps.push((Stage::StripComments, Box::new(StripLicenseHeaders))); }
+    if opts.strip_rust_tests { steps.push((Stage::StripComments, Box::new(StripRustTests))); }
+    if opts.outline { steps.push((Stage::StripComments, Box::new(Outline))); }
+    if opts.mask_java_imports { steps.push((Stage::MaskImports, Box::new(MaskJavaImports))); }
+    if !opts.replacements.is_empty() { steps.push((Stage::Redact, Box::new(Replace(opts.replacements.clone())))); }
+    if let Some(width) = opts.expand_tabs { steps.push((Stage::Truncate, Box::new(ExpandTabs { width, all: opts.expand_all_tabs }))); }
+    if opts.dedent { steps.push((Stage::Truncate, Box::new(Dedent))); }
+    if let Some(max) = opts.max_line_length { steps.push((Stage::Truncate, Box::new(TruncateLongLines(max)))); }
+    steps.extend(opts.transforms.iter().map(|c| -> (Stage, Box<dyn Transform>) { (c.stage, Box::new(c.transform.clone())) }));
+    steps.sort_by_key(|(stage, _)| *stage);
+    steps.into_iter().map(|(_, t)| t).collect()
+}
+
+include!("transform_tests.rs");
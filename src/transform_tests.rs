@@ -0,0 +1,439 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered_file(n: usize) -> String {
+        (1..=n).map(|i| format!("line {}\n", i)).collect()
+    }
+
+    #[test]
+    fn numbers_are_padded_to_the_widest() {
+        assert_eq!(number_lines("a\nb\nc\n"), "1 | a\n2 | b\n3 | c\n");
+        let s = number_lines(&numbered_file(150));
+        let lines: Vec<&str> = s.lines().collect();
+        assert_eq!(lines[0], "  1 | line 1");
+        assert_eq!(lines[41], " 42 | line 42");
+        assert_eq!(lines[149], "150 | line 150");
+        assert_eq!(number_lines(""), "");
+    }
+
+    #[test]
+    fn head_keeps_first_lines_and_reports_the_rest() {
+        assert_eq!(head_lines("a\nb\nc\n", 2), ("a\nb\n".to_string(), 1));
+        assert_eq!(head_lines("a\nb\n", 5), ("a\nb\n".to_string(), 0));
+        assert_eq!(truncation_marker(48201), "… (48201 more lines)\n");
+    }
+
+    #[test]
+    fn numbering_after_head_keeps_original_positions() {
+        let (head, _) = head_lines(&numbered_file(150), 12);
+        let s = number_lines(&head);
+        assert!(s.starts_with(" 1 | line 1\n"));
+        assert!(s.ends_with("12 | line 12\n"));
+    }
+
+    #[test]
+    fn notebooks_flatten_to_cell_sources() {
+        let nb = r##"{"cells": [
+            {"cell_type": "markdown", "metadata": {}, "source": ["# Title\n", "intro"]},
+            {"cell_type": "code", "metadata": {}, "execution_count": 1, "source": "import numpy as np\nnp.ones(3)", "outputs": [{"data": {"image/png": "iVBORw0KGgo="}}]},
+            {"cell_type": "raw", "source": "skipped"},
+            {"cell_type": "code", "source": []}
+        ], "metadata": {}, "nbformat": 4}"##;
+        assert_eq!(flatten_notebook(nb).unwrap(), "# %% [cell 1] [markdown]\n# Title\nintro\n\n# %% [cell 2]\nimport numpy as np\nnp.ones(3)\n\n# %% [cell 4]\n");
+    }
+
+    #[test]
+    fn malformed_notebooks_are_rejected() {
+        assert_eq!(flatten_notebook("{not json"), None);
+        assert_eq!(flatten_notebook(r#"{"nbformat": 4}"#), None);
+        assert_eq!(flatten_notebook(r#"{"cells": [{"cell_type": "code", "source": 3}]}"#), None);
+    }
+
+    fn outlined(path: &str, src: &str) -> String { outline(Path::new(path), src).unwrap() }
+
+    #[test]
+    fn rust_outline_keeps_items_and_elides_bodies() {
+        let src = r#"use std::fmt;
+
+/// A point.
+#[derive(Debug)]
+pub struct Point {
+    pub x: i32,
+    y: i32,
+}
+
+pub enum Shape {
+    Circle { r: f64 },
+    Rect {
+        w: f64,
+        h: f64,
+    },
+}
+
+impl Point {
+    pub const ORIGIN: Point = Point { x: 0, y: 0 };
+
+    pub fn new(x: i32, y: i32) -> Self {
+        let s = "}";
+        Point { x, y }
+    }
+
+    pub(crate) async fn len<'a>(&'a self) -> f64 { 0.0 }
+}
+
+pub trait Area {
+    fn area(&self) -> f64;
+    fn describe(&self) -> String {
+        format!("{}", self.area())
+    }
+}
+
+mod inner {
+    fn helper(
+        a: u8,
+    ) -> u8 {
+        a
+    }
+}
+"#;
+        assert_eq!(outlined("src/lib.rs", src), r#"pub struct Point {
+    pub x: i32,
+    y: i32,
+}
+pub enum Shape {
+    Circle { r: f64 },
+    Rect {
+        w: f64,
+        h: f64,
+    },
+}
+impl Point {
+    pub const ORIGIN: Point = Point { x: 0, y: 0 };
+    pub fn new(x: i32, y: i32) -> Self { ... }
+    pub(crate) async fn len<'a>(&'a self) -> f64 { ... }
+}
+pub trait Area {
+    fn area(&self) -> f64;
+    fn describe(&self) -> String { ... }
+}
+mod inner {
+    fn helper(
+        a: u8,
+    ) -> u8 { ... }
+}
+"#);
+    }
+
+    #[test]
+    fn python_outline_keeps_nested_classes_and_signatures() {
+        let src = r#"import os
+
+class Outer:
+    """Docs: not a field."""
+    name: str = "x"
+    _private = 1
+
+    class Inner:
+        depth = 2
+
+        def walk(self, path,
+                 recursive=True):
+            for p in path:
+                pass
+
+    @property
+    def size(self) -> int:
+        return 1
+
+async def main():
+    await run()
+"#;
+        assert_eq!(outlined("tool.py", src), r#"class Outer:
+    name: str = "x"
+    class Inner:
+        depth = 2
+        def walk(self, path,
+                 recursive=True):
+            ...
+    @property
+    def size(self) -> int:
+        ...
+async def main():
+    ...
+"#);
+    }
+
+    #[test]
+    fn java_outline_keeps_signatures_and_public_fields() {
+        let src = r#"package a;
+
+import java.util.List;
+
+public class Repo<T> implements Store {
+    public static final int LIMIT = 10;
+    private List<T> items;
+
+    public Repo(List<T> items) {
+        this.items = items;
+    }
+
+    @Override
+    public List<T> find(String q) throws IOException {
+        if (q.isEmpty()) { return items; }
+        return List.of();
+    }
+}
+
+interface Store {
+    void save(Object o);
+}
+"#;
+        assert_eq!(outlined("Repo.java", src), r#"public class Repo<T> implements Store {
+    public static final int LIMIT = 10;
+    public Repo(List<T> items) { ... }
+    public List<T> find(String q) throws IOException { ... }
+}
+interface Store {
+    void save(Object o);
+}
+"#);
+    }
+
+    #[test]
+    fn typescript_outline_keeps_declarations() {
+        let src = r#"import { x } from "./x";
+
+export interface User {
+    id: number;
+    name: string;
+}
+
+export type Id = string | number;
+
+export class Service {
+    private cache = new Map();
+
+    constructor(private api: Api) {}
+
+    async load(id: Id): Promise<User> {
+        const s = `${id}}`;
+        return this.api.get(s);
+    }
+}
+
+export const handler = async (req) => {
+    return 1;
+};
+
+export function main(): void {
+    console.log("{");
+}
+"#;
+        assert_eq!(outlined("src/service.ts", src), r#"export interface User {
+    id: number;
+    name: string;
+}
+export type Id = string | number;
+export class Service {
+    constructor(private api: Api) { ... }
+    async load(id: Id): Promise<User> { ... }
+}
+export const handler = async (req) => { ... }
+export function main(): void { ... }
+"#);
+    }
+
+    #[test]
+    fn unsupported_extensions_are_not_outlined() {
+        assert_eq!(outline(Path::new("notes.md"), "# x\n"), None);
+        assert_eq!(outline(Path::new("Makefile"), "all:\n"), None);
+    }
+
+    #[test]
+    fn todos_are_found_by_substring() {
+        let src = "fn a() {}\n    // TODO: split this\nlet x = 1; // FIXME\n// see https://todo.example\n/* XXX */\n";
+        assert_eq!(find_todos(src, DEFAULT_TODO_TAGS), vec![(2, "// TODO: split this"), (3, "let x = 1; // FIXME"), (5, "/* XXX */")]);
+        assert_eq!(find_todos(src, &["todo"]), vec![(4, "// see https://todo.example")]);
+        assert!(find_todos("nothing here\n", DEFAULT_TODO_TAGS).is_empty());
+    }
+
+    #[test]
+    fn strips_trailing_test_module() {
+        let src = "pub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\n#[cfg(test)]\nmod tests {\n    use super::*;\n\n    #[test]\n    fn adds() {\n        assert_eq!(add(1, 2), 3);\n    }\n}\n";
+        assert_eq!(strip_rust_tests(src), "pub fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\n// tests omitted\n");
+        assert_eq!(strip_rust_tests("#[cfg(test)]\nmod tests;\nfn keep() {}\n"), "// tests omitted\nfn keep() {}\n");
+    }
+
+    #[test]
+    fn strips_async_and_attributed_test_functions() {
+        let src = "fn real() {}\n\n/// Checks the client.\n#[ignore]\n#[tokio::test(flavor = \"multi_thread\")]\nasync fn fetches() {\n    let c = client().await;\n    if c.ok() {\n        done();\n    }\n}\n#[test] fn quick() { assert!(true); }\nfn after() {}\n";
+        assert_eq!(strip_rust_tests(src), "fn real() {}\n\n// tests omitted\nfn after() {}\n");
+    }
+
+    #[test]
+    fn braces_inside_literals_do_not_end_the_item() {
+        let src = "mod m {\n    #[test]\n    fn braces() {\n        let s = \"}}\";\n        let c = '}';\n        let r = format!(\"{{{}\", 1);\n        let l: &'static str = \"x\";\n    }\n    fn kept() {}\n}\n";
+        assert_eq!(strip_rust_tests(src), "mod m {\n    // tests omitted\n    fn kept() {}\n}\n");
+    }
+
+    const APACHE: &str = "Copyright (c) 2024 Example Corp.\n\nLicensed under the Apache License, Version 2.0 (the \"License\");\nyou may not use this file except in compliance with the License.";
+
+    #[test]
+    fn strips_license_headers_in_every_comment_style() {
+        let slashes: String = APACHE.lines().map(|l| format!("// {}\n", l).replace("//  \n", "//\n")).collect();
+        assert_eq!(strip_license_header(&format!("{}\npackage main\n", slashes)).unwrap(), "// license header omitted\n\npackage main\n");
+        let hashes: String = APACHE.lines().map(|l| format!("# {}\n", l)).collect();
+        assert_eq!(strip_license_header(&format!("#!/usr/bin/env python3\n{}import os\n", hashes)).unwrap(), "#!/usr/bin/env python3\n# license header omitted\nimport os\n");
+        assert_eq!(strip_license_header(&format!("/*\n * {}\n */\nclass A {{}}\n", APACHE.replace('\n', "\n * "))).unwrap(), "/* license header omitted */\nclass A {}\n");
+        assert_eq!(strip_license_header("<!-- SPDX-License-Identifier: MIT -->\n<h1>Hi</h1>\n").unwrap(), "<!-- license header omitted -->\n<h1>Hi</h1>\n");
+    }
+
+    #[test]
+    fn keeps_documentation_and_later_license_text() {
+        assert_eq!(strip_license_header("//! Parses config files.\n//! See docs/config.md.\nfn parse() {}\n"), None);
+        assert_eq!(strip_license_header("fn main() {}\n// Licensed under MIT\n"), None);
+        assert_eq!(strip_license_header("/* unterminated Licensed under MIT\n"), None);
+    }
+
+    #[test]
+    fn replacements_apply_in_order_with_capture_groups() {
+        let host = Replacement::new(r"internal\.corp\.example", "REDACTED_HOST").unwrap();
+        let redacted = Replacement::new("REDACTED", "X").unwrap();
+        assert_eq!(apply_replacements("a internal.corp.example b\n", &[host.clone(), redacted.clone()]), "a X_HOST b\n");
+        assert_eq!(apply_replacements("a internal.corp.example b\n", &[redacted, host]), "a REDACTED_HOST b\n");
+        let swap = Replacement::new(r"(\w+)@(\w+)\.com", "$2 at $1").unwrap();
+        assert_eq!(apply_replacements("mail bob@acme.com\n", &[swap]), "mail acme at bob\n");
+        let err = Replacement::new("(unclosed", "x").unwrap_err();
+        assert!(err.to_string().contains("'(unclosed'"), "{}", err);
+    }
+
+    #[test]
+    fn long_lines_are_cut_on_char_boundaries() {
+        assert_eq!(truncate_long_lines("short\nabcdefghij\n", 5), "short\nabcde …[truncated 5 chars]\n");
+        let emoji = format!("ab{}\n", "😀".repeat(3));
+        assert_eq!(truncate_long_lines(&emoji, 3), "ab😀 …[truncated 2 chars]\n");
+        let long = "x".repeat(83_117);
+        assert_eq!(truncate_long_lines(&long, 5), "xxxxx …[truncated 83,112 chars]\n");
+        assert_eq!(truncate_long_lines("é\n", 1), "é\n");
+    }
+
+    #[test]
+    fn expands_leading_tabs_to_tab_stops() {
+        let makefile = "build:\n\tcargo build\t# release\n  \tmixed\n";
+        assert_eq!(expand_tabs(makefile, 4, false), "build:\n    cargo build\t# release\n    mixed\n");
+        assert_eq!(expand_tabs(makefile, 4, true), "build:\n    cargo build # release\n    mixed\n");
+        assert_eq!(expand_tabs("\t\tx\n", 2, false), "    x\n");
+    }
+
+    #[test]
+    fn dedent_removes_common_indentation() {
+        let snippet = "    namespace App {\n\n        class A {}\n    }\n";
+        assert_eq!(dedent(snippet), "namespace App {\n\n    class A {}\n}\n");
+        assert_eq!(dedent("\t\tone\n\t\t  two\n"), "one\n  two\n");
+        assert_eq!(dedent("flush\n    indented\n"), "flush\n    indented\n");
+        assert_eq!(dedent("  a\n \n"), "a\n\n");
+    }
+
+    #[test]
+    fn samples_csv_rows_after_the_header() {
+        let csv: String = std::iter::once("id,name\n".to_string()).chain((1..=1000).map(|i| format!("{},user{}\n", i, i))).collect();
+        assert_eq!(sample_data(Path::new("users.csv"), &csv, 2).unwrap(), "id,name\n1,user1\n2,user2\n… (998 more rows)\n");
+        assert_eq!(sample_data(Path::new("small.tsv"), "a\tb\n1\t2\n", 5).unwrap(), "a\tb\n1\t2\n");
+        assert_eq!(sample_data(Path::new("notes.txt"), &csv, 2), None);
+    }
+
+    #[test]
+    fn samples_large_json_arrays() {
+        let items: Vec<String> = (0..2000).map(|i| format!("{{\"id\": {}, \"tags\": [\"a\", \"b\"]}}", i)).collect();
+        let json = format!("[{}]", items.join(",\n"));
+        assert_eq!(sample_data(Path::new("data.json"), &json, 2).unwrap(), "[\n  {\n    \"id\": 0,\n    \"tags\": [\n      \"a\",\n      \"b\"\n    ]\n  },\n  {\n    \"id\": 1,\n    \"tags\": [\n      \"a\",\n      \"b\"\n    ]\n  }\n]\n… (1,998 more elements)\n");
+        assert_eq!(sample_data(Path::new("small.json"), "[1, 2, 3]", 1), None);
+        let jsonl = items.join("\n") + "\n";
+        assert_eq!(sample_data(Path::new("events.jsonl"), &jsonl, 1).unwrap(), format!("{}\n… (1,999 more lines)\n", items[0]));
+        let broken = format!("{}\n", json.trim_end_matches(']'));
+        assert!(sample_data(Path::new("broken.json"), &broken, 3).unwrap().ends_with("… (1997 more lines)\n"));
+    }
+
+    #[test]
+    fn strips_csi_and_osc_sequences() {
+        assert_eq!(strip_ansi("\x1b[32mok\x1b[0m done\n"), "ok done\n");
+        assert_eq!(strip_ansi("\x1b[1;38;5;208mwarn\x1b[m \x1b[2K\x1b[1Gline\n"), "warn line\n");
+        assert_eq!(strip_ansi("\x1b]0;title\x07text\n"), "text\n");
+        assert_eq!(strip_ansi("see \x1b]8;;https://x.dev\x1b\\\x1b[4mdocs\x1b[24m\x1b]8;;\x1b\\ now\n"), "see docs now\n");
+        assert_eq!(strip_ansi("\u{9b}31mred\n"), "red\n");
+        assert_eq!(strip_ansi("\x1b(Bplain\x1b7\n"), "plain\n");
+        assert_eq!(strip_ansi("tail \x1b[3"), "tail ");
+        assert_eq!(strip_ansi("tail \x1b"), "tail ");
+        assert_eq!(strip_ansi("no escapes, 100% [ok]\n"), "no escapes, 100% [ok]\n");
+    }
+
+    #[test]
+    fn strips_yaml_and_toml_frontmatter() {
+        assert_eq!(strip_frontmatter("---\ntitle: Intro\ntags: [a, b]\n---\n# Intro\n"), "# Intro\n");
+        assert_eq!(strip_frontmatter("+++\ntitle = \"Intro\"\n+++\n\nBody\n"), "\nBody\n");
+        assert_eq!(strip_frontmatter("---\ntitle: Only\n---\n"), "");
+        assert_eq!(strip_frontmatter("---\ntitle: x\n...\ntext\n"), "text\n");
+    }
+
+    #[test]
+    fn unclosed_or_missing_frontmatter_is_kept() {
+        assert_eq!(strip_frontmatter("---\ntitle: never closed\n# Heading\n"), "---\ntitle: never closed\n# Heading\n");
+        assert_eq!(strip_frontmatter("# Title\n---\nnot: frontmatter\n---\n"), "# Title\n---\nnot: frontmatter\n---\n");
+    }
+
+    #[test]
+    fn strips_html_comments() {
+        assert_eq!(strip_frontmatter("<!-- hugo: draft -->\n# A\nText <!-- inline --> here\n<!--\nmulti\n-->\nend\n"), "# A\nText  here\nend\n");
+        assert_eq!(strip_frontmatter("a <!-- never closed\nb\n"), "a <!-- never closed\nb\n");
+    }
+
+    #[test]
+    fn java_imports_are_masked_only_in_java_files() {
+        let src = "package a;\nimport java.util.List;\n  import java.util.Map;\nclass A {}\n";
+        assert_eq!(MaskJavaImports.apply(Path::new("A.JAVA"), src.to_string()), "package a;\nimport ...\nclass A {}\n");
+        assert_eq!(MaskJavaImports.apply(Path::new("a.kt"), src.to_string()), src);
+        assert_eq!(MaskJavaImports.apply(Path::new("B.java"), "class B {}".to_string()), "class B {}");
+    }
+
+    struct Tag(&'static str);
+    impl Transform for Tag {
+        fn apply(&self, _path: &Path, content: String) -> String { format!("{}{}\n", content, self.0) }
+    }
+
+    fn run(opts: &Options, path: &str, content: &str) -> String {
+        pipeline(opts).iter().fold(content.to_string(), |c, t| t.apply(Path::new(path), c))
+    }
+
+    #[test]
+    fn redaction_runs_before_truncation() {
+        // Truncating first would leave "sk-abcdef" behind, too short for the pattern to match.
+        let opts = Options::new(["*"]).max_line_length(17);
+        let opts = Options { replacements: vec![Replacement::new("sk-[a-z0-9]{16}", "[KEY]").unwrap()], ..opts };
+        assert_eq!(run(&opts, "a.env", "API_KEY=sk-abcdef0123456789\n"), "API_KEY=[KEY]\n");
+    }
+
+    #[test]
+    fn stages_run_in_declared_order_with_custom_transforms_last_in_their_stage() {
+        assert!(Stage::Decode < Stage::StripFrontmatter && Stage::StripFrontmatter < Stage::StripComments);
+        assert!(Stage::StripComments < Stage::MaskImports && Stage::MaskImports < Stage::Redact && Stage::Redact < Stage::Truncate);
+        let opts = Options::new(["*"]).transform(Stage::Truncate, Tag("late")).transform(Stage::Decode, Tag("early")).transform(Stage::Redact, Tag("redact"))
+            .mask_java_imports(true).max_line_length(20);
+        let opts = Options { replacements: vec![Replacement::new("redact", "R").unwrap()], ..opts };
+        assert_eq!(run(&opts, "A.java", "import x;\n"), "import ...\nearly\nredact\nlate\n");
+        assert_eq!(pipeline(&Options::new(["*"])).len(), 0);
+    }
+
+    #[test]
+    fn skip_regions_are_flat_and_unclosed_ones_keep_everything() {
+        let elide = |s: &str| elide_regions(s, "lf:skip-start", "lf:skip-end");
+        let two = "a\n// lf:skip-start\nb\nc\n// lf:skip-end\nd\n# lf:skip-start\ne\n# lf:skip-end\n";
+        assert_eq!(elide(two).unwrap(), "a\n// lf:skip-start\n[… 2 lines elided (lf:skip region) …]\n// lf:skip-end\nd\n# lf:skip-start\n[… 1 line elided (lf:skip region) …]\n# lf:skip-end\n");
+        let nested = "lf:skip-start\nx\nlf:skip-start\ny\nlf:skip-end\nz\nlf:skip-end";
+        assert_eq!(elide(nested).unwrap(), "lf:skip-start\n[… 3 lines elided (lf:skip region) …]\nlf:skip-end\nz\nlf:skip-end");
+        assert_eq!(elide("a\nb\nlf:skip-start\n"), Err(3));
+        assert_eq!(elide("a\nlf:skip-start\nb\nlf:skip-end").unwrap(), "a\nlf:skip-start\n[… 1 line elided (lf:skip region) …]\nlf:skip-end");
+        assert_eq!(elide("lf:skip-start\nlf:skip-end\n").unwrap(), "lf:skip-start\nlf:skip-end\n");
+        assert_eq!(elide("(\"lf:skip-start\", \"lf:skip-end\")\nx\n").unwrap(), "(\"lf:skip-start\", \"lf:skip-end\")\nx\n");
+    }
+}
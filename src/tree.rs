@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+#[derive(Default)]
+struct Node {
+    children: BTreeMap<String, Node>,
+    is_file: bool,
+}
+
+pub fn render_tree<S: AsRef<str>>(paths: &[S]) -> String {
+    let mut root = Node::default();
+    for p in paths {
+        let mut node = &mut root;
+        for part in p.as_ref().split('/').filter(|s| !s.is_empty() && *s != ".") {
+            node = node.children.entry(part.to_string()).or_default();
+        }
+        node.is_file = true;
+    }
+    let mut out = String::new();
+    render_children(&root, "", &mut out);
+    out
+}
+
+fn render_children(node: &Node, prefix: &str, out: &mut String) {
+    let dirs = node.children.iter().filter(|(_, n)| !n.children.is_empty());
+    let files = node.children.iter().filter(|(_, n)| n.children.is_empty());
+    let entries: Vec<_> = dirs.chain(files).collect();
+    for (i, (name, child)) in entries.iter().enumerate() {
+        let last = i + 1 == entries.len();
+        out.push_str(prefix);
+        out.push_str(if last { "└── " } else { "├── " });
+        out.push_str(name);
+        if !child.children.is_empty() { out.push('/'); }
+        out.push('\n');
+        if !child.children.is_empty() {
+            render_children(child, &format!("{}{}", prefix, if last { "    " } else { "│   " }), out);
+        }
+    }
+}
+
+include!("tree_tests.rs");
@@ -0,0 +1,25 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_nested_paths_dirs_first() {
+        let paths = ["README.md", "src/main.rs", "src/app/mod.rs", "src/app/run.rs", "Cargo.toml"];
+        let expected = "\
+├── src/
+│   ├── app/
+│   │   ├── mod.rs
+│   │   └── run.rs
+│   └── main.rs
+├── Cargo.toml
+└── README.md
+";
+        assert_eq!(render_tree(&paths), expected);
+    }
+
+    #[test]
+    fn empty_and_single() {
+        assert_eq!(render_tree::<&str>(&[]), "");
+        assert_eq!(render_tree(&["./a.txt"]), "└── a.txt\n");
+    }
+}
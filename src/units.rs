@@ -0,0 +1,42 @@
+/// Which multiplier sizes are shown in: 1024-based KiB/MiB (the default) or 1000-based KB/MB (`--si-units`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SizeUnits {
+    #[default]
+    Binary,
+    Si,
+}
+
+impl SizeUnits {
+    fn base_and_labels(self) -> (f64, [&'static str; 4]) {
+        match self {
+            SizeUnits::Binary => (1024.0, ["KiB", "MiB", "GiB", "TiB"]),
+            SizeUnits::Si => (1000.0, ["KB", "MB", "GB", "TB"]),
+        }
+    }
+}
+
+pub fn thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) { out.push(','); }
+        out.push(c);
+    }
+    out
+}
+
+/// `size` with one decimal in the largest unit it reaches; a value that would round up to the next
+/// unit's threshold (`1024.0 KiB`) is shown in that unit instead.
+pub fn format_size(size: u64, units: SizeUnits) -> String {
+    let (base, labels) = units.base_and_labels();
+    if (size as f64) < base { return format!("{} bytes", thousands(size as usize)); }
+    let mut value = size as f64 / base;
+    let mut unit = 0;
+    while (value * 10.0).round() / 10.0 >= base && unit + 1 < labels.len() {
+        value /= base;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, labels[unit])
+}
+
+include!("units_tests.rs");
@@ -0,0 +1,38 @@
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thousands_separators() {
+        assert_eq!(thousands(0), "0");
+        assert_eq!(thousands(999), "999");
+        assert_eq!(thousands(1000), "1,000");
+        assert_eq!(thousands(91203), "91,203");
+        assert_eq!(thousands(1234567), "1,234,567");
+    }
+
+    #[test]
+    fn binary_sizes_at_the_boundaries() {
+        let size = |n| format_size(n, SizeUnits::Binary);
+        assert_eq!(size(0), "0 bytes");
+        assert_eq!(size(1023), "1,023 bytes");
+        assert_eq!(size(1024), "1.0 KiB");
+        assert_eq!(size(1536), "1.5 KiB");
+        assert_eq!(size(1_048_575), "1.0 MiB");
+        assert_eq!(size(1_048_576), "1.0 MiB");
+        assert_eq!(size(1_073_741_824), "1.0 GiB");
+        assert_eq!(size(1 << 50), "1024.0 TiB");
+    }
+
+    #[test]
+    fn si_sizes_at_the_boundaries() {
+        let size = |n| format_size(n, SizeUnits::Si);
+        assert_eq!(size(999), "999 bytes");
+        assert_eq!(size(1000), "1.0 KB");
+        assert_eq!(size(1023), "1.0 KB");
+        assert_eq!(size(1024), "1.0 KB");
+        assert_eq!(size(999_999), "1.0 MB");
+        assert_eq!(size(1_048_576), "1.0 MB");
+        assert_eq!(size(2_500_000_000), "2.5 GB");
+    }
+}
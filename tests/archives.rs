@@ -0,0 +1,76 @@
+#![cfg(feature = "archive-info")]
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use std::io::Write;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(args).arg("--no-clipboard").arg("--tokenizer").arg("approx");
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn zip_and_jar_entries_are_listed() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    for name in ["bundle.zip", "lib.jar"] {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(temp.child(name).path()).unwrap());
+        zip.add_directory("com/foo/", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.start_file("com/foo/Bar.class", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(&[0u8; 3174]).unwrap();
+        zip.start_file("META-INF/MANIFEST.MF", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"Manifest-Version: 1.0\n").unwrap();
+        zip.finish().unwrap();
+    }
+
+    let out = lf(&temp, &["*.zip", "*.jar"]);
+    assert!(out.contains("lib.jar\n[Archive: lib.jar — "), "{}", out);
+    assert!(out.contains(", 2 entries]\n  com/foo/Bar.class (3.1 KiB)\n  META-INF/MANIFEST.MF (22 bytes)\n"), "{}", out);
+    assert!(out.contains("[Archive: bundle.zip — "));
+    temp.close().unwrap();
+}
+
+#[test]
+fn tar_entries_are_listed_and_corrupt_archives_fall_back() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let mut tar = tar::Builder::new(std::fs::File::create(temp.child("src.tar").path()).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(5);
+    header.set_cksum();
+    tar.append_data(&mut header, "src/main.rs", &b"hello"[..]).unwrap();
+    tar.finish().unwrap();
+    drop(tar);
+    temp.child("broken.zip").write_str("definitely not a zip").unwrap();
+
+    let out = lf(&temp, &["*.tar", "*.zip"]);
+    assert!(out.contains("[Archive: src.tar — 2.0 KiB, 1 entries]\n  src/main.rs (5 bytes)\n"), "{}", out);
+    assert!(out.contains("broken.zip\n[Archive file: 20 bytes]"), "{}", out);
+    temp.close().unwrap();
+}
+
+#[cfg(feature = "archive")]
+#[test]
+fn expand_archives_emits_selected_tar_gz_entries() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let gz = flate2::write::GzEncoder::new(std::fs::File::create(temp.child("release-src.tar.gz").path()).unwrap(), flate2::Compression::default());
+    let mut tar = tar::Builder::new(gz);
+    let big = "word ".repeat(400);
+    for (name, body) in [("src/lib.rs", "pub fn lib() {}\n"), ("src/util.rs", "// excluded\n"), ("src/big.rs", big.as_str()), ("docs/notes.md", "# Notes\n"), ("tests/t.rs", "// not selected\n"), ("vendor/inner.zip", "PK")] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(body.len() as u64);
+        header.set_cksum();
+        tar.append_data(&mut header, name, body.as_bytes()).unwrap();
+    }
+    tar.into_inner().unwrap().finish().unwrap();
+    temp.child("src/local.rs").write_str("// on disk\n").unwrap();
+
+    let out = lf(&temp, &["release-src.tar.gz", "src/**", "docs/*", "~src/util.rs", "--expand-archives", "--skip-tokens-per-file", "100"]);
+    assert!(out.contains("release-src.tar.gz!src/lib.rs\npub fn lib() {}\n"), "{}", out);
+    assert!(out.contains("release-src.tar.gz!docs/notes.md\n# Notes\n"), "{}", out);
+    assert!(out.contains("release-src.tar.gz!src/big.rs\n[Skipped: "), "{}", out);
+    assert!(!out.contains("util.rs") && !out.contains("tests/t.rs") && !out.contains("inner.zip") && !out.contains("on disk"), "{}", out);
+
+    let all = lf(&temp, &["release-src.tar.gz", "--expand-archives"]);
+    assert!(all.contains("release-src.tar.gz!tests/t.rs\n") && all.contains("release-src.tar.gz!src/util.rs\n"), "{}", all);
+    temp.close().unwrap();
+}
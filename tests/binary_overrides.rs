@@ -0,0 +1,97 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(args).arg("--no-clipboard").arg("--tokenizer").arg("approx");
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn treat_as_text_reads_binary_extensions_lossily() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("doc.pdf").write_binary(b"%PDF-1.4\n\xff\xfe stream\n").unwrap();
+
+    let out = lf(&temp, &["*.pdf", "--treat-as-text", "pdf"]);
+    assert!(out.contains("%PDF-1.4\n\u{fffd}\u{fffd} stream\n"), "{}", out);
+    assert!(!out.contains("[Document file"));
+
+    let out = lf(&temp, &["*.pdf"]);
+    assert!(out.contains("[Document file:"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn treat_as_binary_replaces_text_with_a_placeholder() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("data.json").write_str("{\"secret\": 1}\n").unwrap();
+
+    let out = lf(&temp, &["*.json", "--treat-as-binary", "json,csv"]);
+    assert!(out.contains("[Binary file: 14 bytes]"), "{}", out);
+    assert!(!out.contains("secret"));
+
+    let out = lf(&temp, &["*.json", "--treat-as-binary", "json", "--treat-as-text", "json"]);
+    assert!(out.contains("secret"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn small_binaries_are_embedded_as_base64() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("favicon.ico").write_binary(&[0, 0, 1, 0, 1, 0]).unwrap();
+    temp.child("big.bin").write_binary(&[7u8; 2048]).unwrap();
+
+    let out = lf(&temp, &["*", "--embed-binaries-under", "1K"]);
+    assert!(out.contains("favicon.ico\n[Binary file: favicon.ico — 6 bytes, base64 below]\n```base64\nAAABAAEA\n```\n"), "{}", out);
+    assert!(out.contains("big.bin\n[Binary file: 2.0 KiB]"));
+    temp.close().unwrap();
+}
+
+fn mixed_fixture() -> assert_fs::TempDir {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/main.rs").write_str("fn main() {}\n").unwrap();
+    temp.child("assets/logo.png").write_binary(&[0u8; 1536]).unwrap();
+    temp.child("assets/icon.ico").write_binary(&[0u8; 512]).unwrap();
+    temp
+}
+
+#[test]
+fn skip_binary_drops_binaries_and_reports_them() {
+    let temp = mixed_fixture();
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["src", "assets", "--skip-binary", "--no-clipboard", "--tokenizer", "approx", "--embed-binaries-under", "1M", "--stats-json=-"]);
+    let out = cmd.assert().success().get_output().clone();
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(stdout.contains("src/main.rs\nfn main() {}\n"));
+    assert!(!stdout.contains("assets/"), "{}", stdout);
+    assert!(stderr.contains("Skipped 2 binary files (2.0 KiB)"), "{}", stderr);
+    assert!(stderr.contains("\"skipped_binary\": 2"));
+    assert!(stderr.contains("\"files\": 1,"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn binary_only_keeps_just_the_binaries() {
+    let temp = mixed_fixture();
+    let out = lf(&temp, &["src", "assets", "--binary-only"]);
+    assert!(out.contains("assets/logo.png\n[Image file: 1.5 KiB]"), "{}", out);
+    assert!(out.contains("assets/icon.ico\n[Image file: 512 bytes]"));
+    assert!(!out.contains("main.rs"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn jar_files_are_binary_in_both_the_cli_and_the_library() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("lib.jar").write_str("PK but readable as text\n").unwrap();
+
+    let out = lf(&temp, &["lib.jar"]);
+    assert!(out.starts_with("lib.jar\n[Binary file: 24 bytes]\n") && !out.contains("readable"), "{}", out);
+
+    let deps = lf::Deps { walker: &lf::fs::StdWalkerFactory, reader: &lf::fs::StdFileReader, tokenizer: std::sync::Arc::new(lf::tokenizer::ApproxTokenizer), clipboard: None };
+    let agg = lf::collect(&deps, &lf::Options::new(["lib.jar"]).dir(temp.path())).unwrap();
+    assert_eq!((agg.files.len(), agg.files[0].kind), (1, lf::FileKind::Binary));
+    temp.close().unwrap();
+}
@@ -0,0 +1,46 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(["src", "-o", "context/dump.txt", "--tokenizer", "approx"]).args(args);
+    cmd.assert()
+}
+
+fn fixture() -> assert_fs::TempDir {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/a.rs").write_str("fn a() {}\n").unwrap();
+    temp.child("src/b.rs").write_str("fn b() {}\nfn c() {}\n").unwrap();
+    temp.child("context").create_dir_all().unwrap();
+    temp
+}
+
+#[test]
+fn check_passes_when_output_is_current() {
+    let temp = fixture();
+    lf(&temp, &[]).success();
+    lf(&temp, &["--check"]).success().stderr(predicate::str::contains("context/dump.txt is up to date"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn check_fails_without_touching_a_stale_file() {
+    let temp = fixture();
+    lf(&temp, &[]).success();
+    let before = std::fs::read_to_string(temp.child("context/dump.txt").path()).unwrap();
+    temp.child("src/b.rs").write_str("fn b() {}\nfn changed() {}\n").unwrap();
+
+    lf(&temp, &["--check"]).code(1).stderr(predicate::str::contains("context/dump.txt is out of date: first difference at line 7, regenerating adds 6 bytes"));
+    temp.child("context/dump.txt").assert(before);
+    temp.close().unwrap();
+}
+
+#[test]
+fn check_treats_a_missing_file_as_different() {
+    let temp = fixture();
+    lf(&temp, &["--check"]).code(1).stderr(predicate::str::contains("context/dump.txt is out of date: the file does not exist"));
+    temp.child("context/dump.txt").assert(predicate::path::missing());
+    temp.close().unwrap();
+}
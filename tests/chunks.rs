@@ -0,0 +1,39 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(args).arg("--no-clipboard").arg("--tokenizer").arg("approx");
+    cmd.assert()
+}
+
+#[test]
+fn chunks_stay_within_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("docs/a.md").write_str(&"abcdefg\n".repeat(5)).unwrap();
+    temp.child("docs/b.md").write_str("abcdefg\n").unwrap();
+
+    lf(&temp, &["docs", "--format", "json", "--chunk-tokens", "4", "--chunk-overlap", "2", "-o", "chunks.json"]).success();
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(temp.path().join("chunks.json")).unwrap()).unwrap();
+    let mut chunks: Vec<(String, u64, u64)> = json.as_array().unwrap().iter()
+        .map(|c| (c["path"].as_str().unwrap().to_string(), c["start_line"].as_u64().unwrap(), c["end_line"].as_u64().unwrap()))
+        .collect();
+    chunks.sort();
+    let a = |s, e| ("docs/a.md".to_string(), s, e);
+    assert_eq!(chunks, vec![a(1, 2), a(2, 3), a(3, 4), a(4, 5), ("docs/b.md".to_string(), 1, 1)]);
+    assert!(json[0]["text"].as_str().unwrap().starts_with("abcdefg\n"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn chunking_needs_json_and_a_smaller_overlap() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("hello\n").unwrap();
+
+    lf(&temp, &["a.txt", "--chunk-tokens", "100"]).code(3).stderr(predicate::str::contains("--chunk-tokens requires --format json or jsonl"));
+    lf(&temp, &["a.txt", "--format", "json", "--chunk-tokens", "100", "--chunk-overlap", "100"]).code(3)
+        .stderr(predicate::str::contains("--chunk-overlap (100) must be smaller than --chunk-tokens (100)"));
+    temp.close().unwrap();
+}
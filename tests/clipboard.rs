@@ -0,0 +1,102 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[cfg(all(unix, feature = "clipboard"))]
+#[test]
+fn clipboard_cmd_receives_output() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("payload\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).arg("a.txt").arg("--clipboard-cmd").arg("tee copied.out");
+    cmd.assert().success().stdout(predicate::str::contains("payload").not());
+
+    temp.child("copied.out").assert("a.txt\npayload\n\n\n");
+
+    temp.close().unwrap();
+}
+
+#[cfg(all(unix, feature = "clipboard"))]
+#[test]
+fn clipboard_cmd_from_config_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("payload\n").unwrap();
+    temp.child(".lf.toml").write_str("clipboard-cmd = \"tee from-config.out\"\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).arg("a.txt");
+    cmd.assert().success();
+
+    temp.child("from-config.out").assert(predicate::str::contains("payload"));
+
+    temp.close().unwrap();
+}
+
+#[cfg(all(unix, feature = "clipboard"))]
+#[test]
+fn failing_clipboard_cmd_exits_unless_falling_back_to_stdout() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("payload\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).arg("a.txt").arg("--clipboard-cmd").arg("false");
+    cmd.assert().code(8).stdout(predicate::str::contains("payload").not())
+        .stderr(predicate::str::contains("Error: failed to copy to clipboard: ").and(predicate::str::contains("--fallback-stdout")));
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).arg("a.txt").arg("--clipboard-cmd").arg("false").arg("--fallback-stdout");
+    cmd.assert().success().stdout(predicate::str::contains("payload"));
+
+    temp.close().unwrap();
+}
+
+#[cfg(not(feature = "clipboard"))]
+#[test]
+fn builds_without_clipboard_print_to_stdout() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("payload\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["a.txt", "--tokenizer", "approx"]);
+    cmd.assert().success().stdout(predicate::str::starts_with("a.txt\npayload\n"));
+    temp.close().unwrap();
+}
+
+#[cfg(all(unix, feature = "clipboard"))]
+#[test]
+fn successful_copy_is_confirmed_and_previewed() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str(&"line\n".repeat(30)).unwrap();
+    temp.child("b.txt").write_str("last\n").unwrap();
+    let lf = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("lf").unwrap();
+        cmd.current_dir(&temp).args(["*.txt", "--tokenizer", "approx", "--clipboard-cmd", "tee copied.out"]).args(args);
+        String::from_utf8(cmd.assert().success().get_output().stderr.clone()).unwrap()
+    };
+
+    assert_eq!(lf(&[]), "Copied 2 files (40 tokens) to clipboard\n");
+    let err = lf(&["--preview=3"]);
+    assert_eq!(err, "Copied 2 files (40 tokens) to clipboard\na.txt\nline\nline\n… (+ 34 more lines copied to clipboard)\n");
+    assert_eq!(lf(&["--preview"]).lines().count(), 22);
+    assert_eq!(lf(&["--preview", "-o", "out.txt", "--tee"]), "Copied 2 files (40 tokens) to clipboard\n");
+    temp.close().unwrap();
+}
+
+#[cfg(all(unix, feature = "clipboard"))]
+#[test]
+fn tee_wins_over_no_clipboard() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("payload\n").unwrap();
+
+    for (env, args) in [("1", &["--tee"][..]), ("0", &["--tee"]), ("0", &["--tee", "--no-clipboard"])] {
+        let mut cmd = Command::cargo_bin("lf").unwrap();
+        cmd.current_dir(&temp).env("LF_NO_CLIPBOARD", env).args(["a.txt", "-o", "out.txt", "--clipboard-cmd", "tee copied.out"]).args(args);
+        cmd.assert().success().stderr(predicate::str::contains("Copied 1 files"));
+        temp.child("out.txt").assert(predicate::str::contains("payload"));
+        temp.child("copied.out").assert(predicate::str::contains("payload"));
+        std::fs::remove_file(temp.path().join("copied.out")).unwrap();
+    }
+    temp.close().unwrap();
+}
@@ -0,0 +1,227 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).arg("--no-clipboard").arg("--tokenizer").arg("approx").args(args);
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn line_numbers_with_head_lines() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let body: String = (1..=150).map(|i| format!("row {}\n", i)).collect();
+    temp.child("big.txt").write_str(&body).unwrap();
+    temp.child("logo.png").write_binary(&[1, 2, 3]).unwrap();
+
+    let s = lf(&temp, &["big.txt", "--line-numbers", "--head-lines", "10"]);
+    assert!(s.contains("big.txt\n 1 | row 1\n"));
+    assert!(s.contains("10 | row 10\n… (140 more lines)\n"));
+    assert!(!s.contains("row 11"));
+
+    let plain = lf(&temp, &["big.txt"]);
+    let numbered = lf(&temp, &["big.txt", "--line-numbers"]);
+    assert!(numbered.contains("150 | row 150\n") && numbered.contains("  1 | row 1\n"));
+    let tokens = |s: &str| s.lines().find(|l| l.starts_with("Tokens")).unwrap().rsplit(' ').next().unwrap().parse::<usize>().unwrap();
+    assert!(tokens(&numbered) > tokens(&plain));
+
+    let bin = lf(&temp, &["logo.png", "--line-numbers"]);
+    assert!(predicate::str::contains("logo.png\n[Image file").eval(&bin));
+    temp.close().unwrap();
+}
+
+#[test]
+fn interactive_requires_a_terminal() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("a\n").unwrap();
+    Command::cargo_bin("lf").unwrap().current_dir(&temp)
+        .args(["--no-clipboard", ".", "--interactive"])
+        .stdin(std::process::Stdio::null())
+        .assert().failure().stderr(predicate::str::contains("needs a terminal"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn notebooks_are_flattened_to_their_sources() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let png = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+    temp.child("analysis.ipynb").write_str(&format!(r##"{{"cells": [
+        {{"cell_type": "markdown", "metadata": {{}}, "source": ["# Analysis"]}},
+        {{"cell_type": "code", "metadata": {{}}, "execution_count": 1, "source": ["plot(df)\n"], "outputs": [{{"output_type": "display_data", "data": {{"image/png": "{}"}}}}]}}
+    ], "metadata": {{"kernelspec": {{"name": "python3"}}}}, "nbformat": 4, "nbformat_minor": 5}}"##, png)).unwrap();
+    temp.child("broken.ipynb").write_str("{\"cells\": [\n").unwrap();
+
+    let out = lf(&temp, &["*.ipynb"]);
+    assert!(out.contains("analysis.ipynb\n# %% [cell 1] [markdown]\n# Analysis\n\n# %% [cell 2]\nplot(df)\n"), "{}", out);
+    assert!(!out.contains(png) && !out.contains("kernelspec"));
+    assert!(out.contains("broken.ipynb\n{\"cells\": [\n"));
+    assert!(out.contains("Lines: 6\n"), "{}", out);
+
+    let raw = lf(&temp, &["analysis.ipynb", "--raw-notebooks"]);
+    assert!(raw.contains(png));
+    temp.close().unwrap();
+}
+
+#[test]
+fn outline_elides_bodies_of_supported_languages() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/lib.rs").write_str("pub struct A {\n    pub x: u8,\n}\n\nimpl A {\n    pub fn get(&self) -> u8 {\n        self.x\n    }\n}\n").unwrap();
+    temp.child("src/notes.txt").write_str("one\ntwo\nthree\n").unwrap();
+
+    let out = lf(&temp, &["src", "--outline", "--head-lines", "2"]);
+    assert!(out.contains("src/lib.rs\npub struct A {\n    pub x: u8,\n… ("), "{}", out);
+    assert!(out.contains("src/notes.txt\none\ntwo\n… (1 more lines)\n"));
+
+    let out = lf(&temp, &["src/lib.rs", "--outline"]);
+    assert!(out.contains("impl A {\n    pub fn get(&self) -> u8 { ... }\n}\n"), "{}", out);
+    assert!(!out.contains("self.x"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn strip_rust_tests_only_touches_rust_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/lib.rs").write_str("pub fn one() -> u8 { 1 }\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn it() { assert_eq!(super::one(), 1); }\n}\n").unwrap();
+    temp.child("src/notes.md").write_str("#[cfg(test)]\nmod tests {}\n").unwrap();
+
+    let out = lf(&temp, &["src", "--strip-rust-tests"]);
+    assert!(out.contains("src/lib.rs\npub fn one() -> u8 { 1 }\n\n// tests omitted\n"), "{}", out);
+    assert!(out.contains("src/notes.md\n#[cfg(test)]\nmod tests {}\n"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn strip_license_headers_replaces_the_leading_block() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.go").write_str("// Copyright (c) 2024 Example\n// Licensed under the Apache License, Version 2.0\n\npackage a\n").unwrap();
+    temp.child("b.go").write_str("// Package b does things.\npackage b\n").unwrap();
+
+    let out = lf(&temp, &["*.go", "--strip-license-headers"]);
+    assert!(out.contains("a.go\n// license header omitted\n\npackage a\n"), "{}", out);
+    assert!(out.contains("b.go\n// Package b does things.\npackage b\n"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn max_line_length_truncates_but_keeps_line_counts() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("app.log").write_str(&format!("ok\n{}\n", "€".repeat(1010))).unwrap();
+
+    let out = lf(&temp, &["app.log", "--max-line-length", "10"]);
+    assert!(out.contains(&format!("app.log\nok\n{} …[truncated 1,000 chars]\n", "€".repeat(10))), "{}", out);
+    assert!(out.contains("Lines: 2\n"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn expand_tabs_and_dedent_compose() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.cs").write_str("\tclass A {\n\t\tint x;\t// field\n\t}\n").unwrap();
+
+    let out = lf(&temp, &["a.cs", "--expand-tabs=2", "--dedent"]);
+    assert!(out.contains("a.cs\nclass A {\n  int x;\t// field\n}\n"), "{}", out);
+    let out = lf(&temp, &["a.cs", "--expand-tabs", "--expand-all-tabs"]);
+    assert!(out.contains("a.cs\n    class A {\n        int x;  // field\n    }\n"), "{}", out);
+    temp.close().unwrap();
+}
+
+#[test]
+fn sample_data_keeps_the_schema() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let csv: String = std::iter::once("id,email\n".to_string()).chain((1..=1000).map(|i| format!("{},u{}@example.com\n", i, i))).collect();
+    temp.child("users.csv").write_str(&csv).unwrap();
+    let items: Vec<String> = (0..1000).map(|i| format!("{{\"id\":{},\"name\":\"item number {}\"}}", i, i)).collect();
+    temp.child("items.json").write_str(&format!("[{}]\n", items.join(","))).unwrap();
+
+    let out = lf(&temp, &["users.csv", "items.json", "--sample-data", "3"]);
+    assert!(out.contains("users.csv\nid,email\n1,u1@example.com\n2,u2@example.com\n3,u3@example.com\n… (997 more rows)\n"), "{}", out);
+    assert!(out.contains("\"name\": \"item number 2\"\n  }\n]\n… (997 more elements)\n"), "{}", out);
+    assert!(!out.contains("item number 3\""));
+    let csv_only = lf(&temp, &["users.csv", "--sample-data", "3"]);
+    assert!(csv_only.contains("Lines: 5\n"), "{}", csv_only);
+    temp.close().unwrap();
+}
+
+#[test]
+fn strip_ansi_cleans_text_but_not_binaries() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("build.log").write_str("\x1b[32mPASS\x1b[0m unit\n\x1b[31mFAIL\x1b[0m e2e\n").unwrap();
+    temp.child("trace.bin").write_binary(b"\x1b[32mraw").unwrap();
+
+    let out = lf(&temp, &["build.log", "trace.bin", "--strip-ansi", "--embed-binaries-under", "1K"]);
+    assert!(out.contains("build.log\nPASS unit\nFAIL e2e\n"), "{}", out);
+    assert!(out.contains("G1szMm1yYXc=\n"), "{}", out);
+    let raw = lf(&temp, &["build.log"]);
+    assert!(raw.contains("\x1b[32mPASS"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn strip_frontmatter_applies_to_markdown_only() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("docs/intro.md").write_str("---\ntitle: Intro\nweight: 10\n---\n<!-- TODO: screenshots -->\n# Intro\n").unwrap();
+    temp.child("docs/config.yml").write_str("---\nkey: value\n---\n").unwrap();
+
+    let out = lf(&temp, &["docs", "--strip-frontmatter"]);
+    assert!(out.contains("docs/intro.md\n# Intro\n"), "{}", out);
+    assert!(out.contains("docs/config.yml\n---\nkey: value\n---\n"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn skip_empty_drops_blank_files_after_transforms() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("pkg/__init__.py").touch().unwrap();
+    temp.child("pkg/blank.txt").write_str("  \n\t\n\n").unwrap();
+    temp.child("pkg/meta.md").write_str("---\ntitle: only front-matter\n---\n").unwrap();
+    temp.child("pkg/main.py").write_str("print('hi')\n").unwrap();
+
+    let out = lf(&temp, &["pkg", "--strip-frontmatter"]);
+    for name in ["pkg/__init__.py", "pkg/blank.txt", "pkg/meta.md", "pkg/main.py"] { assert!(out.contains(&format!("{}\n", name)), "{}", out); }
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["pkg", "--strip-frontmatter", "--skip-empty", "--no-clipboard", "--tokenizer", "approx", "--stats-json"]);
+    let assert = cmd.assert().success().stderr(predicate::str::contains("Skipped 3 empty files"));
+    let out = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    assert!(out.contains("pkg/main.py\nprint('hi')\n"));
+    assert!(!out.contains("__init__") && !out.contains("blank.txt") && !out.contains("meta.md"), "{}", out);
+    assert!(String::from_utf8(assert.get_output().stderr.clone()).unwrap().contains("\"skipped_empty\": 3"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn skip_regions_are_elided_and_counted_as_shown() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("gen.rs").write_str("fn keep() {}\n// lf:skip-start\nconst A: u8 = 1;\nconst B: u8 = 2;\n// lf:skip-end\n").unwrap();
+    temp.child("open.rs").write_str("fn a() {}\n// lf:skip-start\nfn b() {}\n").unwrap();
+
+    let s = lf(&temp, &["gen.rs", "--line-numbers"]);
+    assert!(s.contains("1 | fn keep() {}\n2 | // lf:skip-start\n3 | [… 2 lines elided (lf:skip region) …]\n4 | // lf:skip-end\n"), "{}", s);
+    assert!(s.contains("Lines: 4\n"));
+    assert!(lf(&temp, &["gen.rs", "--no-region-markers"]).contains("const B"));
+    assert!(lf(&temp, &["gen.rs", "--region-markers", "fn keep", "skip-end"]).contains("fn keep() {}\n[… 3 lines elided"));
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["open.rs", "--no-clipboard", "--path-style", "absolute"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("fn b() {}"))
+        .stderr(predicate::str::contains(format!("Warning: {}:2: 'lf:skip-start' has no matching 'lf:skip-end'", temp.path().canonicalize().unwrap().join("open.rs").display())));
+}
+
+#[test]
+fn other_transforms_keep_the_read_line_count() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("lib.rs").write_str("pub fn a() {}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn t() {}\n}\n").unwrap();
+    temp.child("Main.java").write_str("import a.A;\nimport b.B;\nimport c.C;\n\npublic class Main {}\n").unwrap();
+
+    let stripped = lf(&temp, &["lib.rs", "--strip-rust-tests"]);
+    assert!(stripped.contains("lib.rs\npub fn a() {}\n\n// tests omitted\n"), "{}", stripped);
+    assert!(stripped.contains("Lines: 7\n"), "{}", stripped);
+    let masked = lf(&temp, &["Main.java", "--mask-java-imports"]);
+    assert!(masked.contains("Main.java\nimport ...\n\npublic class Main {}\n"), "{}", masked);
+    assert!(masked.contains("Lines: 5\n"), "{}", masked);
+    temp.close().unwrap();
+}
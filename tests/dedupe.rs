@@ -0,0 +1,66 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use std::process::Command;
+
+#[test]
+fn identical_files_are_emitted_once() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let license = "Permission is hereby granted, free of charge, to any person obtaining a copy\n".repeat(20);
+    for dir in ["vendor/c", "vendor/a", "vendor/b"] { temp.child(format!("{}/LICENSE", dir)).write_str(&license).unwrap(); }
+    temp.child("vendor/d/LICENSE").write_str(&license.replacen("charge", "Charge", 1)).unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["vendor", "--dedupe-content", "--no-clipboard", "--tokenizer", "approx", "--stats-json=stats.json"]);
+    let out = cmd.assert().success().get_output().clone();
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(stdout.matches("Permission is hereby granted, free of charge").count(), 20 + 19, "{}", stdout);
+    assert_eq!(stdout.matches("Permission is hereby granted, free of Charge").count(), 1);
+    assert!(stdout.contains("vendor/b/LICENSE\n[identical to vendor/a/LICENSE]\n"), "{}", stdout);
+    assert!(stdout.contains("vendor/c/LICENSE\n[identical to vendor/a/LICENSE]\n"));
+    assert!(stdout.find("vendor/a/LICENSE\n").unwrap() < stdout.find("vendor/b/LICENSE\n").unwrap());
+
+    let stats: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(temp.path().join("stats.json")).unwrap()).unwrap();
+    assert_eq!(stats["duplicates"], 2);
+    assert!(stats["duplicate_tokens_saved"].as_u64().unwrap() > 700);
+    assert!(String::from_utf8(out.stderr).unwrap().contains("Folded 2 duplicate files (saved "));
+    temp.close().unwrap();
+}
+
+#[test]
+fn dedupe_keeps_the_pattern_order() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let body = "fn main() {\n    println!(\"hello from a file long enough to fold\");\n}\n";
+    temp.child("src/main.rs").write_str(body).unwrap();
+    temp.child("docs/copy.rs").write_str(body).unwrap();
+    temp.child("src/x.txt").write_str("x\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["--pattern-order", "src/main.rs", "docs/", "src/x.txt", "--dedupe-content", "--no-clipboard", "--tokenizer", "approx"]);
+    let stdout = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+    let headers: Vec<&str> = stdout.lines().filter(|l| l.starts_with("src/") || l.starts_with("docs/")).collect();
+    assert_eq!(headers, ["src/main.rs", "docs/copy.rs", "src/x.txt"], "{}", stdout);
+    assert!(stdout.contains("docs/copy.rs\n[identical to src/main.rs]\n"), "{}", stdout);
+    temp.close().unwrap();
+}
+
+#[test]
+fn small_duplicates_stay_when_the_pointer_costs_more() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    for dir in ["a", "b"] {
+        temp.child(format!("{}/tiny.txt", dir)).write_str("x\n").unwrap();
+        temp.child(format!("{}/big.txt", dir)).write_str(&"shared line of text\n".repeat(10)).unwrap();
+    }
+    let tokens = |args: &[&str]| -> (u64, serde_json::Value) {
+        let mut cmd = Command::cargo_bin("lf").unwrap();
+        cmd.current_dir(&temp).args(["a", "b", "--no-clipboard", "--tokenizer", "approx", "--stats-json=stats.json"]).args(args);
+        let stdout = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+        assert!(stdout.contains("b/tiny.txt\nx\n"), "{}", stdout);
+        let stats: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(temp.path().join("stats.json")).unwrap()).unwrap();
+        (stats["tokens"].as_u64().unwrap(), stats)
+    };
+    let (plain, _) = tokens(&[]);
+    let (deduped, stats) = tokens(&["--dedupe-content"]);
+    assert_eq!(stats["duplicates"], 1);
+    assert_eq!(stats["duplicate_tokens_saved"].as_u64().unwrap(), plain - deduped);
+    temp.close().unwrap();
+}
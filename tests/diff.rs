@@ -0,0 +1,61 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(["--no-clipboard", "--tokenizer", "approx"]).args(args);
+    cmd.assert()
+}
+
+fn snapshots() -> assert_fs::TempDir {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/kept.rs").write_str("fn kept() {}\n").unwrap();
+    temp.child("src/edited.rs").write_str("fn edited() {}\n").unwrap();
+    temp.child("src/gone.rs").write_str("fn gone() {}\n").unwrap();
+    lf(&temp, &["src", "--format", "json", "-o", "old.json"]).success();
+
+    temp.child("src/edited.rs").write_str("fn edited() {\n    println!(\"now with a body\");\n}\n").unwrap();
+    std::fs::remove_file(temp.child("src/gone.rs").path()).unwrap();
+    temp.child("src/new.rs").write_str("fn new() {}\n").unwrap();
+    lf(&temp, &["src", "--format", "jsonl", "-o", "new.jsonl"]).success();
+    temp
+}
+
+#[test]
+fn diff_classifies_added_removed_and_changed_files() {
+    let temp = snapshots();
+    let out = lf(&temp, &["--diff", "old.json", "new.jsonl"]).success().get_output().stdout.clone();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("+ src/new.rs (+"), "{}", out);
+    assert!(out.contains("- src/gone.rs (-"), "{}", out);
+    assert!(out.contains("~ src/edited.rs (+"), "{}", out);
+    assert!(!out.contains("src/kept.rs"), "{}", out);
+    assert!(out.contains("(1 added, 1 removed, 1 changed, 1 unchanged)"), "{}", out);
+    temp.close().unwrap();
+}
+
+#[test]
+fn fail_on_change_exits_one() {
+    let temp = snapshots();
+    lf(&temp, &["--diff", "old.json", "new.jsonl", "--fail-on-change"]).code(1).stderr(predicate::str::contains("snapshots differ: 1 added, 1 removed, 1 changed"));
+    lf(&temp, &["--diff", "old.json", "old.json", "--fail-on-change"]).success().stdout(predicate::str::contains("No changes (3 files)"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn diff_against_compares_the_current_run() {
+    let temp = snapshots();
+    lf(&temp, &["src", "--format", "json", "-o", "now.json", "--diff-against", "new.jsonl", "--fail-on-change"]).success().stdout(predicate::str::contains("No changes (3 files)"));
+    lf(&temp, &["src", "--format", "json", "-o", "now.json", "--diff-against", "old.json", "--fail-on-change"]).code(1).stdout(predicate::str::contains("+ src/new.rs"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn text_output_is_not_a_snapshot() {
+    let temp = snapshots();
+    lf(&temp, &["src", "-o", "plain.txt"]).success();
+    lf(&temp, &["--diff", "plain.txt", "new.jsonl"]).code(3).stderr(predicate::str::contains("not an lf --format json/jsonl snapshot"));
+    temp.close().unwrap();
+}
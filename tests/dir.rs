@@ -0,0 +1,55 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn lf(cwd: &std::path::Path, args: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(cwd).args(["--no-clipboard", "--tokenizer", "approx"]).args(args);
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn dir_flag_matches_an_in_directory_run() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let backend = temp.child("backend");
+    backend.child(".gitignore").write_str("gen/\n").unwrap();
+    backend.child("src/main.rs").write_str("fn main() {}\n").unwrap();
+    backend.child("src/gen/out.rs").write_str("// generated\n").unwrap();
+    backend.child("README.md").write_str("# backend\n").unwrap();
+    let elsewhere = temp.child("elsewhere");
+    elsewhere.create_dir_all().unwrap();
+
+    let inside = lf(backend.path(), &["src/**"]);
+    let outside = lf(elsewhere.path(), &["-C", backend.path().to_str().unwrap(), "src/**"]);
+    assert_eq!(inside, outside);
+    assert!(outside.starts_with("src/main.rs\n"));
+    assert!(!outside.contains("generated") && !outside.contains("README"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn dir_must_exist() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    Command::cargo_bin("lf").unwrap().current_dir(temp.path())
+        .args(["--no-clipboard", "-C", "missing", "."])
+        .assert().failure().stderr(predicate::str::contains("not a directory"));
+}
+
+#[test]
+fn multiple_dirs_are_aggregated_with_root_prefixes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("protos/.gitignore").write_str("draft.proto\n").unwrap();
+    temp.child("protos/api.proto").write_str("// shared\n").unwrap();
+    temp.child("protos/draft.proto").write_str("// draft\n").unwrap();
+    temp.child("app/api.proto").write_str("// local\n").unwrap();
+    temp.child("app/draft.proto").write_str("// local draft\n").unwrap();
+
+    let out = lf(temp.path(), &["--dir", "protos", "--dir", "app", "--dir", "app", "**/*.proto"]);
+    assert!(out.contains("protos/api.proto\n// shared\n"));
+    assert!(out.contains("app/api.proto\n// local\n"));
+    assert!(out.contains("app/draft.proto\n// local draft\n"));
+    assert!(!out.contains("// draft\n"));
+    assert_eq!(out.matches("app/api.proto").count(), 1);
+    temp.close().unwrap();
+}
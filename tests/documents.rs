@@ -0,0 +1,69 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(args).arg("--no-clipboard").arg("--tokenizer").arg("approx");
+    cmd.assert()
+}
+
+#[cfg(feature = "doc-extract")]
+fn write_pdf(path: &std::path::Path, text: &str) {
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, Document, Object, Stream};
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" });
+    let resources_id = doc.add_object(dictionary! { "Font" => dictionary! { "F1" => font_id } });
+    let content = Content { operations: vec![
+        Operation::new("BT", vec![]),
+        Operation::new("Tf", vec!["F1".into(), 24.into()]),
+        Operation::new("Td", vec![72.into(), 700.into()]),
+        Operation::new("Tj", vec![Object::string_literal(text)]),
+        Operation::new("ET", vec![]),
+    ] };
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+    let page_id = doc.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id, "Contents" => content_id });
+    doc.objects.insert(pages_id, Object::Dictionary(dictionary! {
+        "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1, "Resources" => resources_id,
+        "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+    }));
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog_id);
+    doc.save(path).unwrap();
+}
+
+#[cfg(feature = "doc-extract")]
+#[test]
+fn pdf_and_docx_text_is_extracted() {
+    use std::io::Write;
+    let temp = assert_fs::TempDir::new().unwrap();
+    write_pdf(temp.child("design.pdf").path(), "Queue design overview");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(temp.child("spec.docx").path()).unwrap());
+    zip.start_file("word/document.xml", zip::write::SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"<w:document><w:body><w:p><w:r><w:t>Retry budget is 3</w:t></w:r></w:p></w:body></w:document>").unwrap();
+    zip.finish().unwrap();
+    temp.child("broken.pdf").write_str("%PDF-1.4 nope").unwrap();
+
+    let out = lf(&temp, &["*.pdf", "*.docx", "--extract-documents"]).success().get_output().clone();
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.contains("design.pdf [extracted from PDF]\n"), "{}", stdout);
+    assert!(stdout.contains("Queue design overview"));
+    assert!(stdout.contains("spec.docx [extracted from DOCX]\nRetry budget is 3\n"));
+    assert!(stdout.contains("broken.pdf\n[Document file: 13 bytes]"));
+    assert!(String::from_utf8(out.stderr).unwrap().contains("Warning: could not extract text from broken.pdf"));
+
+    let plain = String::from_utf8(lf(&temp, &["*.pdf"]).success().get_output().stdout.clone()).unwrap();
+    assert!(plain.contains("design.pdf\n[Document file:") && !plain.contains("Queue design"));
+    temp.close().unwrap();
+}
+
+#[cfg(not(feature = "doc-extract"))]
+#[test]
+fn extract_documents_needs_the_feature() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("design.pdf").write_str("%PDF-1.4").unwrap();
+    lf(&temp, &["*.pdf", "--extract-documents"]).code(3).stderr(predicates::str::contains("doc-extract feature"));
+    temp.close().unwrap();
+}
@@ -0,0 +1,39 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+fn dry_run_reports_without_reading_or_writing() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/main.rs").write_str(&"fn secret_body() {}\n".repeat(50)).unwrap();
+    temp.child("src/lib.rs").write_str("pub mod app;\n").unwrap();
+    temp.child("assets/logo.png").write_binary(&[0u8; 2000]).unwrap();
+    temp.child("notes/skip.md").write_str("skip me\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["src", "assets", "~**/lib.rs", "--dry-run", "-o", "dump.txt", "--tokenizer", "approx"]);
+    let out = cmd.assert().success().stdout(predicate::str::contains("secret_body").not()).get_output().stdout.clone();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("Would emit 2 files (1 binary), 2.9 KiB, ~750 tokens (bytes/4)\nLargest files:\n"), "{}", out);
+    let main = out.find("src/main.rs").unwrap();
+    let logo = out.find("assets/logo.png").unwrap();
+    assert!(logo < main && !out.contains("lib.rs") && !out.contains("skip.md"));
+    temp.child("dump.txt").assert(predicate::path::missing());
+    temp.close().unwrap();
+}
+
+#[test]
+fn si_units_switches_sizes_to_powers_of_1000() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("assets/logo.png").write_binary(&[0u8; 2000]).unwrap();
+    temp.child("assets/icon.png").write_binary(&[0u8; 1000]).unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["assets", "--dry-run", "--si-units"]);
+    cmd.assert().success().stdout(predicate::str::starts_with("Would emit 2 files (2 binary), 3.0 KB, ~750 tokens (bytes/4)\nLargest files:\n     2.0 KB  assets/logo.png\n     1.0 KB  assets/icon.png\n"));
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["assets", "--no-clipboard", "--tokenizer", "approx"]);
+    cmd.assert().success().stdout(predicate::str::contains("assets/logo.png\n[Image file: 2.0 KiB]").and(predicate::str::contains("[Image file: 1,000 bytes]")));
+    temp.close().unwrap();
+}
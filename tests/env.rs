@@ -0,0 +1,90 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, env: &[(&str, &str)], args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(["--tokenizer", "approx"]).args(args);
+    for var in ["LF_NO_CLIPBOARD", "LF_FORMAT", "LF_EXCLUDE", "LF_CLIPBOARD_CMD"] { cmd.env_remove(var); }
+    cmd.envs(env.iter().copied());
+    cmd.assert()
+}
+
+fn fixture() -> assert_fs::TempDir {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/main.rs").write_str("fn main() {}\n").unwrap();
+    temp.child("target/debug/gen.rs").write_str("// generated\n").unwrap();
+    temp.child("node_modules/pkg/index.rs").write_str("// vendored\n").unwrap();
+    temp
+}
+
+#[test]
+fn no_clipboard_from_env() {
+    let temp = fixture();
+    lf(&temp, &[("LF_NO_CLIPBOARD", "1")], &["src"]).success().stdout(predicate::str::starts_with("src/main.rs\nfn main() {}\n"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn format_from_env_and_flag_override() {
+    let temp = fixture();
+    lf(&temp, &[("LF_FORMAT", "markdown")], &["src", "-n"]).success().stdout(predicate::str::contains("```rust\nfn main() {}\n```"));
+    lf(&temp, &[("LF_FORMAT", "markdown")], &["src", "-n", "--format", "plain"]).success().stdout(predicate::str::contains("```").not());
+    lf(&temp, &[("LF_FORMAT", "yaml")], &["src", "-n"]).failure().stderr(predicate::str::contains("LF_FORMAT").or(predicate::str::contains("yaml")));
+    temp.close().unwrap();
+}
+
+#[test]
+fn excludes_from_env_and_flag_override() {
+    let temp = fixture();
+    let env = [("LF_EXCLUDE", "target/**:~node_modules/")];
+    let out = lf(&temp, &env, &["**/*.rs", "-n"]).success().get_output().stdout.clone();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("fn main()") && !out.contains("generated") && !out.contains("vendored"), "{}", out);
+
+    let out = lf(&temp, &env, &["**/*.rs", "-n", "--exclude", "src/**"]).success().get_output().stdout.clone();
+    let out = String::from_utf8(out).unwrap();
+    assert!(!out.contains("fn main()") && out.contains("generated") && out.contains("vendored"), "{}", out);
+    temp.close().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn exclude_flag_keeps_a_literal_colon() {
+    let temp = fixture();
+    temp.child("notes/a:b.txt").write_str("colon\n").unwrap();
+    temp.child("notes/b.txt").write_str("plain\n").unwrap();
+    let out = lf(&temp, &[], &["notes", "-n", "--exclude", "notes/a:b.txt"]).success().get_output().stdout.clone();
+    let out = String::from_utf8(out).unwrap();
+    assert!(!out.contains("colon") && out.contains("plain"), "{}", out);
+    temp.close().unwrap();
+}
+
+#[cfg(all(unix, feature = "clipboard"))]
+#[test]
+fn clipboard_cmd_env_beats_the_config_file() {
+    let temp = fixture();
+    temp.child(".lf.toml").write_str("clipboard-cmd = \"tee from-config.out\"\n").unwrap();
+    lf(&temp, &[("LF_CLIPBOARD_CMD", "tee from-env.out")], &["src"]).success();
+    temp.child("from-env.out").assert(predicate::str::contains("fn main() {}"));
+    temp.child("from-config.out").assert(predicate::path::missing());
+
+    lf(&temp, &[("LF_CLIPBOARD_CMD", "tee from-env.out")], &["src", "--clipboard-cmd", "tee from-flag.out"]).success();
+    temp.child("from-flag.out").assert(predicate::str::contains("fn main() {}"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn no_patterns_default_to_everything() {
+    let temp = fixture();
+    temp.child(".gitignore").write_str("target/\nnode_modules/\n").unwrap();
+    let out = lf(&temp, &[], &["-n"]).success().stderr(predicate::str::contains("No patterns given; defaulting to '**/*'")).get_output().stdout.clone();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("src/main.rs\nfn main() {}\n") && !out.contains("generated") && !out.contains("No patterns"), "{}", out);
+
+    lf(&temp, &[], &["-n", "--require-patterns"]).code(1).stderr(predicate::str::contains("At least one pattern must be provided"));
+    temp.child(".lf.toml").write_str("require-patterns = true\n").unwrap();
+    lf(&temp, &[], &["-n"]).code(1).stdout("");
+    temp.close().unwrap();
+}
@@ -0,0 +1,32 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use std::process::Command;
+
+fn explain(temp: &assert_fs::TempDir, args: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).arg("--no-clipboard").args(args);
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn explain_names_the_deciding_rule() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/main.rs").write_str("fn main() {}\n").unwrap();
+    temp.child("node_modules/x/index.js").write_str("module.exports = 1;\n").unwrap();
+    temp.child("dist/app.js").write_str("bundle\n").unwrap();
+    temp.child(".gitignore").write_str("dist/\n").unwrap();
+    temp.child(".env").write_str("SECRET=1\n").unwrap();
+
+    let out = explain(&temp, &["src", "**/*.js", "~**/node_modules/**", "--explain", "src/main.rs", "--explain", "node_modules/x/index.js", "--explain", "dist/app.js", "--explain", "README.md"]);
+    assert!(out.contains("src/main.rs: included by pattern \"src\"\n"), "{}", out);
+    assert!(out.contains("node_modules/x/index.js: excluded by pattern \"~**/node_modules/**\" (include pattern \"**/*.js\" matched)\n"), "{}", out);
+    assert!(out.contains("dist/app.js: excluded by ignore rule \"dist/\" from "), "{}", out);
+    assert!(out.contains("README.md: not matched by any include pattern\n"), "{}", out);
+    assert!(!out.contains("fn main"));
+
+    let all = explain(&temp, &[".", "--explain-all"]);
+    assert!(all.contains(".env: not matched: hidden path"), "{}", all);
+    assert!(all.contains("src/main.rs: included by pattern \".\"\n"), "{}", all);
+    assert!(all.contains("dist/app.js: excluded by ignore rule"), "{}", all);
+    temp.close().unwrap();
+}
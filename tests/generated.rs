@@ -0,0 +1,51 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(["--no-clipboard", "--tokenizer", "approx"]).args(args);
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn gitattributes_generated_files_become_placeholders() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child(".gitattributes").write_str("*.pb.go linguist-generated=true\n").unwrap();
+    temp.child("api/user.pb.go").write_str(&"// generated code\n".repeat(4812)).unwrap();
+    temp.child("api/user.go").write_str("package api\n").unwrap();
+    temp.child("third_party/.gitattributes").write_str("*.js linguist-vendored\n").unwrap();
+    temp.child("third_party/lib.js").write_str("var vendored = 1;\n").unwrap();
+
+    let out = lf(&temp, &["api", "third_party"]);
+    assert!(out.contains("api/user.pb.go\n[Generated file omitted: api/user.pb.go, 4,812 lines]\n"), "{}", out);
+    assert!(out.contains("third_party/lib.js\n[Generated file omitted: third_party/lib.js, 1 lines]\n"), "{}", out);
+    assert!(out.contains("api/user.go\npackage api\n"));
+    assert!(!out.contains("// generated code") && !out.contains("var vendored"));
+
+    let full = lf(&temp, &["api", "--include-generated"]);
+    assert!(full.contains("// generated code") && !full.contains("omitted"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn minified_files_are_summarized_and_counted() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("dist/bundle.min.js").write_str("var a=1;\n").unwrap();
+    temp.child("dist/app.js").write_str(&format!("{}\n{}\n{}\n", "x".repeat(9000), "y".repeat(9000), "z".repeat(9000))).unwrap();
+    temp.child("src/schema.rs").write_str("// @generated automatically by Diesel CLI.\ntable! {}\n").unwrap();
+    temp.child("src/main.rs").write_str("fn main() {}\n").unwrap();
+
+    let json = temp.child("stats.json");
+    let out = lf(&temp, &["dist", "src", &format!("--stats-json={}", json.path().display())]);
+    assert!(out.contains("dist/app.js\n[Minified/generated file: dist/app.js — 26.4 KiB, 3 lines]\n"), "{}", out);
+    assert!(out.contains("[Minified/generated file: dist/bundle.min.js — 9 bytes, 1 lines]"));
+    assert!(out.contains("[Minified/generated file: src/schema.rs"));
+    assert!(out.contains("fn main() {}"));
+    let stats: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(json.path()).unwrap()).unwrap();
+    assert_eq!(stats["summarized"], 3);
+
+    let full = lf(&temp, &["dist", "--include-generated"]);
+    assert!(full.contains("var a=1;") && !full.contains("Minified"));
+    temp.close().unwrap();
+}
@@ -0,0 +1,178 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::path::Path;
+use std::process::Command;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git").current_dir(dir)
+        .args(["-c", "user.name=lf", "-c", "user.email=lf@example.com", "-c", "commit.gpgsign=false"])
+        .args(args).status().unwrap();
+    assert!(status.success(), "git {:?}", args);
+}
+
+fn repo() -> assert_fs::TempDir {
+    let temp = assert_fs::TempDir::new().unwrap();
+    git(temp.path(), &["init", "-q"]);
+    temp
+}
+
+fn lf(dir: &Path, args: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(dir).args(["--no-clipboard", "--tokenizer", "approx"]).args(args);
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn git_only_skips_untracked_files() {
+    let temp = repo();
+    temp.child(".gitignore").write_str("*.log\n").unwrap();
+    temp.child("src/lib.rs").write_str("pub fn a() {}\n").unwrap();
+    temp.child("forced.log").write_str("kept\n").unwrap();
+    git(temp.path(), &["add", ".gitignore", "src/lib.rs"]);
+    git(temp.path(), &["add", "-f", "forced.log"]);
+    temp.child("scratch.rs").write_str("// scratch\n").unwrap();
+    temp.child("src/gen.rs").write_str("// generated\n").unwrap();
+
+    let all = lf(temp.path(), &["."]);
+    assert!(all.contains("scratch.rs") && !all.contains("forced.log"));
+
+    let tracked = lf(temp.path(), &[".", "--git-only"]);
+    assert!(tracked.contains("src/lib.rs\n"));
+    assert!(tracked.contains("forced.log\nkept\n"));
+    assert!(!tracked.contains("scratch") && !tracked.contains("generated"));
+    assert_eq!(tracked, lf(temp.path(), &[".", "--git-only", "--no-gitignore"]));
+    temp.close().unwrap();
+}
+
+#[test]
+fn git_only_outside_a_repo_fails() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("a\n").unwrap();
+    Command::cargo_bin("lf").unwrap().current_dir(temp.path())
+        .env("GIT_CEILING_DIRECTORIES", temp.path().parent().unwrap())
+        .args(["--no-clipboard", ".", "--git-only"])
+        .assert().failure().stderr(predicate::str::contains("needs a git repository"));
+}
+
+#[test]
+fn changed_since_selects_modified_files_only() {
+    let temp = repo();
+    temp.child("keep.rs").write_str("// untouched\n").unwrap();
+    temp.child("edit.rs").write_str("// v1\n").unwrap();
+    temp.child("old_name.rs").write_str("// moved\n").unwrap();
+    temp.child("gone.rs").write_str("// deleted\n").unwrap();
+    temp.child("dirty.rs").write_str("// clean\n").unwrap();
+    git(temp.path(), &["add", "."]);
+    git(temp.path(), &["commit", "-qm", "base"]);
+    git(temp.path(), &["branch", "base"]);
+
+    temp.child("edit.rs").write_str("// v2\n").unwrap();
+    git(temp.path(), &["mv", "old_name.rs", "new_name.rs"]);
+    git(temp.path(), &["rm", "-q", "gone.rs"]);
+    git(temp.path(), &["commit", "-qam", "change"]);
+    temp.child("dirty.rs").write_str("// unstaged\n").unwrap();
+
+    let out = lf(temp.path(), &[".", "--changed-since", "base"]);
+    assert!(out.contains("edit.rs\n// v2\n"));
+    assert!(out.contains("new_name.rs\n// moved\n"));
+    assert!(out.contains("dirty.rs\n// unstaged\n"));
+    assert!(!out.contains("keep.rs") && !out.contains("gone.rs") && !out.contains("old_name.rs"));
+
+    let rs_only = lf(temp.path(), &["edit.rs", "--changed-since", "base"]);
+    assert!(rs_only.contains("edit.rs") && !rs_only.contains("dirty.rs"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn changed_since_invalid_ref_reports_git_error() {
+    let temp = repo();
+    temp.child("a.rs").write_str("a\n").unwrap();
+    git(temp.path(), &["add", "."]);
+    git(temp.path(), &["commit", "-qm", "base"]);
+    Command::cargo_bin("lf").unwrap().current_dir(temp.path())
+        .args(["--no-clipboard", ".", "--changed-since", "no-such-ref"])
+        .assert().failure().stderr(predicate::str::contains("no-such-ref"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn staged_emits_index_content_not_the_working_tree() {
+    let temp = repo();
+    temp.child("src/a.rs").write_str("// v1\n").unwrap();
+    temp.child("b.rs").write_str("// untouched\n").unwrap();
+    git(temp.path(), &["add", "."]);
+    git(temp.path(), &["commit", "-qm", "base"]);
+
+    temp.child("src/a.rs").write_str("// v2 staged\n").unwrap();
+    git(temp.path(), &["add", "src/a.rs"]);
+    temp.child("src/a.rs").write_str("// v3 working tree\n").unwrap();
+    temp.child("b.rs").write_str("// unstaged edit\n").unwrap();
+
+    let out = lf(temp.path(), &[".", "--staged"]);
+    assert!(out.contains("src/a.rs\n// v2 staged\n"), "{}", out);
+    assert!(!out.contains("v3") && !out.contains("b.rs"));
+
+    let sub = lf(&temp.path().join("src"), &[".", "--staged"]);
+    assert!(sub.contains("a.rs\n// v2 staged\n"), "{}", sub);
+    temp.close().unwrap();
+}
+
+#[test]
+fn at_rev_emits_files_as_they_were_at_the_revision() {
+    let temp = repo();
+    temp.child("src/a.rs").write_str("// v1\n").unwrap();
+    temp.child("src/gone.rs").write_str("// deleted later\n").unwrap();
+    temp.child("notes.txt").write_str("old notes\n").unwrap();
+    temp.child("data.dat").write_binary(b"ab\0cd").unwrap();
+    git(temp.path(), &["add", "."]);
+    git(temp.path(), &["commit", "-qm", "v1"]);
+    git(temp.path(), &["tag", "v1"]);
+
+    temp.child("src/a.rs").write_str("// v2\n").unwrap();
+    temp.child("src/new.rs").write_str("// added later\n").unwrap();
+    git(temp.path(), &["rm", "-q", "src/gone.rs"]);
+    git(temp.path(), &["add", "."]);
+    git(temp.path(), &["commit", "-qm", "v2"]);
+
+    let out = lf(temp.path(), &[".", "~*.txt", "--at-rev", "v1"]);
+    assert!(out.contains("src/a.rs @ v1\n// v1\n"), "{}", out);
+    assert!(out.contains("src/gone.rs @ v1\n// deleted later\n"));
+    assert!(out.contains("data.dat @ v1\n[Binary file: 5 bytes]"), "{}", out);
+    assert!(!out.contains("v2") && !out.contains("new.rs") && !out.contains("notes"));
+
+    let sub = lf(&temp.path().join("src"), &["a.rs", "--at-rev", "v1"]);
+    assert!(sub.contains("a.rs @ v1\n// v1\n") && !sub.contains("gone.rs"), "{}", sub);
+    temp.close().unwrap();
+}
+
+#[test]
+fn at_rev_rejects_working_tree_flags() {
+    let temp = repo();
+    temp.child("a.rs").write_str("a\n").unwrap();
+    git(temp.path(), &["add", "."]);
+    git(temp.path(), &["commit", "-qm", "base"]);
+    Command::cargo_bin("lf").unwrap().current_dir(temp.path())
+        .args(["--no-clipboard", ".", "--at-rev", "HEAD", "--recent", "3"])
+        .assert().code(3).stderr(predicate::str::contains("--at-rev"));
+    Command::cargo_bin("lf").unwrap().current_dir(temp.path())
+        .args(["--no-clipboard", ".", "--at-rev", "no-such-rev"])
+        .assert().failure().stderr(predicate::str::contains("no-such-rev"));
+    Command::cargo_bin("lf").unwrap().current_dir(temp.path())
+        .args(["--no-clipboard", ".", "--at-rev=--name-only"])
+        .assert().failure().stderr(predicate::str::contains("Not a valid object name --name-only"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn changed_since_rejects_revs_that_look_like_options() {
+    let temp = repo();
+    temp.child("a.rs").write_str("a\n").unwrap();
+    git(temp.path(), &["add", "."]);
+    git(temp.path(), &["commit", "-qm", "base"]);
+    Command::cargo_bin("lf").unwrap().current_dir(temp.path())
+        .args(["--no-clipboard", ".", "--changed-since=--output=pwned"])
+        .assert().code(3).stderr(predicate::str::contains("--changed-since takes a git revision"));
+    assert!(!temp.path().join("pwned").exists());
+    temp.close().unwrap();
+}
@@ -53,7 +53,8 @@ fn gitignore_negation_is_ignored_but_common_cases_work() {
     cmd.current_dir(&temp).arg("**/*").arg("--no-clipboard");
 
     cmd.assert()
-        .success()
+        .code(2)
+        .stdout(predicate::str::contains("No files found"))
         .stdout(predicate::str::contains("other.txt").not());
 
     temp.close().unwrap();
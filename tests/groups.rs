@@ -0,0 +1,55 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(["--no-clipboard", "--tokenizer", "approx", "--group-by-dir"]).args(args);
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+fn fixture() -> assert_fs::TempDir {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/api/routes.rs").write_str("fn routes() {}\nfn more() {}\n").unwrap();
+    temp.child("src/main.rs").write_str("fn main() {}\n").unwrap();
+    temp.child("docs/guide.md").write_str("# Guide\n\nRead me.\n").unwrap();
+    temp.child("tests/smoke.rs").write_str("#[test]\nfn smoke() {}\n").unwrap();
+    temp
+}
+
+// "-------- src: 2 files, 3 lines, 9 tokens --------" -> 3 for "lines"
+fn number_after(line: &str, label: &str) -> usize {
+    let (_, totals) = line.trim_matches(['-', ' ']).split_once(": ").unwrap();
+    totals.split(", ").find_map(|t| t.strip_suffix(label)).unwrap().trim().replace(',', "").parse().unwrap()
+}
+
+#[test]
+fn banners_and_subtotals_per_top_level_dir() {
+    let temp = fixture();
+    let out = lf(&temp, &["docs", "src", "tests"]);
+    let banners: Vec<&str> = out.lines().filter(|l| l.starts_with("======== ")).collect();
+    assert_eq!(banners, ["======== docs ========", "======== src ========", "======== tests ========"]);
+    assert!(out.find("======== src ========").unwrap() < out.find("src/api/routes.rs").unwrap());
+    assert!(out.find("src/main.rs").unwrap() < out.find("-------- src: 2 files, 3 lines").unwrap());
+    assert!(out.find("-------- src:").unwrap() < out.find("======== tests ========").unwrap());
+
+    let subtotals: Vec<&str> = out.lines().filter(|l| l.starts_with("-------- ")).collect();
+    assert_eq!(subtotals.len(), 3);
+    let sum = |label: &str| subtotals.iter().map(|l| number_after(l, label)).sum::<usize>();
+    assert_eq!(sum("files"), 4);
+    let total_lines: usize = out.lines().find_map(|l| l.strip_prefix("Lines: ")).unwrap().parse().unwrap();
+    let total_tokens: usize = out.lines().find_map(|l| l.strip_prefix("Tokens (approx): ")).unwrap().parse().unwrap();
+    assert_eq!(sum("lines"), total_lines);
+    assert_eq!(sum("tokens"), total_tokens);
+    temp.close().unwrap();
+}
+
+#[test]
+fn group_depth_and_markdown_headings() {
+    let temp = fixture();
+    let out = lf(&temp, &["src", "--group-depth", "2", "--format", "markdown"]);
+    let headings: Vec<&str> = out.lines().filter(|l| l.starts_with("## ")).collect();
+    assert_eq!(headings, ["## src", "## src/api"]);
+    assert!(out.contains("_src/api: 1 files, 2 lines, "), "{}", out);
+    temp.close().unwrap();
+}
@@ -0,0 +1,39 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use std::process::Command;
+
+const BLOB_SHA256: &str = "ae4b3280e56e2faf83f414a6e3dabe9d5fbe18976544c05fed121accb85b53fc";
+const HELLO_SHA256: &str = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(args).arg("--no-clipboard").arg("--tokenizer").arg("approx");
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn hash_binaries_appends_sha256_to_placeholders() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("blob.bin").write_binary(&[0, 1, 2]).unwrap();
+    temp.child("hello.txt").write_str("hello\n").unwrap();
+
+    let out = lf(&temp, &["*", "--hash-binaries"]);
+    assert!(out.contains(&format!("blob.bin\n[Binary file: 3 bytes] sha256:{}\n", BLOB_SHA256)), "{}", out);
+    assert!(out.contains("hello.txt\nhello\n"));
+    assert!(!lf(&temp, &["*"]).contains("sha256:"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn manifest_lists_every_matched_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("blob.bin").write_binary(&[0, 1, 2]).unwrap();
+    temp.child("hello.txt").write_str("hello\n").unwrap();
+    let manifest = temp.child("manifest.tsv");
+
+    lf(&temp, &["*", "~manifest.tsv", "--manifest", manifest.path().to_str().unwrap()]);
+    let mut lines: Vec<String> = std::fs::read_to_string(manifest.path()).unwrap().lines().map(String::from).collect();
+    lines.sort();
+    assert_eq!(lines, [format!("blob.bin\t3\t{}", BLOB_SHA256), format!("hello.txt\t6\t{}", HELLO_SHA256)]);
+    temp.close().unwrap();
+}
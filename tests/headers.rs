@@ -0,0 +1,60 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn run(temp: &assert_fs::TempDir, extra: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(["a.rs", "--no-clipboard", "--tokenizer", "approx"]).args(extra);
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn header_meta_fields_and_token_totals() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.rs").write_str("fn a() {}\nfn b() {}\n").unwrap();
+
+    let plain = run(&temp, &[]);
+    let meta = run(&temp, &["--header-meta=lines,tokens"]);
+    assert!(meta.starts_with("a.rs  (2 lines, 5 tokens)\n"));
+    let tokens = |s: &str| s.lines().find(|l| l.starts_with("Tokens")).unwrap().to_string();
+    assert_eq!(tokens(&plain), tokens(&meta));
+
+    let all = run(&temp, &["--header-meta"]);
+    assert!(predicate::str::is_match(r"^a\.rs  \(2 lines, 5 tokens, 20 bytes, modified \d{4}-\d{2}-\d{2}\)\n").unwrap().eval(&all));
+
+    let xml = run(&temp, &["--format", "xml", "--header-meta=lines"]);
+    assert!(xml.starts_with("<file path=\"a.rs\" lines=\"2\">\n"));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn header_and_footer_templates_number_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("alpha\n").unwrap();
+    temp.child("b.txt").write_str("beta\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["a.txt", "b.txt", "--no-clipboard", "--header-template", "== {{{index}/{total}}} {path} ==", "--footer-template", "== end {ext} =="]);
+    let s = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+    assert!(s.contains("== {1/2} ") && s.contains("== {2/2} "));
+    assert!(s.contains(" a.txt ==\nalpha\n== end txt ==\n"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn templates_from_config_and_unknown_placeholders() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("alpha\n").unwrap();
+    temp.child(".lf.toml").write_str("header-template = \"// {path}\"\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["a.txt", "--no-clipboard"]);
+    cmd.assert().success().stdout(predicate::str::starts_with("// a.txt\nalpha\n"));
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["a.txt", "--no-clipboard", "--header-template", "{file}"]);
+    cmd.assert().failure().stderr(predicate::str::contains("unknown placeholder '{file}'"));
+    temp.close().unwrap();
+}
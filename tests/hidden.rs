@@ -66,3 +66,21 @@ fn explicit_hidden_dir_can_be_included() {
 
     temp.close().unwrap();
 }
+
+#[test]
+fn dotenv_glob_finds_hidden_env_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child(".env").write_str("A=1\n").unwrap();
+    temp.child(".env.local").write_str("B=2\n").unwrap();
+    temp.child("app.env").write_str("C=3\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).arg("**/.env*").arg("--no-clipboard");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(".env\nA=1"))
+        .stdout(predicate::str::contains(".env.local\nB=2"))
+        .stdout(predicate::str::contains("app.env").not());
+
+    temp.close().unwrap();
+}
@@ -0,0 +1,39 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+fn html_format_is_a_standalone_browsable_page() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/lib.rs").write_str("pub fn first<T: Clone>(v: &[T]) -> Option<T> { v.first().cloned() }\n").unwrap();
+    temp.child("src/main.rs").write_str("fn main() {}\n").unwrap();
+    temp.child("src/logo.png").write_binary(&[0u8; 64]).unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["src", "--format", "html", "-o", "out.html", "--tokenizer", "approx"]);
+    cmd.assert().success();
+    let html = std::fs::read_to_string(temp.path().join("out.html")).unwrap();
+
+    assert!(html.starts_with("<!DOCTYPE html>\n") && html.ends_with("</body>\n</html>\n"), "{}", html);
+    assert_eq!(html.matches("<details").count(), 3);
+    assert_eq!(html.matches("</details>").count(), 3);
+    assert_eq!(html.matches("<li><a href=\"#file-").count(), 3);
+    assert!(html.contains("<p class=\"totals\">3 files, 2 lines, "));
+    assert!(html.contains("<summary>src/lib.rs (1 lines, "));
+    assert!(html.contains("first&lt;T: Clone&gt;(v: &amp;[T]) -&gt; Option&lt;T&gt;"));
+    assert!(!html.contains("<T: Clone>"));
+    assert!(html.contains("<p>[Image file: 64 bytes]</p>"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn html_format_rejects_tree_and_prefix() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("a\n").unwrap();
+    for extra in ["--tree", "--prefix=hi"] {
+        let mut cmd = Command::cargo_bin("lf").unwrap();
+        cmd.current_dir(&temp).args(["a.txt", "--no-clipboard", "--format", "html", extra]);
+        cmd.assert().code(3).stderr(predicate::str::contains("not supported with --format json, jsonl or html"));
+    }
+}
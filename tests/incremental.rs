@@ -0,0 +1,51 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use filetime::{set_file_mtime, FileTime};
+use predicates::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(["src", "-o", "dump.txt", "--incremental", "--tokenizer", "approx"]).args(args);
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn regenerates_only_when_inputs_change() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/a.rs").write_str("fn a() {}\n").unwrap();
+    temp.child("src/b.rs").write_str("fn b() {}\n").unwrap();
+
+    assert!(!lf(&temp, &[]).contains("up to date"));
+    temp.child("dump.txt.lf-state").assert(predicate::path::exists());
+    assert_eq!(lf(&temp, &[]), "Output up to date (2 files, 6 tokens)\n");
+
+    temp.child("src/b.rs").write_str("fn b() { todo!() }\n").unwrap();
+    set_file_mtime(temp.child("src/b.rs").path(), FileTime::from_unix_time(1_700_000_000, 0)).unwrap();
+    let out = lf(&temp, &[]);
+    assert!(out.starts_with("Files: 2   Size: ") && out.contains("\nLines: 2\n"), "{}", out);
+    temp.child("dump.txt").assert(predicate::str::contains("todo!()"));
+    assert!(lf(&temp, &[]).starts_with("Output up to date (2 files, "));
+
+    assert!(!lf(&temp, &["--line-numbers"]).contains("up to date"));
+    temp.child("src/c.rs").write_str("fn c() {}\n").unwrap();
+    assert!(!lf(&temp, &["--line-numbers"]).contains("up to date"));
+    assert!(lf(&temp, &["--line-numbers"]).starts_with("Output up to date (3 files, "));
+    temp.close().unwrap();
+}
+
+#[test]
+fn output_inside_the_selection_does_not_invalidate() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/a.rs").write_str("fn a() {}\n").unwrap();
+
+    let run = || {
+        let mut cmd = Command::cargo_bin("lf").unwrap();
+        cmd.current_dir(&temp).args(["src", "-o", "src/dump.txt", "--incremental", "--tokenizer", "approx"]);
+        String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+    };
+    run();
+    run();
+    assert!(run().starts_with("Output up to date"));
+    temp.close().unwrap();
+}
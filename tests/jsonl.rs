@@ -0,0 +1,60 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(args).arg("--tokenizer").arg("approx");
+    cmd.assert()
+}
+
+#[test]
+fn every_line_is_json_and_summary_comes_last() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/a.rs").write_str("fn a() {}\nfn b() {}\n").unwrap();
+    temp.child("src/b.rs").write_str("// \"quoted\"\n").unwrap();
+
+    let out = lf(&temp, &["src", "--format", "jsonl", "--no-clipboard"]).success().get_output().stdout.clone();
+    let records: Vec<serde_json::Value> = String::from_utf8(out).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(records.len(), 3);
+    let (summary, files) = records.split_last().unwrap();
+    assert_eq!(summary["type"], "summary");
+    assert_eq!((summary["files"].as_u64(), summary["lines"].as_u64()), (Some(2), Some(3)));
+    let a = files.iter().find(|r| r["path"] == "src/a.rs").unwrap();
+    assert_eq!(a["content"], "fn a() {}\nfn b() {}\n");
+    assert_eq!(a["lines"], 2);
+    assert!(files.iter().all(|r| r.get("type").is_none() && r["tokens"].as_u64().unwrap() > 0));
+    temp.close().unwrap();
+}
+
+#[test]
+fn jsonl_writes_to_a_file_but_not_the_clipboard() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("hello\n").unwrap();
+
+    if cfg!(feature = "clipboard") {
+        lf(&temp, &["a.txt", "--format", "jsonl"]).code(3).stderr(predicate::str::contains("--format jsonl streams to stdout or a file"));
+    }
+    lf(&temp, &["a.txt", "--format", "jsonl", "-o", "out.jsonl"]).success().stdout("");
+    temp.child("out.jsonl").assert("{\"path\":\"a.txt\",\"lines\":1,\"tokens\":2,\"content\":\"hello\\n\"}\n{\"type\":\"summary\",\"files\":1,\"lines\":1,\"tokens\":2}\n");
+    temp.close().unwrap();
+}
+
+#[test]
+fn json_on_stdout_is_parseable_with_the_summary_on_stderr() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("hello\n").unwrap();
+    temp.child("b.txt").write_str("world\n").unwrap();
+
+    let out = lf(&temp, &["*.txt", "--format", "json", "--no-clipboard"]).success()
+        .stderr(predicate::str::contains("Files: 2")).get_output().stdout.clone();
+    let files: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(files.as_array().map(Vec::len), Some(2));
+
+    let out = lf(&temp, &["*.txt", "--format", "json", "--clipboard-limit", "1"]).success()
+        .stderr(predicate::str::contains("Clipboard: skipped, payload over --clipboard-limit")).get_output().stdout.clone();
+    let files: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(files.as_array().map(Vec::len), Some(2));
+    temp.close().unwrap();
+}
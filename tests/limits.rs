@@ -0,0 +1,118 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(["src", "--no-clipboard", "--tokenizer", "approx"]).args(args);
+    cmd.assert()
+}
+
+// Four 400-byte files of 100 approx tokens each, emitted in name order.
+fn fixture() -> assert_fs::TempDir {
+    let temp = assert_fs::TempDir::new().unwrap();
+    for name in ["a", "b", "c", "d"] {
+        temp.child(format!("src/{}.txt", name)).write_str(&format!("{}\n", name.repeat(399))).unwrap();
+    }
+    temp
+}
+
+fn emitted(out: &[u8]) -> Vec<String> {
+    String::from_utf8(out.to_vec()).unwrap().lines().filter(|l| l.starts_with("src/")).map(String::from).collect()
+}
+
+#[test]
+fn max_files_keeps_the_first_files() {
+    let temp = fixture();
+    let out = lf(&temp, &["--max-files", "3"]).success()
+        .stderr(predicate::str::contains("Stopped at --max-files 3: omitted 1 files\n  src/d.txt\n")).get_output().stdout.clone();
+    assert_eq!(emitted(&out), ["src/a.txt", "src/b.txt", "src/c.txt"]);
+    temp.close().unwrap();
+}
+
+#[test]
+fn max_total_bytes_uses_file_sizes() {
+    let temp = fixture();
+    let out = lf(&temp, &["--max-total-bytes", "1000"]).success()
+        .stderr(predicate::str::contains("Stopped at --max-total-bytes 1,000 bytes: omitted 2 files\n  src/c.txt\n  src/d.txt\n")).get_output().stdout.clone();
+    assert_eq!(emitted(&out), ["src/a.txt", "src/b.txt"]);
+    temp.close().unwrap();
+}
+
+#[test]
+fn the_most_restrictive_cap_wins() {
+    let temp = fixture();
+    let out = lf(&temp, &["--max-files", "1", "--max-total-bytes", "1000"]).success()
+        .stderr(predicate::str::contains("Stopped at --max-files 1: omitted 3 files")).get_output().stdout.clone();
+    assert_eq!(emitted(&out), ["src/a.txt"]);
+
+    let out = lf(&temp, &["--max-files", "3", "--max-tokens", "250"]).success()
+        .stderr(predicate::str::contains("Stopped at --max-tokens 250: omitted 2 files\n  src/c.txt\n  src/d.txt\n")).get_output().stdout.clone();
+    assert_eq!(emitted(&out), ["src/a.txt", "src/b.txt"]);
+    temp.close().unwrap();
+}
+
+#[test]
+fn strict_limits_still_write_but_exit_non_zero() {
+    let temp = fixture();
+    lf(&temp, &["--max-files", "2", "--strict-limits", "-o", "out.txt"]).code(7).stderr(predicate::str::contains("2 files omitted by --max-files 2"));
+    temp.child("out.txt").assert(predicate::str::contains("src/b.txt").and(predicate::str::contains("src/c.txt").not()));
+    lf(&temp, &["--max-files", "4", "--strict-limits"]).success();
+    temp.close().unwrap();
+}
+
+#[test]
+fn capping_every_file_still_lists_the_omissions() {
+    let temp = fixture();
+    lf(&temp, &["--max-files", "0", "--strict-limits"]).code(7)
+        .stderr(predicate::str::contains("Stopped at --max-files 0: omitted 4 files\n  src/a.txt\n").and(predicate::str::contains("4 files omitted by --max-files 0")));
+    lf(&temp, &["--max-total-bytes", "100"]).code(2)
+        .stderr(predicate::str::contains("Stopped at --max-total-bytes 100 bytes: omitted 4 files\n"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn per_file_token_thresholds_warn_and_skip() {
+    let temp = fixture();
+    temp.child("src/e.txt").write_str("short\n").unwrap();
+    lf(&temp, &["--warn-tokens-per-file", "50", "--skip-tokens-per-file", "99"]).success()
+        .stderr(predicate::str::contains("Warning: 4 files exceed 50 tokens each:\n  src/a.txt (100 tokens)\n"))
+        .stderr(predicate::str::contains("Skipped 4 files over the per-file token limit (400 tokens)"))
+        .stdout(predicate::str::contains("[Skipped: 100 tokens, exceeds per-file limit]").and(predicate::str::contains("short")));
+    lf(&temp, &["--warn-tokens-per-file", "100"]).success().stderr(predicate::str::contains("exceed").not());
+}
+
+#[test]
+fn max_tokens_per_dir_caps_each_directory_on_its_own() {
+    let temp = fixture();
+    temp.child("docs/guide.md").write_str(&format!("{}\n", "g".repeat(399))).unwrap();
+    let headers = |out: &[u8]| -> Vec<String> { String::from_utf8(out.to_vec()).unwrap().lines().filter(|l| l.starts_with("src/") || l.starts_with("docs/")).map(String::from).collect() };
+
+    let out = lf(&temp, &["docs", "--max-tokens-per-dir", "250"]).success()
+        .stderr(predicate::str::contains("Stopped src/ at --max-tokens-per-dir 250: omitted 2 files\n  src/c.txt\n  src/d.txt\n"))
+        .stderr(predicate::str::contains("docs/ at").not()).get_output().stdout.clone();
+    assert_eq!(headers(&out), ["docs/guide.md", "src/a.txt", "src/b.txt"]);
+
+    let out = lf(&temp, &["docs", "--max-tokens-per-dir", "250", "--max-tokens", "250"]).success()
+        .stderr(predicate::str::contains("Stopped at --max-tokens 250: omitted 1 files\n  src/b.txt\n")).get_output().stdout.clone();
+    assert_eq!(headers(&out), ["docs/guide.md", "src/a.txt"]);
+
+    lf(&temp, &["docs", "--max-tokens-per-dir", "250", "--strict-limits"]).code(7).stderr(predicate::str::contains("2 files omitted by --max-tokens-per-dir 250"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn max_tokens_per_dir_keeps_the_pattern_order() {
+    let temp = fixture();
+    temp.child("docs/guide.md").write_str(&format!("{}\n", "g".repeat(399))).unwrap();
+    let headers = |cap: &str| -> Vec<String> {
+        let mut cmd = Command::cargo_bin("lf").unwrap();
+        cmd.current_dir(&temp).args(["--pattern-order", "src/b.txt", "docs/", "src/a.txt", "--no-clipboard", "--tokenizer", "approx", "--max-tokens-per-dir", cap]);
+        let out = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+        out.lines().filter(|l| l.starts_with("src/") || l.starts_with("docs/")).map(String::from).collect()
+    };
+    assert_eq!(headers("1000"), ["src/b.txt", "docs/guide.md", "src/a.txt"]);
+    assert_eq!(headers("150"), ["docs/guide.md", "src/a.txt"]);
+    temp.close().unwrap();
+}
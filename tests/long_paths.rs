@@ -0,0 +1,19 @@
+#![cfg(windows)]
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+fn files_past_max_path_are_read() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let deep: String = (0..12).map(|i| format!("node_modules_{:02}_{}/", i, "x".repeat(12))).collect();
+    let rel = format!("{}index.js", deep);
+    assert!(temp.path().join(&rel).to_string_lossy().len() > 260);
+    std::fs::create_dir_all(std::path::PathBuf::from(format!(r"\\?\{}", temp.path().join(&deep).display()))).unwrap();
+    std::fs::write(format!(r"\\?\{}", temp.path().join(&rel).display()), "module.exports = 1;\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["**/*.js", "--no-clipboard", "--tokenizer", "approx"]);
+    cmd.assert().success().stdout(predicate::str::contains(format!("{}\nmodule.exports = 1;\n", rel)));
+}
@@ -0,0 +1,37 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+fn toc_anchors_match_headings() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a/mod.rs").write_str("pub mod x;\n").unwrap();
+    temp.child("b/mod.rs").write_str("pub mod y;\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["**/*.rs", "--no-clipboard", "--format", "markdown", "--toc"]);
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let s = String::from_utf8(out).unwrap();
+
+    let anchors: Vec<&str> = s.lines().filter_map(|l| l.strip_prefix("- [")).map(|l| &l[l.find("](#").unwrap() + 3..l.len() - 1]).collect();
+    let headings: Vec<&str> = s.lines().filter_map(|l| l.strip_prefix("### ")).collect();
+    assert_eq!(anchors.len(), 2);
+    assert_ne!(anchors[0], anchors[1]);
+    let mut slugger = lf::format::Slugger::default();
+    let expected: Vec<String> = headings.iter().map(|h| slugger.slug(h)).collect();
+    assert_eq!(anchors, expected);
+    assert!(s.contains("```rust\npub mod x;\n"));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn toc_requires_markdown() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("x\n").unwrap();
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["a.txt", "--no-clipboard", "--toc"]);
+    cmd.assert().failure().stderr(predicate::str::contains("--format markdown"));
+    temp.close().unwrap();
+}
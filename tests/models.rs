@@ -0,0 +1,35 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn fixture() -> assert_fs::TempDir {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str(&"abcd".repeat(1000)).unwrap();
+    temp
+}
+
+#[test]
+fn under_limit_is_silent() {
+    let temp = fixture();
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["a.txt", "--no-clipboard", "--tokenizer", "approx", "--target-context", "5000", "--fit"]);
+    cmd.assert().success().stderr(predicate::str::contains("WARNING").not());
+    temp.close().unwrap();
+}
+
+#[test]
+fn over_limit_warns_and_fit_fails() {
+    let temp = fixture();
+    temp.child(".lf.toml").write_str("[models]\ntiny = 500\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["a.txt", "--no-clipboard", "--tokenizer", "approx", "--target-model", "tiny"]);
+    cmd.assert().success().stderr(predicate::str::contains("WARNING: 1k tokens exceeds tiny's 500 context by 501"));
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["a.txt", "--no-clipboard", "--tokenizer", "approx", "--target-model", "tiny", "--fit"]);
+    cmd.assert().code(1).stderr(predicate::str::contains("WARNING"));
+
+    temp.close().unwrap();
+}
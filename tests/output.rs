@@ -0,0 +1,182 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+fn append_adds_to_existing_output_in_order() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("core.txt").write_str("first payload\n").unwrap();
+    temp.child("design.md").write_str("second payload\n").unwrap();
+    let out = temp.child("prompt.txt");
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).arg("core.txt").arg("-o").arg(out.path());
+    cmd.assert().success().stdout(predicate::str::contains("Lines: 1"));
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).arg("design.md").arg("-o").arg(out.path()).arg("--append");
+    cmd.assert().success().stdout(predicate::str::contains("Lines: 1"));
+
+    let s = std::fs::read_to_string(out.path()).unwrap();
+    let first = s.find("first payload").unwrap();
+    let second = s.find("second payload").unwrap();
+    assert!(first < second);
+    assert!(s[first..second].contains("========"));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn append_creates_missing_output() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("hello\n").unwrap();
+    let out = temp.child("new.txt");
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).arg("a.txt").arg("-o").arg(out.path()).arg("--append");
+    cmd.assert().success();
+
+    out.assert(predicate::str::contains("hello").and(predicate::str::contains("========").not()));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn failed_run_leaves_existing_output_untouched() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("ok.txt").write_str("fine\n").unwrap();
+    temp.child("broken.txt").write_binary(&[0x66, 0xff, 0xfe, 0x0a]).unwrap();
+    let out = temp.child("dump.txt");
+    out.write_str("previous dump\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).arg("*.txt").arg("~dump.txt").arg("-o").arg(out.path());
+    cmd.assert().failure();
+
+    out.assert("previous dump\n");
+    let leftovers: Vec<_> = std::fs::read_dir(temp.path()).unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().contains(".tmp-"))
+        .collect();
+    assert!(leftovers.is_empty());
+
+    temp.close().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn rewritten_output_keeps_its_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/a.txt").write_str("hello\n").unwrap();
+    let out = temp.child("out.txt");
+    out.write_str("old\n").unwrap();
+    std::fs::set_permissions(out.path(), std::fs::Permissions::from_mode(0o640)).unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["src/*", "-o", "out.txt"]);
+    cmd.assert().success();
+
+    out.assert(predicate::str::contains("hello"));
+    assert_eq!(std::fs::metadata(out.path()).unwrap().permissions().mode() & 0o777, 0o640);
+    temp.close().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn output_through_a_symlink_rewrites_the_linked_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/a.txt").write_str("hello\n").unwrap();
+    temp.child("real.txt").write_str("old\n").unwrap();
+    std::os::unix::fs::symlink("real.txt", temp.path().join("link.txt")).unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["src/*", "-o", "link.txt"]);
+    cmd.assert().success();
+
+    assert!(std::fs::symlink_metadata(temp.path().join("link.txt")).unwrap().file_type().is_symlink());
+    temp.child("real.txt").assert(predicate::str::contains("hello"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn count_only_prints_totals_without_content() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/a.rs").write_str("fn a() {}\nfn b() {}\n").unwrap();
+    temp.child("src/b.rs").write_str("fn c() {}\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).arg("src/").arg("--count-only").arg("--tokenizer").arg("approx");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("Files: 2  Lines: 3  Tokens: "))
+        .stdout(predicate::str::contains("fn a").not())
+        .stdout(predicate::str::contains("src/a.rs").not());
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn plain_output_is_byte_identical() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/a.rs").write_str("fn a() {}\n").unwrap();
+    temp.child("src/b.txt").write_str("no trailing newline").unwrap();
+    temp.child("src/c/logo.png").write_binary(&[0u8; 16]).unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["src", "-o", "out.txt"]);
+    cmd.assert().success().stdout(predicate::str::starts_with("Files: 3   Size: 92 bytes\nLines: 2\n"));
+    out_is(&temp, "src/a.rs\nfn a() {}\n\n\nsrc/b.txt\nno trailing newline\n\n\nsrc/c/logo.png\n[Image file: 16 bytes]\n\n");
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["**/*.rs", "-o", "out.txt", "--header-template", "== {path} ({index}/{total}) ==", "--footer-template=-- {lines} lines --"]);
+    cmd.assert().success();
+    out_is(&temp, "== src/a.rs (1/1) ==\nfn a() {}\n-- 1 lines --\n\n\n");
+    temp.close().unwrap();
+}
+
+fn out_is(temp: &assert_fs::TempDir, expected: &str) {
+    assert_eq!(std::fs::read_to_string(temp.path().join("out.txt")).unwrap(), expected);
+}
+
+#[test]
+fn pattern_order_follows_the_given_patterns() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("alpha.txt").write_str("a\n").unwrap();
+    temp.child("docs/guide.md").write_str("g\n").unwrap();
+    temp.child("docs/api.md").write_str("i\n").unwrap();
+    temp.child("zeta.txt").write_str("z\n").unwrap();
+
+    let headers = |extra: &[&str]| {
+        let mut cmd = Command::cargo_bin("lf").unwrap();
+        cmd.current_dir(&temp).args(["zeta.txt", "docs/", "alpha.txt", "--no-clipboard", "--tokenizer", "approx"]).args(extra);
+        let out = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+        out.lines().filter(|l| l.ends_with(".txt") || l.ends_with(".md")).map(String::from).collect::<Vec<_>>()
+    };
+    assert_eq!(headers(&["--pattern-order"]), ["zeta.txt", "docs/api.md", "docs/guide.md", "alpha.txt"]);
+    assert_ne!(headers(&[]), headers(&["--pattern-order"]));
+    temp.close().unwrap();
+}
+
+#[test]
+fn number_files_assigns_contiguous_stable_ids() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    for name in ["b.rs", "a.rs", "src/c.rs", "src/d/e.rs", "z.md"] { temp.child(name).write_str("x\n").unwrap(); }
+    let run = |format: &str| {
+        let mut cmd = Command::cargo_bin("lf").unwrap();
+        cmd.current_dir(&temp).args(["**/*", "--no-clipboard", "--number-files", "--format", format]);
+        String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+    };
+    let first = run("plain");
+    assert_eq!(first, run("plain"));
+    let index: Vec<&str> = first.lines().take_while(|l| !l.is_empty()).collect();
+    assert_eq!(index.len(), 5);
+    for (i, line) in index.iter().enumerate() {
+        let (id, path) = line.split_once(' ').unwrap();
+        assert_eq!(id, format!("[#{}]", i + 1));
+        assert!(first.contains(&format!("\n\n{} {}\nx\n", id, path)), "{}", first);
+    }
+    assert!(run("markdown").contains("### [#5] "));
+    temp.close().unwrap();
+}
@@ -0,0 +1,202 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn first_line(dir: &std::path::Path, style: &str) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(dir).args(["src/**", "--no-clipboard", "--tokenizer", "approx", "--path-style", style]);
+    let out = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+    out.lines().next().unwrap().to_string()
+}
+
+#[test]
+fn path_styles_from_a_subdirectory() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child(".git").create_dir_all().unwrap();
+    temp.child("core/src/lib.rs").write_str("pub fn f() {}\n").unwrap();
+    let core = temp.child("core");
+
+    assert_eq!(first_line(core.path(), "relative"), "src/lib.rs");
+    assert_eq!(first_line(core.path(), "repo-root"), "core/src/lib.rs");
+    let abs = first_line(core.path(), "absolute");
+    assert!(abs.ends_with("/core/src/lib.rs"), "{}", abs);
+    assert!(!abs.contains('\\'));
+    assert!(std::path::Path::new(&abs).is_absolute() || cfg!(windows));
+    temp.close().unwrap();
+}
+
+#[test]
+fn nfc_patterns_match_nfd_names() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("docs/cafe\u{301}.md").write_str("menu\n").unwrap();
+
+    for pattern in ["docs/caf\u{e9}.md", "caf\u{e9}.md", "docs/cafe\u{301}.md"] {
+        let mut cmd = Command::cargo_bin("lf").unwrap();
+        cmd.current_dir(&temp).args([pattern, "--no-clipboard", "--tokenizer", "approx"]);
+        let out = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+        assert!(out.contains("menu\n"), "{}: {}", pattern, out);
+    }
+    temp.close().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_names_are_shown_with_escapes() {
+    use std::os::unix::ffi::OsStrExt;
+    let temp = assert_fs::TempDir::new().unwrap();
+    let name = std::ffi::OsStr::from_bytes(b"bad\xff\xfename.txt");
+    if std::fs::write(temp.path().join(name), "payload\n").is_err() { return; } // filesystems that insist on UTF-8 names
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["*.txt", "--no-clipboard", "--tokenizer", "approx"]);
+    let out = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+    assert!(out.contains("bad\\xff\\xfename.txt\npayload\n"), "{}", out);
+    temp.close().unwrap();
+}
+
+mod backslash_patterns {
+    use lf::patterns::{PatternOptions, PatternSet};
+    use std::path::Path;
+
+    fn included(patterns: &[&str], path: &str) -> bool {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        PatternSet::compile(&patterns, &PatternOptions::default()).unwrap().matches(Path::new(path)).is_match()
+    }
+
+    #[test]
+    fn backslash_globs_match_like_forward_slashes() {
+        assert!(included(&[r"src\**\*.rs"], "src/app/mod.rs"));
+        assert!(included(&[r"src\**\*.rs"], r"src\app\mod.rs"));
+        assert!(!included(&[r"src\**\*.rs"], "tests/app.rs"));
+    }
+
+    #[test]
+    fn trailing_backslash_means_directory() {
+        assert!(included(&[r"src\app\"], "src/app/main.rs"));
+        assert!(!included(&[r"src\app\"], "src/lib.rs"));
+    }
+
+    #[test]
+    fn backslash_excludes() {
+        assert!(!included(&["**/*.rs", r"~target\"], "target/debug/build.rs"));
+        assert!(!included(&["**/*.rs", r"~target\**"], r"target\debug\build.rs"));
+        assert!(included(&["**/*.rs", r"~target\"], "src/main.rs"));
+    }
+
+    #[test]
+    fn unc_prefixes_round_trip() {
+        assert!(included(&[r"\\server\share\src\*.rs"], r"\\server\share\src\main.rs"));
+        assert!(included(&[r"\\?\C:\repo\*.rs"], r"\\?\C:\repo\main.rs"));
+    }
+}
+
+#[test]
+fn overlapping_includes_emit_a_file_once() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("docs/Guide.md").write_str("# Guide\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["docs/Guide.md", "Guide.md", "**/*.md", "docs", "-C", ".", "-C", "docs", "--no-clipboard", "--tokenizer", "approx"]);
+    let out = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+    assert_eq!(out.matches("# Guide\n").count(), 1, "{}", out);
+    temp.close().unwrap();
+}
+
+#[test]
+fn case_collisions_are_reported() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("docs/README.md").write_str("upper\n").unwrap();
+    temp.child("docs/Readme.md").write_str("mixed\n").unwrap();
+    let on_disk = std::fs::read_dir(temp.child("docs").path()).unwrap().count();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["docs", "--no-clipboard", "--tokenizer", "approx"]);
+    let out = cmd.assert().success().get_output().clone();
+    let (stdout, stderr) = (String::from_utf8(out.stdout).unwrap(), String::from_utf8(out.stderr).unwrap());
+    if on_disk == 2 {
+        assert!(stderr.contains("Warning: paths differ only in case and would collide on a case-insensitive filesystem: docs/README.md, docs/Readme.md\n"), "{}", stderr);
+        assert!(stdout.contains("upper\n") && stdout.contains("mixed\n"));
+    } else {
+        assert_eq!(stdout.matches("mixed\n").count(), 1);
+    }
+    temp.close().unwrap();
+}
+
+mod pattern_set {
+    use lf::patterns::{MatchResult, PatternOptions, PatternSet, RuleOrigin};
+    use std::path::Path;
+
+    #[test]
+    fn reports_the_rule_that_fired() {
+        let temp = assert_fs::TempDir::new().unwrap();
+        std::fs::write(temp.path().join(".gitignore"), "*.log\n").unwrap();
+        let patterns = vec!["**/*".to_string(), "docs".to_string()];
+        let set = PatternSet::compile(&patterns, &PatternOptions { gitignore: true, root: temp.path().to_path_buf() }).unwrap();
+
+        match set.matches(Path::new("docs/intro.md")) {
+            MatchResult::Matched { rule, hidden: false } => assert_eq!(rule.pattern, "**/*"),
+            other => panic!("{:?}", other),
+        }
+        match set.matches(Path::new("logs/run.log")) {
+            MatchResult::Excluded { rule, include, .. } => {
+                assert_eq!(rule.origin, RuleOrigin::IgnoreFile(temp.path().join(".gitignore")));
+                assert_eq!(include.pattern, "**/*");
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(set.matches(Path::new(".cache/x")), MatchResult::HiddenSkipped);
+        assert!(lf::patterns::path_matches(Path::new("docs/intro.md"), &set.include, &set.hidden_include, &set.exclude));
+        temp.close().unwrap();
+    }
+}
+
+#[test]
+fn absolute_and_home_patterns_are_taken_as_named() {
+    let work = assert_fs::TempDir::new().unwrap();
+    work.child("local.txt").write_str("local body\n").unwrap();
+    let elsewhere = assert_fs::TempDir::new().unwrap();
+    elsewhere.child("nginx.conf").write_str("worker_processes 4;\n").unwrap();
+    elsewhere.child("conf.d/site.conf").write_str("server {}\n").unwrap();
+    elsewhere.child("conf.d/site.log").write_str("noise\n").unwrap();
+    let home = assert_fs::TempDir::new().unwrap();
+    home.child("notes/todo.md").write_str("- buy milk\n").unwrap();
+
+    let conf = elsewhere.child("nginx.conf");
+    let dir = elsewhere.child("conf.d");
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&work).env("HOME", home.path())
+        .args([conf.path().to_str().unwrap(), dir.path().to_str().unwrap(), "~/notes/todo.md", "~*.log", "--no-clipboard", "--tokenizer", "approx"]);
+    let out = String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap();
+    assert!(out.contains(&format!("{}\nworker_processes 4;\n", conf.path().display())), "{}", out);
+    assert!(out.contains("site.conf\nserver {}\n") && !out.contains("noise"), "{}", out);
+    assert!(out.contains("todo.md\n- buy milk\n") && !out.contains("local body"), "{}", out);
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&work).args(["local.txt", elsewhere.child("missing.txt").path().to_str().unwrap(), "--no-clipboard"]);
+    cmd.assert().code(5);
+}
+
+#[test]
+fn dead_patterns_warn_or_fail_with_strict_patterns() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/main.rs").write_str("fn main() {}\n").unwrap();
+    temp.child("README.md").write_str("# hi\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["src/**", "scr/**", "**/*.md", "~*.log", "--no-clipboard"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("src/main.rs"))
+        .stderr(predicate::str::contains("Warning: pattern 'scr/**' matched no files"))
+        .stderr(predicate::str::contains("*.md").not())
+        .stderr(predicate::str::contains("*.log").not());
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["src/**", "scr/**", "--no-clipboard", "--strict-patterns"]);
+    cmd.assert()
+        .code(2)
+        .stdout(predicate::str::contains("main.rs").not())
+        .stderr(predicate::str::contains("pattern 'scr/**' matched no files"));
+    temp.close().unwrap();
+}
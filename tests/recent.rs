@@ -0,0 +1,23 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use filetime::{set_file_mtime, FileTime};
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+fn recent_keeps_newest_files_in_order() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let base = 1_700_000_000;
+    for (name, offset) in [("old.rs", 0), ("newest.rs", 300), ("middle.rs", 200), ("tie_b.rs", 100), ("tie_a.rs", 100), ("notes.md", 400)] {
+        temp.child(name).write_str(&format!("// {}\n", name)).unwrap();
+        set_file_mtime(temp.child(name).path(), FileTime::from_unix_time(base + offset, 0)).unwrap();
+    }
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["*.rs", "--recent", "4", "--no-clipboard", "--tokenizer", "approx"]);
+    let out = cmd.assert().success().stderr(predicate::str::contains("Keeping the 4 most recently modified files (modified at or after 2023-11-14 22:15:00 UTC)"));
+    let stdout = String::from_utf8(out.get_output().stdout.clone()).unwrap();
+    let headers: Vec<&str> = stdout.lines().filter(|l| l.ends_with(".rs") && !l.starts_with("//")).collect();
+    assert_eq!(headers, ["newest.rs", "middle.rs", "tie_a.rs", "tie_b.rs"]);
+    temp.close().unwrap();
+}
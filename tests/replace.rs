@@ -0,0 +1,48 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(args).arg("--no-clipboard").arg("--tokenizer").arg("approx");
+    cmd.assert()
+}
+
+fn stdout(a: assert_cmd::assert::Assert) -> String {
+    String::from_utf8(a.success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn replacements_chain_in_order() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("app.conf").write_str("url = https://internal.corp.example/api\n").unwrap();
+
+    let out = stdout(lf(&temp, &["*.conf", "--replace", r"internal\.corp\.example", "REDACTED_HOST", "--replace", "REDACTED", "X"]));
+    assert!(out.contains("url = https://X_HOST/api\n"), "{}", out);
+    let out = stdout(lf(&temp, &["*.conf", "--replace", "REDACTED", "X", "--replace", r"internal\.corp\.example", "REDACTED_HOST"]));
+    assert!(out.contains("url = https://REDACTED_HOST/api\n"), "{}", out);
+    temp.close().unwrap();
+}
+
+#[test]
+fn capture_groups_and_profiles() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("users.txt").write_str("bob@acme.com\n").unwrap();
+    temp.child("lf.toml").write_str("[profiles.share]\nreplace = [['(\\w+)@(\\w+)\\.com', '$2:$1']]\n").unwrap();
+
+    let out = stdout(lf(&temp, &["users.txt", "--replace", r"(\w+)@(\w+)\.com", "<$1 at $2>"]));
+    assert!(out.contains("users.txt\n<bob at acme>\n"), "{}", out);
+    let out = stdout(lf(&temp, &["users.txt", "--config", "lf.toml", "--profile", "share", "--replace", "acme", "ACME"]));
+    assert!(out.contains("users.txt\nACME:bob\n"), "{}", out);
+    lf(&temp, &["users.txt", "--config", "lf.toml", "--profile", "nope"]).code(3).stderr(predicate::str::contains("unknown profile 'nope'"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn invalid_regex_fails_before_reading_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("a\n").unwrap();
+    lf(&temp, &["a.txt", "--replace", "(unclosed", "x"]).code(3).stderr(predicate::str::contains("invalid --replace pattern '(unclosed'")).stdout(predicate::str::contains("a.txt").not());
+    temp.close().unwrap();
+}
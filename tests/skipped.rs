@@ -0,0 +1,35 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(args).args(["--no-clipboard", "--tokenizer", "approx"]);
+    String::from_utf8(cmd.assert().success().get_output().stderr.clone()).unwrap()
+}
+
+#[test]
+fn show_skipped_reports_every_category() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child(".git").create_dir_all().unwrap();
+    temp.child(".gitignore").write_str("build/\n").unwrap();
+    temp.child("src/a.rs").write_str("fn a() {}\n").unwrap();
+    temp.child("src/a_test.rs").write_str("fn t() {}\n").unwrap();
+    temp.child("build/out.rs").write_str("fn o() {}\n").unwrap();
+    temp.child(".env").write_str("SECRET=1\n").unwrap();
+    temp.child("logo.png").write_binary(&[0u8; 16]).unwrap();
+    temp.child("big.txt").write_str(&"word ".repeat(100)).unwrap();
+    for i in 0..12 { temp.child(format!("gen/g{:02}.rs", i)).write_str("x\n").unwrap(); }
+
+    let err = lf(&temp, &["**/*", "~**/*_test.rs", "~gen/", "--skip-binary", "--skip-tokens-per-file", "50", "--show-skipped"]);
+    assert!(err.contains("Excluded by gitignore: 1 files\n  build/out.rs\n"), "{}", err);
+    assert!(err.contains("Excluded by ~ patterns: 13 files\n  gen/g00.rs (~gen/)\n"), "{}", err);
+    assert!(err.contains("  gen/g09.rs (~gen/)\n  … and 3 more\n"), "{}", err);
+    assert!(err.contains("Hidden and not explicitly included: 2 files\n  .env\n  .gitignore\n"), "{}", err);
+    assert!(err.contains("Too large (over a size or token limit): 1 files\n  big.txt\n"), "{}", err);
+    assert!(err.contains("Binary (--skip-binary): 1 files\n  logo.png\n"), "{}", err);
+    assert!(!err.contains(".git/") && !err.contains("src/a.rs"));
+
+    assert!(!lf(&temp, &["**/*", "--skip-binary"]).contains("Excluded by"));
+    temp.close().unwrap();
+}
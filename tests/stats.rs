@@ -0,0 +1,90 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+fn stats_by_ext_lists_each_extension() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/main.rs").write_str("fn main() {\n    println!(\"hi\");\n}\n").unwrap();
+    temp.child("src/lib.rs").write_str("pub fn f() {}\n").unwrap();
+    temp.child("README.md").write_str("# Title\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["**/*", "--no-clipboard", "--tokenizer", "approx", "--stats-by-ext"]);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Extension"))
+        .stderr(predicate::str::is_match(r"(?m)^\.rs\s+2\s+4\s").unwrap())
+        .stderr(predicate::str::is_match(r"(?m)^\.md\s+1\s+1\s").unwrap());
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn lang_stats_groups_a_polyglot_tree() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/main.rs").write_str("fn main() {\n\n    run();\n}\n").unwrap();
+    temp.child("src/lib.rs").write_str("pub fn run() {}\n").unwrap();
+    temp.child("tools/gen.py").write_str("import os\n\n\nprint(os.name)\n").unwrap();
+    temp.child("web/app.ts").write_str("export const x = 1;\n").unwrap();
+    temp.child("README.md").write_str("# Title\n\nText\n").unwrap();
+    temp.child("build.zig").write_str("const std = 1;\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["**/*", "--no-clipboard", "--tokenizer", "approx", "--lang-stats"]);
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Language"))
+        .stderr(predicate::str::is_match(r"(?m)^Rust\s+2\s+5\s+1\s").unwrap())
+        .stderr(predicate::str::is_match(r"(?m)^Python\s+1\s+4\s+2\s").unwrap())
+        .stderr(predicate::str::is_match(r"(?m)^Other\s+1\s+1\s+0\s").unwrap())
+        .stderr(predicate::str::is_match(r"(?m)^TypeScript\s+1\s").unwrap());
+
+    temp.close().unwrap();
+}
+
+fn json_fixture() -> assert_fs::TempDir {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/main.rs").write_str("fn main() {\n    println!(\"hi\");\n}\n").unwrap();
+    temp.child("notes.md").write_str("# Notes\n").unwrap();
+    temp.child("logo.png").write_binary(&[0x89, 0x50, 0x4e, 0x47]).unwrap();
+    temp
+}
+
+fn summary_number(stdout: &str, prefix: &str) -> u64 {
+    let line = stdout.lines().find(|l| l.starts_with(prefix)).unwrap();
+    line.rsplit(' ').next().unwrap().parse().unwrap()
+}
+
+#[test]
+fn stats_json_matches_human_summary() {
+    let temp = json_fixture();
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["**/*", "--no-clipboard", "--tokenizer", "approx", "--stats-json"]);
+    let out = cmd.assert().success().get_output().clone();
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    let v: serde_json::Value = serde_json::from_slice(&out.stderr).unwrap();
+    assert_eq!(v["files"], 3);
+    assert_eq!(v["lines"].as_u64().unwrap(), summary_number(&stdout, "Lines:"));
+    assert_eq!(v["tokens"].as_u64().unwrap(), summary_number(&stdout, "Tokens (approx):"));
+    assert_eq!(v["tokenizer"], "approx");
+    let entries = v["entries"].as_array().unwrap();
+    let bytes: u64 = entries.iter().map(|e| e["bytes"].as_u64().unwrap()).sum();
+    assert_eq!(v["bytes"].as_u64().unwrap(), bytes);
+    let png = entries.iter().find(|e| e["path"] == "logo.png").unwrap();
+    assert_eq!(png["binary"], true);
+    assert!(v["timing"]["total_ms"].is_u64());
+    temp.close().unwrap();
+}
+
+#[test]
+fn stats_json_can_go_to_a_file() {
+    let temp = json_fixture();
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["src/", "--no-clipboard", "--tokenizer", "approx", "--stats-json=stats.json"]);
+    cmd.assert().success().stderr(predicate::str::is_empty());
+    let v: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(temp.child("stats.json").path()).unwrap()).unwrap();
+    assert_eq!(v["entries"][0]["path"], "src/main.rs");
+    temp.close().unwrap();
+}
@@ -0,0 +1,34 @@
+use assert_cmd::Command;
+use assert_fs::prelude::*;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str], stdin: &str) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(args).args(["--no-clipboard", "--tokenizer", "approx"]).write_stdin(stdin);
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn stdin_is_a_file_among_the_matched_ones() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("notes.txt").write_str("on disk\n").unwrap();
+
+    let out = lf(&temp, &["-", "*.txt", "-"], "piped one\npiped two");
+    assert!(out.starts_with("<stdin>\npiped one\npiped two\n\n\nnotes.txt\non disk\n"), "{}", out);
+    assert_eq!(out.matches("piped one").count(), 1);
+    assert!(out.contains("Files: 2 ") && out.contains("\nLines: 3\n"), "{}", out);
+}
+
+#[test]
+fn stdin_name_sets_the_header_fence_and_counts_like_the_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let source = "fn main() {\n    println!(\"hi\");\n}\n";
+    temp.child("src/app.rs").write_str(source).unwrap();
+
+    let piped = lf(&temp, &["-", "--stdin-name", "src/app.rs", "--format", "markdown"], source);
+    assert!(piped.starts_with("### src/app.rs\n\n```rust\nfn main() {\n"), "{}", piped);
+    let on_disk = lf(&temp, &["src/app.rs", "--format", "markdown"], "");
+    assert_eq!(piped, on_disk);
+
+    let numbered = lf(&temp, &["-", "--line-numbers"], source);
+    assert!(numbered.contains("<stdin>\n1 | fn main() {\n"), "{}", numbered);
+}
@@ -0,0 +1,31 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use std::process::Command;
+
+fn ms_values(line: &str) -> Vec<f64> {
+    line.split(" ms").filter_map(|part| part.split_whitespace().last()?.parse().ok()).collect()
+}
+
+#[test]
+fn timings_report_phases_and_the_slowest_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    for i in 0..12 { temp.child(format!("src/f{:02}.rs", i)).write_str(&"fn f() {}\n".repeat(i + 1)).unwrap(); }
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["src", "--no-clipboard", "--tokenizer", "approx", "--timings", "--stats-json=stats.json"]);
+    let stderr = String::from_utf8(cmd.assert().success().get_output().stderr.clone()).unwrap();
+
+    let (phases, files) = stderr.split_once("Slowest files:\n").unwrap();
+    for phase in ["walk", "read", "transform", "tokenize", "wall"] {
+        let line = phases.lines().find(|l| l.trim_start().starts_with(phase)).unwrap_or_else(|| panic!("{}: {}", phase, stderr));
+        assert!(ms_values(line).iter().all(|&v| v >= 0.0) && ms_values(line).len() == 1, "{}", line);
+    }
+    let rows: Vec<&str> = files.lines().skip(1).take_while(|l| l.contains(" ms  src/")).collect();
+    assert_eq!(rows.len(), 10, "{}", stderr);
+    for row in rows { assert_eq!(ms_values(row).len(), 4, "{}", row); }
+
+    let stats: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(temp.path().join("stats.json")).unwrap()).unwrap();
+    assert!(stats["timing"]["phases"]["tokenize_us"].is_u64());
+    assert!(stats["entries"][0]["metrics"]["read_us"].is_u64());
+    temp.close().unwrap();
+}
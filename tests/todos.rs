@@ -0,0 +1,29 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> String {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(args).arg("--no-clipboard").arg("--tokenizer").arg("approx");
+    String::from_utf8(cmd.assert().success().get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn todos_report_path_line_findings() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/a.rs").write_str("fn a() {}\n// TODO: handle errors\nlet x = 1; // HACK\n").unwrap();
+    temp.child("src/b.py").write_str("# FIXME flaky\n\n# see https://todo.example\n").unwrap();
+    temp.child("src/clean.rs").write_str("fn clean() {}\n").unwrap();
+    temp.child("src/logo.png").write_binary(b"TODO").unwrap();
+
+    let out = lf(&temp, &["src", "--todos"]);
+    assert!(out.contains("src/a.rs:2: // TODO: handle errors\nsrc/a.rs:3: let x = 1; // HACK\n"), "{}", out);
+    assert!(out.contains("src/b.py:1: # FIXME flaky\n"));
+    assert!(!out.contains("clean") && !out.contains("logo.png") && !out.contains("fn a()"));
+    assert!(out.contains("Findings: 3\n"), "{}", out);
+
+    let out = lf(&temp, &["src", "--todos", "--todo-tags", "todo,FIXME"]);
+    assert!(out.contains("src/b.py:3: # see https://todo.example\n"), "{}", out);
+    assert!(out.contains("Findings: 2\n"));
+    temp.close().unwrap();
+}
@@ -0,0 +1,30 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+fn tokenizer_flag_changes_stats_label() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("abcdefgh\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).arg("a.txt").arg("--no-clipboard").arg("--tokenizer").arg("approx");
+    cmd.assert().success().stdout(predicate::str::contains("Tokens (approx):"));
+
+    #[cfg(feature = "token-counting")]
+    {
+        let mut cmd = Command::cargo_bin("lf").unwrap();
+        cmd.current_dir(&temp).arg("a.txt").arg("--no-clipboard").arg("--tokenizer").arg("cl100k");
+        cmd.assert().success().stdout(predicate::str::contains("Tokens (cl100k_base):"));
+    }
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn unknown_tokenizer_lists_available_ones() {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.arg(".").arg("--tokenizer").arg("gpt2");
+    cmd.assert().failure().stderr(predicate::str::contains("o200k").and(predicate::str::contains("cl100k")).and(predicate::str::contains("approx")));
+}
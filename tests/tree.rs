@@ -0,0 +1,47 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn fixture() -> assert_fs::TempDir {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/app/run.rs").write_str("fn run() {}\n").unwrap();
+    temp.child("src/main.rs").write_str("fn main() {}\n").unwrap();
+    temp.child("docs/guide.md").write_str("# Guide\n").unwrap();
+    temp.child("other/skip.txt").write_str("skip\n").unwrap();
+    temp
+}
+
+const TREE: &str = "\
+├── docs/
+│   └── guide.md
+└── src/
+    ├── app/
+    │   └── run.rs
+    └── main.rs
+";
+
+#[test]
+fn tree_is_prepended_before_contents() {
+    let temp = fixture();
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["src/", "docs/", "--no-clipboard", "--tree"]);
+    let out = cmd.assert().success().get_output().stdout.clone();
+    let s = String::from_utf8(out).unwrap();
+    assert!(s.starts_with(TREE));
+    assert!(s.contains("fn main() {}"));
+    assert!(!s.contains("other"));
+    temp.close().unwrap();
+}
+
+#[test]
+fn tree_only_emits_no_contents() {
+    let temp = fixture();
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["src/", "docs/", "--no-clipboard", "--tree-only"]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(TREE))
+        .stdout(predicate::str::contains("fn main").not());
+    temp.close().unwrap();
+}
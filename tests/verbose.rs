@@ -0,0 +1,25 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use std::process::Command;
+
+#[test]
+fn very_verbose_logs_match_decisions() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/main.rs").write_str("fn main() {}\n").unwrap();
+    temp.child("src/skip.rs").write_str("// skip\n").unwrap();
+    temp.child(".env").write_str("SECRET=1\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(&temp).args(["src", "~**/skip.rs", "--no-clipboard", "--tokenizer", "approx", "-vv"]);
+    let out = cmd.assert().success().get_output().clone();
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    let line = |name: &str| stderr.lines().find(|l| l.contains("match decision") && l.contains(name)).unwrap_or_else(|| panic!("no decision for {} in:\n{}", name, stderr)).to_string();
+    assert!(line("src/main.rs").contains("set=\"visible\" included=true"));
+    assert!(line("src/skip.rs").contains("set=\"exclude\" included=false"));
+    assert!(line(".env").contains("set=\"hidden\" included=false"));
+
+    let mut quiet = Command::cargo_bin("lf").unwrap();
+    quiet.current_dir(&temp).args(["src", "--no-clipboard", "--tokenizer", "approx"]);
+    assert!(!String::from_utf8(quiet.assert().success().get_output().stderr.clone()).unwrap().contains("match decision"));
+    temp.close().unwrap();
+}
@@ -0,0 +1,54 @@
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn lf(temp: &assert_fs::TempDir, args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = Command::cargo_bin("lf").unwrap();
+    cmd.current_dir(temp).args(args).arg("--no-clipboard").arg("--tokenizer").arg("approx");
+    cmd.assert()
+}
+
+fn stdout(a: assert_cmd::assert::Assert) -> String {
+    String::from_utf8(a.success().get_output().stdout.clone()).unwrap()
+}
+
+fn tokens(out: &str) -> usize {
+    out.lines().find_map(|l| l.strip_prefix("Tokens (approx): ")).unwrap().parse().unwrap()
+}
+
+#[test]
+fn prefix_and_suffix_wrap_output_in_order() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("src/main.rs").write_str("fn main() {}\n").unwrap();
+    temp.child("question.txt").write_str("What does main do?\n").unwrap();
+
+    let out = stdout(lf(&temp, &["src/*.rs", "--format", "markdown", "--toc", "--prefix", "You are reviewing:", "--prefix", "Be brief.", "--suffix", "@question.txt"]));
+    assert!(out.starts_with("You are reviewing:\n\nBe brief.\n\n"), "{}", out);
+    let toc = out.find("src/main.rs").unwrap();
+    let file = out.find("fn main() {}").unwrap();
+    let question = out.find("What does main do?").unwrap();
+    assert!(toc < file && file < question, "{}", out);
+    assert!(out.contains("```\n\nWhat does main do?\nFiles: 1   Size: "), "{}", out);
+    temp.close().unwrap();
+}
+
+#[test]
+fn prefix_counts_toward_tokens() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("hello\n").unwrap();
+
+    let plain = tokens(&stdout(lf(&temp, &["a.txt"])));
+    let wrapped = tokens(&stdout(lf(&temp, &["a.txt", "--prefix", "Please review these files carefully and report every bug you find."])));
+    assert!(wrapped > plain + 5, "{} vs {}", wrapped, plain);
+    temp.close().unwrap();
+}
+
+#[test]
+fn missing_prefix_file_is_an_invalid_option() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("a.txt").write_str("hello\n").unwrap();
+
+    lf(&temp, &["a.txt", "--prefix", "@missing.txt"]).code(3).stderr(predicate::str::contains("cannot read --prefix file 'missing.txt'"));
+    temp.close().unwrap();
+}